@@ -0,0 +1,842 @@
+use crate::audit;
+use crate::auth::{self, ApiToken, Scope};
+use crate::backup;
+use crate::bedrock_command::{BedrockCommand, TargetSelector};
+use crate::command::validate_text;
+use crate::config::Config;
+use crate::config_manager::{AllowlistEntry, Permission};
+use crate::daemon;
+use crate::events::{ConsoleWatcher, EventBus};
+use crate::instances::{self, Instance};
+use crate::metrics;
+use crate::players::{PlayerDatabase, PlayerSort};
+use crate::properties;
+use crate::service_lock::Claim;
+use crate::websocket;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+pub const DEFAULT_BIND: &str = "127.0.0.1:8080";
+
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn not_found() -> Self {
+        ApiError {
+            status: StatusCode::NOT_FOUND,
+            message: "Rota não encontrada".to_string(),
+        }
+    }
+
+    fn unauthorized() -> Self {
+        ApiError {
+            status: StatusCode::UNAUTHORIZED,
+            message: "Token ausente ou inválido".to_string(),
+        }
+    }
+
+    fn forbidden(scope: Scope) -> Self {
+        ApiError {
+            status: StatusCode::FORBIDDEN,
+            message: format!("Esta rota requer um token com escopo {}", scope),
+        }
+    }
+
+    fn conflict(message: &str) -> Self {
+        ApiError {
+            status: StatusCode::CONFLICT,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        ApiError {
+            status: StatusCode::BAD_REQUEST,
+            message,
+        }
+    }
+}
+
+type ApiResult = Result<Value, ApiError>;
+
+#[derive(Deserialize)]
+struct CommandRequest {
+    command: String,
+}
+
+#[derive(Deserialize)]
+struct KickRequest {
+    name: String,
+    #[serde(default)]
+    reason: String,
+}
+
+#[derive(Deserialize)]
+struct SayRequest {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct AllowlistRequest {
+    name: String,
+    #[serde(default)]
+    ignores_player_limit: bool,
+}
+
+// API HTTP de controle de uma instância. Roda em primeiro plano até o
+// processo ser encerrado.
+pub fn run(instance: Instance, bind: &str) -> Result<(), String> {
+    let addr: SocketAddr = bind
+        .parse()
+        .map_err(|_| format!("Endereço inválido: {}", bind))?;
+    if auth::list_tokens()?.is_empty() {
+        return Err(
+            "Nenhum token de API criado; use `token create <nome> <escopo>` antes de iniciar a API"
+                .to_string(),
+        );
+    }
+    if !addr.ip().is_loopback() {
+        println!(
+            "Atenção: a API em {} aceita conexões de outras máquinas da rede",
+            addr
+        );
+    }
+
+    // Eventos do console para os clientes do WebSocket
+    let events = EventBus::new();
+    let _watcher = ConsoleWatcher::spawn(&instance.work_dir, events.clone());
+    // Sem o daemon, a própria API roda os serviços da instância enquanto
+    // estiver no ar, como o menu
+    instances::spawn_services(&events, &instance, Claim::WhenFree);
+
+    let runtime =
+        tokio::runtime::Runtime::new().map_err(|e| format!("Erro ao iniciar a API: {}", e))?;
+    runtime.block_on(async move {
+        let make_service = make_service_fn(move |_| {
+            let instance = instance.clone();
+            let events = events.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle(request, instance.clone(), events.clone())
+                }))
+            }
+        });
+
+        let server = hyper::Server::try_bind(&addr)
+            .map_err(|e| format!("Erro ao abrir {}: {}", addr, e))?
+            .serve(make_service);
+        println!("API HTTP ouvindo em http://{}", addr);
+        server.await.map_err(|e| format!("Erro na API: {}", e))
+    })
+}
+
+async fn handle(
+    request: Request<Body>,
+    instance: Instance,
+    events: EventBus,
+) -> Result<Response<Body>, Infallible> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    // Decodificado uma vez só: a verificação de escopo e a rota precisam ver
+    // os mesmos segmentos
+    let segments = path_segments(&path);
+
+    let token = match authorize(&request, required_scope(&method, &segments)) {
+        Ok(token) => token,
+        Err(error) => return Ok(error_response(error)),
+    };
+
+    if method == Method::GET && path == "/api/console" {
+        if !websocket::is_upgrade_request(&request) {
+            let error = ApiError::from("Use um cliente WebSocket nesta rota".to_string());
+            return Ok(error_response(error));
+        }
+        return Ok(websocket::accept(request, instance, events, token)
+            .unwrap_or_else(|message| error_response(ApiError::from(message))));
+    }
+
+    if method == Method::GET && path == "/metrics" {
+        let text = tokio::task::spawn_blocking(move || metrics::render(&instance))
+            .await
+            .unwrap_or_default();
+        return Ok(Response::builder()
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .body(Body::from(text))
+            .expect("Resposta HTTP inválida"));
+    }
+
+    let body = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) => body,
+        Err(e) => {
+            let error = ApiError::from(format!("Erro ao ler requisição: {}", e));
+            return Ok(error_response(error));
+        }
+    };
+
+    // As operações usam screen, sysinfo e arquivos, então rodam fora do executor
+    // As ações feitas pela rota ficam na auditoria em nome do token
+    let result = tokio::task::spawn_blocking(move || {
+        audit::with_actor(&format!("api:{}", token.name), || {
+            let segments: Vec<&str> = segments.iter().map(|s| s.as_str()).collect();
+            route(&method, &segments, &body, &instance)
+        })
+    })
+    .await
+    .unwrap_or_else(|e| {
+        Err(ApiError {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: e.to_string(),
+        })
+    });
+
+    Ok(match result {
+        Ok(value) => json_response(StatusCode::OK, &value),
+        Err(error) => error_response(error),
+    })
+}
+
+// O token vem no cabeçalho `Authorization: Bearer <token>` ou, para clientes
+// WebSocket de navegador que não enviam cabeçalhos, em `?token=<token>`
+fn authorize(request: &Request<Body>, required: Scope) -> Result<ApiToken, ApiError> {
+    // Navegadores não enviam cabeçalhos na abertura do WebSocket, então só ali
+    // o token pode vir na URL (que acaba em logs e históricos)
+    let websocket =
+        request.uri().path() == "/api/console" && websocket::is_upgrade_request(request);
+    let token = request
+        .headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|value| value.trim().to_string())
+        .or_else(|| {
+            websocket
+                .then(|| websocket::query_param(request, "token"))
+                .flatten()
+        })
+        .ok_or_else(ApiError::unauthorized)?;
+
+    let token = auth::authenticate(&token)
+        .map_err(|message| ApiError {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message,
+        })?
+        .ok_or_else(ApiError::unauthorized)?;
+    if !token.allows(required) {
+        return Err(ApiError::forbidden(required));
+    }
+    Ok(token)
+}
+
+// Escopo mínimo de cada rota. Consultas exigem apenas leitura; rotas
+// desconhecidas exigem admin antes de responder 404.
+fn required_scope(method: &Method, segments: &[String]) -> Scope {
+    let segments: Vec<&str> = segments.iter().map(|s| s.as_str()).collect();
+    match (method, segments.as_slice()) {
+        (&Method::GET, _) => Scope::Read,
+        (&Method::POST, ["api", "kick" | "say" | "allowlist"])
+        | (&Method::DELETE, ["api", "allowlist", _]) => Scope::Moderator,
+        (&Method::POST, ["api", "update"]) | (&Method::POST, ["api", "backups", _, "restore"]) => {
+            Scope::Owner
+        }
+        _ => Scope::Admin,
+    }
+}
+
+fn json_response(status: StatusCode, value: &Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(value.to_string()))
+        .expect("Resposta HTTP inválida")
+}
+
+fn error_response(error: ApiError) -> Response<Body> {
+    json_response(error.status, &json!({ "error": error.message }))
+}
+
+// Segmentos do caminho com %XX decodificado
+fn path_segments(path: &str) -> Vec<String> {
+    path.trim_matches('/')
+        .split('/')
+        .map(percent_decode)
+        .collect()
+}
+
+fn route(method: &Method, segments: &[&str], body: &[u8], instance: &Instance) -> ApiResult {
+    match (method, segments) {
+        (&Method::GET, ["api", "status"]) => status(instance),
+        (&Method::POST, ["api", "start"]) => {
+            daemon::start_instance(instance)?;
+            status(instance)
+        }
+        (&Method::POST, ["api", "stop"]) => {
+            daemon::stop_instance(instance)?;
+            status(instance)
+        }
+        (&Method::POST, ["api", "restart"]) => {
+            if instance.is_running() {
+                daemon::stop_instance(instance)?;
+            }
+            daemon::start_instance(instance)?;
+            status(instance)
+        }
+        (&Method::POST, ["api", "command"]) => {
+            let request: CommandRequest = parse_body(body)?;
+            ensure_running(instance)?;
+            let result = instance
+                .admin()
+                .execute_and_capture(&request.command, Duration::from_secs(1));
+            audit::record(
+                &instance.work_dir,
+                "command",
+                json!({ "command": request.command }),
+                &result,
+            );
+            Ok(json!({ "output": result? }))
+        }
+        (&Method::POST, ["api", "kick"]) => {
+            let request: KickRequest = parse_body(body)?;
+            ensure_running(instance)?;
+            instance
+                .admin()
+                .kick_player(&request.name, &request.reason)?;
+            Ok(json!({ "kicked": request.name }))
+        }
+        (&Method::POST, ["api", "say"]) => {
+            let request: SayRequest = parse_body(body)?;
+            ensure_running(instance)?;
+            instance.admin().say(&request.message)?;
+            Ok(json!({ "sent": request.message }))
+        }
+        (&Method::POST, ["api", "update"]) => {
+            if instance.is_running() {
+                return Err(ApiError::conflict(
+                    "Pare o servidor antes de instalar ou atualizar",
+                ));
+            }
+            Config::new(instance.work_dir.clone()).install()?;
+            status(instance)
+        }
+        (&Method::GET, ["api", "players"]) => {
+            let database = PlayerDatabase::load(&instance.work_dir)?;
+            let online: Vec<&str> = database
+                .online_players()
+                .into_iter()
+                .map(|record| record.name.as_str())
+                .collect();
+            Ok(json!({ "online": online, "players": database.sorted(PlayerSort::Playtime) }))
+        }
+        (&Method::GET, ["api", "allowlist"]) => {
+            Ok(json!(instance.config_manager().read_allowlist()?))
+        }
+        (&Method::POST, ["api", "allowlist"]) => {
+            let request: AllowlistRequest = parse_body(body)?;
+            allowlist_add(instance, request)?;
+            Ok(json!(instance.config_manager().read_allowlist()?))
+        }
+        (&Method::DELETE, ["api", "allowlist", name]) => {
+            allowlist_remove(instance, name)?;
+            Ok(json!(instance.config_manager().read_allowlist()?))
+        }
+        (&Method::GET, ["api", "permissions"]) => {
+            Ok(json!(instance.config_manager().read_permissions()?))
+        }
+        (&Method::PUT, ["api", "permissions"]) => {
+            let permission: Permission = parse_body(body)?;
+            set_permission(instance, permission)?;
+            Ok(json!(instance.config_manager().read_permissions()?))
+        }
+        (&Method::GET, ["api", "backups"]) => Ok(json!(backup::list_backups(&instance.work_dir)?)),
+        (&Method::POST, ["api", "backups"]) => Ok(json!(backup::create_backup(instance)?)),
+        (&Method::POST, ["api", "backups", name, "restore"]) => {
+            if instance.is_running() {
+                return Err(ApiError::conflict(
+                    "Pare o servidor antes de restaurar um backup",
+                ));
+            }
+            backup::restore_backup(instance, name)?;
+            Ok(json!({ "restored": name }))
+        }
+        (&Method::GET, ["api", "properties"]) => {
+            Ok(json!(instance.config_manager().read_all_properties()?))
+        }
+        (&Method::PUT, ["api", "properties"]) => {
+            let updates: BTreeMap<String, String> = parse_body(body)?;
+            let restart_required = set_properties(instance, &updates)?;
+            Ok(json!({
+                "properties": instance.config_manager().read_all_properties()?,
+                "restart_required": restart_required,
+            }))
+        }
+        _ => Err(ApiError::not_found()),
+    }
+}
+
+fn status(instance: &Instance) -> ApiResult {
+    let lock = instance.running_server();
+    let (port, port_v6) = instance.ports();
+    let online: Vec<String> = PlayerDatabase::load(&instance.work_dir)
+        .map(|database| {
+            database
+                .online_players()
+                .into_iter()
+                .map(|record| record.name.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(json!({
+        "instance": instance.name,
+        "running": lock.is_some(),
+        "pid": lock.as_ref().map(|lock| lock.pid),
+        "started_at": lock.as_ref().map(|lock| lock.started_at),
+        "version": lock.as_ref().and_then(|lock| lock.version.clone()),
+        "port": port,
+        "port_v6": port_v6,
+        "online_players": online,
+    }))
+}
+
+fn ensure_running(instance: &Instance) -> Result<(), ApiError> {
+    if instance.is_running() {
+        Ok(())
+    } else {
+        Err(ApiError::conflict("O servidor não está em execução"))
+    }
+}
+
+fn parse_body<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<T, ApiError> {
+    serde_json::from_slice(body).map_err(|e| ApiError::from(format!("JSON inválido: {}", e)))
+}
+
+// Com o servidor rodando, o próprio servidor atualiza o allowlist.json; parado,
+// o arquivo é editado diretamente
+fn allowlist_add(instance: &Instance, request: AllowlistRequest) -> Result<(), String> {
+    let target = TargetSelector::player(&request.name)?;
+    if instance.is_running() {
+        return instance.admin().run(&BedrockCommand::WhitelistAdd(target));
+    }
+
+    let config = instance.config_manager();
+    let mut entries = config.read_allowlist()?;
+    if entries
+        .iter()
+        .any(|entry| entry.name.eq_ignore_ascii_case(&request.name))
+    {
+        return Ok(());
+    }
+    entries.push(AllowlistEntry {
+        name: request.name,
+        xuid: String::new(),
+        ignores_player_limit: request.ignores_player_limit,
+    });
+    config.save_allowlist(&entries)
+}
+
+fn allowlist_remove(instance: &Instance, name: &str) -> Result<(), String> {
+    let target = TargetSelector::player(name)?;
+    if instance.is_running() {
+        return instance
+            .admin()
+            .run(&BedrockCommand::WhitelistRemove(target));
+    }
+
+    let config = instance.config_manager();
+    let mut entries = config.read_allowlist()?;
+    entries.retain(|entry| !entry.name.eq_ignore_ascii_case(name));
+    config.save_allowlist(&entries)
+}
+
+fn set_permission(instance: &Instance, permission: Permission) -> Result<(), String> {
+    if !["visitor", "member", "operator"].contains(&permission.permission.as_str()) {
+        return Err(format!(
+            "Permissão inválida: '{}' (use visitor, member ou operator)",
+            permission.permission
+        ));
+    }
+    if permission.xuid.is_empty() || !permission.xuid.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("XUID inválido: '{}'", permission.xuid));
+    }
+
+    let config = instance.config_manager();
+    let mut permissions = config.read_permissions()?;
+    permissions.retain(|existing| existing.xuid != permission.xuid);
+    permissions.push(permission);
+    config.save_permissions(&permissions)?;
+
+    if instance.is_running() {
+        instance.admin().send_command("permission reload")?;
+    }
+    Ok(())
+}
+
+// Devolve as propriedades alteradas que só valem depois de reiniciar
+fn set_properties(
+    instance: &Instance,
+    updates: &BTreeMap<String, String>,
+) -> Result<Vec<String>, String> {
+    for (key, value) in updates {
+        validate_text(key)?;
+        validate_text(value)?;
+        properties::validate(key, value)?;
+    }
+
+    let updates: Vec<(&str, String)> = updates
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.clone()))
+        .collect();
+    let changed = instance.config_manager().set_properties(&updates)?;
+    properties::apply_live(instance, &changed)
+}
+
+// Decodifica %XX nos segmentos do caminho (ex: nomes de jogador com espaço)
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instance_state::InstanceState;
+    use crate::instances::InstanceRegistry;
+    use crate::test_util::{self, TempCurrentDir};
+    use std::fs;
+
+    // Instância padrão num diretório atual temporário, já com os arquivos de
+    // configuração criados
+    fn setup(name: &str) -> (TempCurrentDir, Instance) {
+        let dir = test_util::enter_temp_dir(name);
+        let instance = InstanceRegistry::load()
+            .unwrap()
+            .get("default")
+            .unwrap()
+            .clone();
+        fs::create_dir_all(&instance.work_dir).unwrap();
+        instance.config_manager().initialize_configs().unwrap();
+        (dir, instance)
+    }
+
+    fn call(instance: &Instance, method: Method, path: &str, body: &str) -> ApiResult {
+        let segments = path_segments(path);
+        let segments: Vec<&str> = segments.iter().map(|s| s.as_str()).collect();
+        route(&method, &segments, body.as_bytes(), instance)
+    }
+
+    fn status_of(result: ApiResult) -> StatusCode {
+        match result {
+            Ok(_) => StatusCode::OK,
+            Err(error) => error.status,
+        }
+    }
+
+    #[test]
+    fn status_of_stopped_instance() {
+        let (_dir, instance) = setup("api-status");
+        let status = call(&instance, Method::GET, "/api/status", "").unwrap();
+        assert_eq!(status["instance"], "default");
+        assert_eq!(status["running"], false);
+        assert_eq!(status["port"], 19132);
+        assert_eq!(status["port_v6"], 19133);
+    }
+
+    // Um `sleep` copiado como bedrock_server faz as vezes do servidor: o
+    // gerenciador o encontra pelo executável da instância
+    #[cfg(unix)]
+    #[test]
+    fn status_reports_fake_server_binary() {
+        let (_dir, instance) = setup("api-fake-server");
+        let binary = instance.work_dir.join("bedrock_server");
+        fs::copy("/bin/sleep", &binary).unwrap();
+        let mut child = std::process::Command::new(&binary)
+            .arg("30")
+            .spawn()
+            .unwrap();
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while !instance.is_running() && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        let status = call(&instance, Method::GET, "/api/status", "");
+        let restore = call(
+            &instance,
+            Method::POST,
+            "/api/backups/worlds-x.zip/restore",
+            "",
+        );
+        let update = call(&instance, Method::POST, "/api/update", "");
+        let _ = child.kill();
+        let _ = child.wait();
+
+        let status = status.unwrap();
+        assert_eq!(status["running"], true);
+        assert_eq!(status["pid"], child.id());
+        assert_eq!(status_of(restore), StatusCode::CONFLICT);
+        assert_eq!(status_of(update), StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn commands_require_running_server() {
+        let (_dir, instance) = setup("api-not-running");
+        for (path, body) in [
+            ("/api/command", r#"{"command":"list"}"#),
+            ("/api/kick", r#"{"name":"Steve"}"#),
+            ("/api/say", r#"{"message":"oi"}"#),
+        ] {
+            let result = call(&instance, Method::POST, path, body);
+            assert_eq!(status_of(result), StatusCode::CONFLICT, "{}", path);
+        }
+        let result = call(&instance, Method::POST, "/api/say", "{");
+        assert_eq!(status_of(result), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn unknown_routes_are_not_found() {
+        let (_dir, instance) = setup("api-not-found");
+        let result = call(&instance, Method::GET, "/api/nada", "");
+        assert_eq!(status_of(result), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn properties_are_validated() {
+        let (_dir, instance) = setup("api-properties");
+        let properties = call(
+            &instance,
+            Method::PUT,
+            "/api/properties",
+            r#"{"max-players":"20"}"#,
+        )
+        .unwrap();
+        assert_eq!(properties["properties"]["max-players"], "20");
+        // Com o servidor parado tudo vale no próximo início
+        assert_eq!(properties["restart_required"], json!([]));
+
+        let properties = call(
+            &instance,
+            Method::PUT,
+            "/api/properties",
+            r#"{"difficulty":"hard","max-players":"30"}"#,
+        )
+        .unwrap();
+        assert_eq!(properties["properties"]["difficulty"], "hard");
+        assert_eq!(properties["restart_required"], json!([]));
+
+        for body in [
+            r#"{"max-players":"muitos"}"#,
+            r#"{"difficulty":"impossible"}"#,
+            r#"{"motd":"oi\nstop"}"#,
+        ] {
+            let result = call(&instance, Method::PUT, "/api/properties", body);
+            assert_eq!(status_of(result), StatusCode::BAD_REQUEST, "{}", body);
+        }
+        assert_eq!(
+            instance
+                .config_manager()
+                .read_property("max-players")
+                .as_deref(),
+            Some("30")
+        );
+    }
+
+    #[test]
+    fn allowlist_is_edited_while_stopped() {
+        let (_dir, instance) = setup("api-allowlist");
+        let list = call(
+            &instance,
+            Method::POST,
+            "/api/allowlist",
+            r#"{"name":"Steve Alex"}"#,
+        )
+        .unwrap();
+        assert_eq!(list[0]["name"], "Steve Alex");
+
+        let list = call(&instance, Method::DELETE, "/api/allowlist/Steve%20Alex", "").unwrap();
+        assert_eq!(list, json!([]));
+
+        let result = call(
+            &instance,
+            Method::POST,
+            "/api/allowlist",
+            r#"{"name":"a\"b"}"#,
+        );
+        assert_eq!(status_of(result), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn permissions_are_validated() {
+        let (_dir, instance) = setup("api-permissions");
+        let permissions = call(
+            &instance,
+            Method::PUT,
+            "/api/permissions",
+            r#"{"permission":"operator","xuid":"2535400000000000"}"#,
+        )
+        .unwrap();
+        assert_eq!(permissions[0]["permission"], "operator");
+
+        for body in [
+            r#"{"permission":"root","xuid":"1"}"#,
+            r#"{"permission":"member","xuid":"abc"}"#,
+        ] {
+            let result = call(&instance, Method::PUT, "/api/permissions", body);
+            assert_eq!(status_of(result), StatusCode::BAD_REQUEST, "{}", body);
+        }
+    }
+
+    #[test]
+    fn backups_are_created_listed_and_restored() {
+        let (_dir, instance) = setup("api-backups");
+        let world = instance.work_dir.join("worlds").join("Bedrock level");
+        fs::create_dir_all(&world).unwrap();
+        fs::write(world.join("level.dat"), "original").unwrap();
+
+        let first = call(&instance, Method::POST, "/api/backups", "").unwrap();
+        let second = call(&instance, Method::POST, "/api/backups", "").unwrap();
+        assert_ne!(first["name"], second["name"]);
+        let list = call(&instance, Method::GET, "/api/backups", "").unwrap();
+        assert_eq!(list.as_array().unwrap().len(), 2);
+
+        // A pausa do watchdog impede criar backups, mas não restaurar
+        InstanceState::update(&instance.work_dir, |state| {
+            state.backups_paused = Some("disco cheio".to_string())
+        });
+        let result = call(&instance, Method::POST, "/api/backups", "");
+        assert_eq!(status_of(result), StatusCode::BAD_REQUEST);
+
+        fs::write(world.join("level.dat"), "alterado").unwrap();
+        let path = format!("/api/backups/{}/restore", first["name"].as_str().unwrap());
+        call(&instance, Method::POST, &path, "").unwrap();
+        assert_eq!(
+            fs::read_to_string(world.join("level.dat")).unwrap(),
+            "original"
+        );
+
+        let result = call(
+            &instance,
+            Method::POST,
+            "/api/backups/..%2Fx.zip/restore",
+            "",
+        );
+        assert_eq!(status_of(result), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn routes_require_minimum_scope() {
+        assert_eq!(
+            required_scope(&Method::GET, &path_segments("/api/status")),
+            Scope::Read
+        );
+        assert_eq!(
+            required_scope(&Method::POST, &path_segments("/api/kick")),
+            Scope::Moderator
+        );
+        assert_eq!(
+            required_scope(&Method::DELETE, &path_segments("/api/allowlist/Steve")),
+            Scope::Moderator
+        );
+        assert_eq!(
+            required_scope(&Method::POST, &path_segments("/api/start")),
+            Scope::Admin
+        );
+        assert_eq!(
+            required_scope(&Method::PUT, &path_segments("/api/properties")),
+            Scope::Admin
+        );
+        assert_eq!(
+            required_scope(&Method::POST, &path_segments("/api/update")),
+            Scope::Owner
+        );
+        assert_eq!(
+            required_scope(&Method::POST, &path_segments("/api/backups/x.zip/restore")),
+            Scope::Owner
+        );
+
+        // Caminhos com %XX são decodificados antes da verificação, como na rota
+        for path in [
+            "/api/upd%61te",
+            "/api/backups/x.zip/re%73tore",
+            "/%61pi/update",
+        ] {
+            assert_eq!(
+                required_scope(&Method::POST, &path_segments(path)),
+                Scope::Owner,
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn query_token_only_accepted_on_websocket_upgrade() {
+        let (_dir, _instance) = setup("api-authorize");
+        let token = auth::create_token("painel", Scope::Admin).unwrap();
+        let request = |uri: String, upgrade: bool, header: bool| {
+            let mut builder = Request::builder().uri(uri);
+            if upgrade {
+                builder = builder.header("upgrade", "websocket");
+            }
+            if header {
+                builder = builder.header("authorization", format!("Bearer {}", token));
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        let ok = authorize(
+            &request("/api/status".to_string(), false, true),
+            Scope::Read,
+        );
+        assert_eq!(ok.unwrap().name, "painel");
+
+        let query = format!("?token={}", token);
+        let upgrade = request(format!("/api/console{}", query), true, false);
+        assert!(authorize(&upgrade, Scope::Read).is_ok());
+
+        for (uri, upgrade) in [
+            (format!("/api/status{}", query), false),
+            (format!("/api/console{}", query), false),
+            (format!("/api/status{}", query), true),
+        ] {
+            let result = authorize(&request(uri, upgrade, false), Scope::Read);
+            assert_eq!(
+                result.err().map(|e| e.status),
+                Some(StatusCode::UNAUTHORIZED)
+            );
+        }
+
+        let result = authorize(
+            &request("/api/status".to_string(), false, true),
+            Scope::Owner,
+        );
+        assert_eq!(result.err().map(|e| e.status), Some(StatusCode::FORBIDDEN));
+    }
+}
@@ -0,0 +1,291 @@
+use crate::ban_list::parse_duration;
+use crate::instances::InstanceRegistry;
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// Registro de uma ação administrativa, uma linha JSON por entrada em
+// audit.jsonl. O arquivo só recebe novas linhas; nada é reescrito.
+#[derive(Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub actor: String,
+    pub instance: String,
+    pub action: String,
+    pub args: Value,
+    pub result: String,
+}
+
+// Quem está agindo: definido uma vez pelo processo (menu, CLI, daemon) e
+// substituído por thread quando a ação vem de outra origem (tokens da API,
+// serviços automáticos)
+static DEFAULT_ACTOR: Mutex<String> = Mutex::new(String::new());
+
+// Nomes de instância já encontrados, por diretório
+static INSTANCE_NAMES: Mutex<BTreeMap<PathBuf, String>> = Mutex::new(BTreeMap::new());
+
+thread_local! {
+    static THREAD_ACTOR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// Ex: "menu:maria", "cli:root"
+pub fn set_default_actor(origin: &str) {
+    let user = env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_default();
+    let actor = if user.is_empty() {
+        origin.to_string()
+    } else {
+        format!("{}:{}", origin, user)
+    };
+    *DEFAULT_ACTOR.lock().unwrap() = actor;
+}
+
+// Executa `f` com as ações registradas em nome de `actor` nesta thread
+pub fn with_actor<R>(actor: &str, f: impl FnOnce() -> R) -> R {
+    let previous = THREAD_ACTOR.with(|cell| cell.replace(Some(actor.to_string())));
+    let result = f();
+    THREAD_ACTOR.with(|cell| *cell.borrow_mut() = previous);
+    result
+}
+
+pub fn current_actor() -> String {
+    THREAD_ACTOR
+        .with(|cell| cell.borrow().clone())
+        .unwrap_or_else(|| {
+            let actor = DEFAULT_ACTOR.lock().unwrap().clone();
+            if actor.is_empty() {
+                "desconhecido".to_string()
+            } else {
+                actor
+            }
+        })
+}
+
+fn audit_path() -> Result<PathBuf, String> {
+    Ok(env::current_dir()
+        .map_err(|e| format!("Erro ao obter diretório atual: {}", e))?
+        .join("audit.jsonl"))
+}
+
+// Nome da instância dona do diretório, para as entradas serem filtráveis.
+// Os nomes encontrados no registro ficam guardados, já que não mudam.
+pub fn instance_name(work_dir: &Path) -> String {
+    if let Some(name) = INSTANCE_NAMES.lock().unwrap().get(work_dir) {
+        return name.clone();
+    }
+    let registered = InstanceRegistry::load().ok().and_then(|registry| {
+        registry
+            .list()
+            .into_iter()
+            .find(|instance| instance.work_dir == work_dir)
+            .map(|instance| instance.name.clone())
+    });
+    if let Some(name) = &registered {
+        INSTANCE_NAMES
+            .lock()
+            .unwrap()
+            .insert(work_dir.to_path_buf(), name.clone());
+    }
+    registered
+        .or_else(|| {
+            // Instância recém-criada, ainda fora do instances.json
+            work_dir
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .unwrap_or_else(|| work_dir.display().to_string())
+}
+
+// Acrescenta a entrada ao log de auditoria. Uma falha ao gravar é apenas
+// avisada para não desfazer a ação que já foi executada.
+pub fn record<T, E: ToString>(work_dir: &Path, action: &str, args: Value, result: &Result<T, E>) {
+    let entry = AuditEntry {
+        timestamp: Utc::now(),
+        actor: current_actor(),
+        instance: instance_name(work_dir),
+        action: action.to_string(),
+        args,
+        result: match result {
+            Ok(_) => "ok".to_string(),
+            Err(e) => format!("erro: {}", e.to_string()),
+        },
+    };
+    if let Err(e) = append(&entry) {
+        println!("Erro ao gravar log de auditoria: {}", e);
+    }
+}
+
+fn append(entry: &AuditEntry) -> Result<(), String> {
+    let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(audit_path()?)
+        .map_err(|e| e.to_string())?;
+    writeln!(file, "{}", line).map_err(|e| e.to_string())
+}
+
+// Filtros do comando `audit`. Ator e ação aceitam o prefixo antes de ':' ou
+// '.', então `--actor api` encontra todos os tokens e `--action backup`
+// encontra backup.create e backup.restore.
+#[derive(Default)]
+pub struct AuditQuery {
+    pub since: Option<DateTime<Utc>>,
+    pub actor: Option<String>,
+    pub action: Option<String>,
+}
+
+fn matches_prefix(value: &str, filter: &str, separator: char) -> bool {
+    value == filter
+        || value
+            .strip_prefix(filter)
+            .is_some_and(|rest| rest.starts_with(separator))
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.since.is_none_or(|since| entry.timestamp >= since)
+            && self
+                .actor
+                .as_deref()
+                .is_none_or(|actor| matches_prefix(&entry.actor, actor, ':'))
+            && self
+                .action
+                .as_deref()
+                .is_none_or(|action| matches_prefix(&entry.action, action, '.'))
+    }
+}
+
+// `--since` aceita uma duração (30m, 12h, 7d, 2w) ou uma data AAAA-MM-DD
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+        return Local
+            .from_local_datetime(&midnight)
+            .single()
+            .map(|date| date.with_timezone(&Utc))
+            .ok_or_else(|| format!("Data inválida: {}", value));
+    }
+    Ok(Utc::now() - parse_duration(value)?)
+}
+
+pub fn query(filter: &AuditQuery) -> Result<Vec<AuditEntry>, String> {
+    let path = audit_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Erro ao ler audit.jsonl: {}", e))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+        .filter(|entry| filter.matches(entry))
+        .collect())
+}
+
+pub fn print(entries: &[AuditEntry]) {
+    println!("\n=== Auditoria ===");
+    if entries.is_empty() {
+        println!("Nenhuma ação encontrada.");
+    }
+    for entry in entries {
+        println!(
+            "{}  {:<16} [{}] {} {} -> {}",
+            entry
+                .timestamp
+                .with_timezone(&Local)
+                .format("%d/%m/%Y %H:%M:%S"),
+            entry.actor,
+            entry.instance,
+            entry.action,
+            entry.args,
+            entry.result
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use chrono::Duration;
+    use serde_json::json;
+
+    fn entry(hours_ago: i64, actor: &str, action: &str) -> AuditEntry {
+        AuditEntry {
+            timestamp: Utc::now() - Duration::hours(hours_ago),
+            actor: actor.to_string(),
+            instance: "default".to_string(),
+            action: action.to_string(),
+            args: json!({}),
+            result: "ok".to_string(),
+        }
+    }
+
+    fn actions(filter: AuditQuery) -> Vec<String> {
+        query(&filter)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.action)
+            .collect()
+    }
+
+    #[test]
+    fn query_filters_by_since_actor_and_action() {
+        let _dir = test_util::enter_temp_dir("audit");
+        for entry in [
+            entry(48, "cli:root", "backup.create"),
+            entry(2, "api:painel", "backup.restore"),
+            entry(1, "apicultor", "backups"),
+            entry(0, "menu:maria", "ban"),
+        ] {
+            append(&entry).unwrap();
+        }
+        // Linhas ilegíveis são ignoradas
+        fs::OpenOptions::new()
+            .append(true)
+            .open("audit.jsonl")
+            .and_then(|mut file| writeln!(file, "{{quebrado"))
+            .unwrap();
+
+        assert_eq!(actions(AuditQuery::default()).len(), 4);
+        assert_eq!(
+            actions(AuditQuery {
+                since: Some(parse_since("1d").unwrap()),
+                ..AuditQuery::default()
+            }),
+            ["backup.restore", "backups", "ban"]
+        );
+        assert_eq!(
+            actions(AuditQuery {
+                actor: Some("api".to_string()),
+                ..AuditQuery::default()
+            }),
+            ["backup.restore"]
+        );
+        assert_eq!(
+            actions(AuditQuery {
+                action: Some("backup".to_string()),
+                ..AuditQuery::default()
+            }),
+            ["backup.create", "backup.restore"]
+        );
+        assert_eq!(
+            actions(AuditQuery {
+                since: Some(parse_since("1d").unwrap()),
+                action: Some("backup".to_string()),
+                ..AuditQuery::default()
+            }),
+            ["backup.restore"]
+        );
+        assert!(parse_since("2024-13-01").is_err());
+    }
+}
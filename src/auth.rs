@@ -0,0 +1,154 @@
+use crate::manager_config::ManagerConfig;
+use chrono::{DateTime, Local, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+const TOKEN_PREFIX: &str = "mbs_";
+
+// Escopos em ordem crescente; cada um inclui as permissões dos anteriores
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    // Status, jogadores, backups e configurações (somente leitura)
+    Read,
+    // Expulsar jogadores, allowlist e mensagens no chat
+    Moderator,
+    // Iniciar/parar, comandos, configurações e backups
+    Admin,
+    // Instalar/atualizar o servidor e restaurar backups
+    Owner,
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Scope::Read => "read",
+            Scope::Moderator => "moderator",
+            Scope::Admin => "admin",
+            Scope::Owner => "owner",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "read" => Ok(Scope::Read),
+            "moderator" => Ok(Scope::Moderator),
+            "admin" => Ok(Scope::Admin),
+            "owner" => Ok(Scope::Owner),
+            _ => Err(format!(
+                "Escopo inválido: '{}' (use read, moderator, admin ou owner)",
+                value
+            )),
+        }
+    }
+}
+
+// Token da API. Só o hash SHA-256 é guardado; o valor aparece uma única vez,
+// na criação.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ApiToken {
+    pub name: String,
+    pub scope: Scope,
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiToken {
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scope >= scope
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// Cria um token e devolve seu valor em texto para ser mostrado ao usuário
+pub fn create_token(name: &str, scope: Scope) -> Result<String, String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Nome de token inválido: '{}' (use letras, números, '-' ou '_')",
+            name
+        ));
+    }
+
+    let mut config = ManagerConfig::load()?;
+    if config.api_tokens.iter().any(|token| token.name == name) {
+        return Err(format!("Já existe um token chamado {}", name));
+    }
+
+    let secret: [u8; 32] = rand::thread_rng().gen();
+    let token: String = TOKEN_PREFIX.to_string()
+        + &secret
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+    config.api_tokens.push(ApiToken {
+        name: name.to_string(),
+        scope,
+        token_hash: hash_token(&token),
+        created_at: Utc::now(),
+    });
+    config.save()?;
+    Ok(token)
+}
+
+pub fn revoke_token(name: &str) -> Result<bool, String> {
+    let mut config = ManagerConfig::load()?;
+    let before = config.api_tokens.len();
+    config.api_tokens.retain(|token| token.name != name);
+    if config.api_tokens.len() == before {
+        return Ok(false);
+    }
+    config.save()?;
+    Ok(true)
+}
+
+// O arquivo é relido a cada requisição para que tokens revogados deixem de
+// valer sem reiniciar a API
+pub fn authenticate(token: &str) -> Result<Option<ApiToken>, String> {
+    let hash = hash_token(token);
+    Ok(ManagerConfig::load()?
+        .api_tokens
+        .into_iter()
+        .find(|candidate| candidate.token_hash == hash))
+}
+
+pub fn list_tokens() -> Result<Vec<ApiToken>, String> {
+    Ok(ManagerConfig::load()?.api_tokens)
+}
+
+pub fn print_tokens() -> Result<(), String> {
+    let tokens = list_tokens()?;
+    println!("\n=== Tokens da API ===");
+    if tokens.is_empty() {
+        println!("Nenhum token criado.");
+    }
+    for token in tokens {
+        println!(
+            "- {} [{}] criado em {}",
+            token.name,
+            token.scope,
+            token
+                .created_at
+                .with_timezone(&Local)
+                .format("%d/%m/%Y %H:%M")
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,353 @@
+use crate::audit;
+use crate::events::{self, ServerEvent};
+use crate::instance_state::InstanceState;
+use crate::instances::Instance;
+use crate::webhooks;
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use serde_json::json;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use zip::write::FileOptions;
+
+#[derive(Serialize, Clone)]
+pub struct BackupInfo {
+    pub name: String,
+    pub size_bytes: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+fn backups_dir(work_dir: &Path) -> PathBuf {
+    work_dir.join("backups")
+}
+
+// Backups ficam em backups/worlds-AAAAMMDD-HHMMSS-mmm.zip, do mais novo ao
+// mais antigo
+pub fn list_backups(work_dir: &Path) -> Result<Vec<BackupInfo>, String> {
+    let dir = backups_dir(work_dir);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&dir).map_err(|e| format!("Erro ao ler backups: {}", e))?;
+    let mut backups: Vec<BackupInfo> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "zip"))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some(BackupInfo {
+                name: entry.file_name().to_string_lossy().to_string(),
+                size_bytes: metadata.len(),
+                created_at: metadata.modified().ok()?.into(),
+            })
+        })
+        .collect();
+    backups.sort_by_key(|backup| Reverse(backup.created_at));
+    Ok(backups)
+}
+
+// Momento do último backup bem-sucedido. Backups anteriores ao state.json
+// não foram registrados, então vale a data do zip mais novo.
+pub fn last_backup_at(work_dir: &Path) -> Option<DateTime<Utc>> {
+    InstanceState::load(work_dir).last_backup_at.or_else(|| {
+        list_backups(work_dir)
+            .ok()
+            .and_then(|backups| backups.first().map(|info| info.created_at))
+    })
+}
+
+// Copia os mundos para um zip. Com o servidor rodando, usa `save hold` para
+// que os arquivos não mudem durante a cópia e `save resume` ao final.
+pub fn create_backup(instance: &Instance) -> Result<BackupInfo, String> {
+    let result = match InstanceState::load(&instance.work_dir).backups_paused {
+        Some(reason) => Err(format!("Backups pausados: {}", reason)),
+        None => write_backup(instance),
+    };
+    finish_backup(instance, result)
+}
+
+// Registra o resultado no estado, na auditoria e nos webhooks
+fn finish_backup(
+    instance: &Instance,
+    result: Result<BackupInfo, String>,
+) -> Result<BackupInfo, String> {
+    InstanceState::update(&instance.work_dir, |state| {
+        state.last_backup_success = Some(result.is_ok());
+        if result.is_ok() {
+            state.last_backup_at = Some(Utc::now());
+        }
+    });
+    let name = result.as_ref().map(|info| info.name.clone()).ok();
+    audit::record(
+        &instance.work_dir,
+        "backup.create",
+        json!({ "name": name }),
+        &result,
+    );
+    let event = match &result {
+        Ok(info) => ServerEvent::BackupCompleted {
+            name: info.name.clone(),
+        },
+        Err(e) => ServerEvent::BackupFailed { error: e.clone() },
+    };
+    events::announce(&instance.work_dir, &event);
+    webhooks::notify(&instance.work_dir, event);
+    result
+}
+
+fn write_backup(instance: &Instance) -> Result<BackupInfo, String> {
+    let worlds = instance.work_dir.join("worlds");
+    if !worlds.exists() {
+        return Err("Nenhum mundo encontrado para copiar".to_string());
+    }
+
+    let dir = backups_dir(&instance.work_dir);
+    fs::create_dir_all(&dir).map_err(|e| format!("Erro ao criar diretório de backups: {}", e))?;
+    let (name, file) = create_backup_file(&dir)?;
+    let path = dir.join(&name);
+
+    let running = instance.is_running();
+    let lengths = if running {
+        match hold_saves(instance) {
+            Ok(lengths) => lengths,
+            Err(e) => {
+                let _ = fs::remove_file(&path);
+                return Err(e);
+            }
+        }
+    } else {
+        HashMap::new()
+    };
+    let result = write_zip(&worlds, file, &lengths);
+    if running {
+        let _ = instance.admin().send_command("save resume");
+    }
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&path);
+        return Err(e);
+    }
+
+    let size_bytes = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    println!("Backup criado: {}", path.display());
+    Ok(BackupInfo {
+        name,
+        size_bytes,
+        created_at: Utc::now(),
+    })
+}
+
+// Cria o zip com um nome ainda não usado; dois backups no mesmo instante (API
+// e agendador, por exemplo) não sobrescrevem um ao outro
+fn create_backup_file(dir: &Path) -> Result<(String, fs::File), String> {
+    let stamp = Local::now().format("%Y%m%d-%H%M%S-%3f").to_string();
+    for attempt in 0.. {
+        let name = match attempt {
+            0 => format!("worlds-{}.zip", stamp),
+            n => format!("worlds-{}-{}.zip", stamp, n),
+        };
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(dir.join(&name))
+        {
+            Ok(file) => return Ok((name, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Erro ao criar {}: {}", name, e)),
+        }
+    }
+    unreachable!()
+}
+
+// Suspende as gravações do mundo e devolve o tamanho de cada arquivo
+// informado pelo `save query`. O Bedrock continua escrevendo além desses
+// tamanhos durante o `save hold`, então a cópia precisa ser cortada neles.
+fn hold_saves(instance: &Instance) -> Result<HashMap<String, u64>, String> {
+    let admin = instance.admin();
+    admin.send_command("save hold")?;
+
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while Instant::now() < deadline {
+        let lines = admin.execute_and_capture("save query", Duration::from_millis(500))?;
+        if let Some(pos) = lines.iter().position(|line| line.contains("Data saved")) {
+            return Ok(parse_saved_files(&lines[pos + 1..]));
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+
+    let _ = admin.send_command("save resume");
+    Err("O servidor não confirmou o salvamento do mundo a tempo".to_string())
+}
+
+// Interpreta a lista do `save query`, relativa à pasta worlds:
+// "Bedrock level/db/000005.ldb:1234, Bedrock level/db/CURRENT:16, ..."
+fn parse_saved_files(lines: &[String]) -> HashMap<String, u64> {
+    let mut lengths = HashMap::new();
+    for line in lines {
+        // Nomes de mundo podem conter ", "; o pedaço sem tamanho faz parte
+        // do próximo
+        let mut pending = String::new();
+        for piece in line.split(", ") {
+            pending.push_str(piece);
+            match pending.rsplit_once(':') {
+                Some((path, length)) if length.trim().parse::<u64>().is_ok() => {
+                    lengths.insert(
+                        path.trim().replace('\\', "/"),
+                        length.trim().parse().unwrap(),
+                    );
+                    pending.clear();
+                }
+                _ => pending.push_str(", "),
+            }
+        }
+    }
+    lengths
+}
+
+// Copia `source` para o zip, cortando cada arquivo listado em `lengths` no
+// tamanho informado
+fn write_zip(source: &Path, file: fs::File, lengths: &HashMap<String, u64>) -> Result<(), String> {
+    let mut zip = zip::ZipWriter::new(file);
+    add_to_zip(&mut zip, source, source, lengths)?;
+    zip.finish()
+        .map_err(|e| format!("Erro ao finalizar backup: {}", e))?;
+    Ok(())
+}
+
+fn add_to_zip(
+    zip: &mut zip::ZipWriter<fs::File>,
+    root: &Path,
+    dir: &Path,
+    lengths: &HashMap<String, u64>,
+) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Erro ao ler {}: {}", dir.display(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Erro ao ler {}: {}", dir.display(), e))?;
+        let path = entry.path();
+        let relative = path
+            .strip_prefix(root)
+            .map_err(|e| format!("Caminho inválido: {}", e))?
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        if path.is_dir() {
+            zip.add_directory(relative.as_str(), FileOptions::default())
+                .map_err(|e| format!("Erro ao adicionar {}: {}", relative, e))?;
+            add_to_zip(zip, root, &path, lengths)?;
+        } else {
+            zip.start_file(relative.as_str(), FileOptions::default())
+                .map_err(|e| format!("Erro ao adicionar {}: {}", relative, e))?;
+            let file = fs::File::open(&path)
+                .map_err(|e| format!("Erro ao abrir {}: {}", path.display(), e))?;
+            let length = lengths.get(&relative).copied().unwrap_or(u64::MAX);
+            io::copy(&mut file.take(length), zip)
+                .map_err(|e| format!("Erro ao copiar {}: {}", path.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+// Substitui os mundos pelo conteúdo do backup. O servidor precisa estar
+// parado; antes de apagar, os mundos atuais viram um novo backup, mesmo com os
+// backups pausados pelo watchdog.
+pub fn restore_backup(instance: &Instance, name: &str) -> Result<(), String> {
+    let result = extract_backup(instance, name);
+    audit::record(
+        &instance.work_dir,
+        "backup.restore",
+        json!({ "name": name }),
+        &result,
+    );
+    result
+}
+
+fn extract_backup(instance: &Instance, name: &str) -> Result<(), String> {
+    if instance.is_running() {
+        return Err("Pare o servidor antes de restaurar um backup".to_string());
+    }
+    if name.contains('/') || name.contains('\\') || name.contains("..") {
+        return Err(format!("Nome de backup inválido: {}", name));
+    }
+    let path = backups_dir(&instance.work_dir).join(name);
+    if !path.exists() {
+        return Err(format!("Backup não encontrado: {}", name));
+    }
+
+    let worlds = instance.work_dir.join("worlds");
+    if worlds.exists() {
+        finish_backup(instance, write_backup(instance))?;
+        fs::remove_dir_all(&worlds).map_err(|e| format!("Erro ao remover mundos atuais: {}", e))?;
+    }
+
+    let file = fs::File::open(&path).map_err(|e| format!("Erro ao abrir backup: {}", e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Erro ao ler backup: {}", e))?;
+    archive
+        .extract(&worlds)
+        .map_err(|e| format!("Erro ao extrair backup: {}", e))?;
+    println!("Backup {} restaurado", name);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn save_query_lengths_are_parsed() {
+        let lines = vec![
+            "Bedrock level/db/000005.ldb:1234, Bedrock level/db/CURRENT:16".to_string(),
+            "Mundo, um/level.dat:2048, Mundo, um/db/MANIFEST-000002:77".to_string(),
+        ];
+        let lengths = parse_saved_files(&lines);
+        assert_eq!(lengths["Bedrock level/db/000005.ldb"], 1234);
+        assert_eq!(lengths["Bedrock level/db/CURRENT"], 16);
+        assert_eq!(lengths["Mundo, um/level.dat"], 2048);
+        assert_eq!(lengths["Mundo, um/db/MANIFEST-000002"], 77);
+        assert_eq!(lengths.len(), 4);
+    }
+
+    #[test]
+    fn zip_truncates_files_to_reported_length() {
+        let dir = test_util::temp_dir("backup-zip");
+        let worlds = dir.join("worlds");
+        fs::create_dir_all(worlds.join("w/db")).unwrap();
+        fs::write(worlds.join("w/db/000005.ldb"), b"0123456789").unwrap();
+        fs::write(worlds.join("w/level.dat"), b"abcdef").unwrap();
+
+        let lengths = HashMap::from([("w/db/000005.ldb".to_string(), 4)]);
+        let (name, file) = create_backup_file(&dir).unwrap();
+        write_zip(&worlds, file, &lengths).unwrap();
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(dir.join(name)).unwrap()).unwrap();
+        let mut content = String::new();
+        archive
+            .by_name("w/db/000005.ldb")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "0123");
+        content.clear();
+        archive
+            .by_name("w/level.dat")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "abcdef");
+    }
+
+    #[test]
+    fn backups_in_the_same_instant_get_distinct_names() {
+        let dir = test_util::temp_dir("backup-names");
+        let (first, _) = create_backup_file(&dir).unwrap();
+        let (second, _) = create_backup_file(&dir).unwrap();
+        assert_ne!(first, second);
+        assert!(first.starts_with("worlds-") && first.ends_with(".zip"));
+    }
+}
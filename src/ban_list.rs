@@ -0,0 +1,315 @@
+use crate::audit;
+use crate::command::validate_player_name;
+use crate::events::{EventBus, ServerEvent};
+use crate::instances::Instance;
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BanEntry {
+    pub name: String,
+    pub xuid: Option<String>,
+    pub reason: String,
+    pub issued_by: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl BanEntry {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires| expires <= Utc::now())
+    }
+
+    pub fn matches(&self, name: &str, xuid: &str) -> bool {
+        let xuid_matches = !xuid.is_empty() && self.xuid.as_deref() == Some(xuid);
+        xuid_matches || self.name.eq_ignore_ascii_case(name)
+    }
+
+    pub fn kick_message(&self) -> String {
+        match self.expires_at {
+            Some(expires) => format!(
+                "Banido até {}: {}",
+                expires.with_timezone(&Local).format("%d/%m/%Y %H:%M"),
+                self.reason
+            ),
+            None => format!("Banido: {}", self.reason),
+        }
+    }
+}
+
+// Lista de banimentos mantida pelo gerenciador, já que o servidor não possui /ban
+pub struct BanList {
+    path: PathBuf,
+    entries: Vec<BanEntry>,
+}
+
+impl BanList {
+    pub fn load(work_dir: &Path) -> Result<Self, String> {
+        let path = work_dir.join("bans.json");
+        let entries = if path.exists() {
+            let content =
+                fs::read_to_string(&path).map_err(|e| format!("Erro ao ler bans.json: {}", e))?;
+            serde_json::from_str(&content).map_err(|e| format!("Erro ao ler bans.json: {}", e))?
+        } else {
+            Vec::new()
+        };
+        Ok(BanList { path, entries })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(&self.entries)
+            .map_err(|e| format!("Erro ao serializar bans.json: {}", e))?;
+        fs::write(&self.path, content).map_err(|e| format!("Erro ao salvar bans.json: {}", e))
+    }
+
+    pub fn ban(
+        &mut self,
+        name: &str,
+        xuid: Option<&str>,
+        reason: &str,
+        issued_by: &str,
+        duration: Option<Duration>,
+    ) -> Result<BanEntry, String> {
+        validate_player_name(name)?;
+
+        // Um novo banimento substitui o anterior do mesmo jogador
+        self.entries
+            .retain(|entry| !entry.matches(name, xuid.unwrap_or("")));
+
+        let now = Utc::now();
+        let entry = BanEntry {
+            name: name.to_string(),
+            xuid: xuid.filter(|x| !x.is_empty()).map(|x| x.to_string()),
+            reason: if reason.is_empty() {
+                "Banido pelo administrador".to_string()
+            } else {
+                reason.to_string()
+            },
+            issued_by: issued_by.to_string(),
+            issued_at: now,
+            expires_at: duration.map(|d| now + d),
+        };
+        self.entries.push(entry.clone());
+        let result = self.save();
+        audit::record(self.work_dir(), "ban", json!(entry), &result);
+        result.map(|_| entry)
+    }
+
+    pub fn unban(&mut self, name_or_xuid: &str) -> Result<bool, String> {
+        let before = self.entries.len();
+        self.entries
+            .retain(|entry| !entry.matches(name_or_xuid, name_or_xuid));
+        let removed = self.entries.len() != before;
+        if removed {
+            let result = self.save();
+            audit::record(
+                self.work_dir(),
+                "unban",
+                json!({ "player": name_or_xuid }),
+                &result,
+            );
+            result?;
+        }
+        Ok(removed)
+    }
+
+    fn work_dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("."))
+    }
+
+    pub fn active(&self) -> Vec<&BanEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| !entry.is_expired())
+            .collect()
+    }
+
+    pub fn find_active(&self, name: &str, xuid: &str) -> Option<&BanEntry> {
+        self.entries
+            .iter()
+            .find(|entry| !entry.is_expired() && entry.matches(name, xuid))
+    }
+
+    // Guarda o XUID de jogadores banidos apenas pelo nome, para que trocar de
+    // nome não burle o banimento
+    fn learn_xuid(&mut self, name: &str, xuid: &str) -> Result<(), String> {
+        if xuid.is_empty() {
+            return Ok(());
+        }
+        let mut changed = false;
+        for entry in self.entries.iter_mut() {
+            if entry.xuid.is_none() && entry.name.eq_ignore_ascii_case(name) {
+                entry.xuid = Some(xuid.to_string());
+                changed = true;
+            }
+        }
+        if changed {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    pub fn print(&self) {
+        let active = self.active();
+        if active.is_empty() {
+            println!("Nenhum jogador banido.");
+            return;
+        }
+
+        println!("\n=== Jogadores Banidos ===");
+        for entry in active {
+            let expires = match entry.expires_at {
+                Some(expires) => expires
+                    .with_timezone(&Local)
+                    .format("%d/%m/%Y %H:%M")
+                    .to_string(),
+                None => "permanente".to_string(),
+            };
+            println!(
+                "- {} (xuid: {}) | motivo: {} | por: {} em {} | expira: {}",
+                entry.name,
+                entry.xuid.as_deref().unwrap_or("desconhecido"),
+                entry.reason,
+                entry.issued_by,
+                entry
+                    .issued_at
+                    .with_timezone(&Local)
+                    .format("%d/%m/%Y %H:%M"),
+                expires
+            );
+        }
+    }
+}
+
+// Aceita durações como 30m, 12h, 7d ou 2w
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    let invalid = || {
+        format!(
+            "Duração inválida: '{}' (use por exemplo 30m, 12h, 7d)",
+            input
+        )
+    };
+    let Some((split, _)) = input.char_indices().last().filter(|(split, _)| *split > 0) else {
+        return Err(invalid());
+    };
+
+    let (amount, unit) = input.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    if amount <= 0 {
+        return Err(invalid());
+    }
+
+    // Durações grandes demais para uma data também são recusadas
+    let duration = match unit {
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => None,
+    };
+    duration
+        .filter(|duration| Utc::now().checked_add_signed(*duration).is_some())
+        .ok_or_else(invalid)
+}
+
+// Expulsa imediatamente jogadores banidos assim que o console reporta a conexão
+pub fn spawn_enforcer(events: &EventBus, instance: Instance) {
+    let receiver = events.subscribe();
+
+    // As expulsões aparecem na auditoria em nome do próprio serviço
+    thread::spawn(move || {
+        audit::with_actor("auto:banimentos", || {
+            let admin = instance.admin();
+            let work_dir = instance.work_dir;
+            for event in receiver {
+                let ServerEvent::PlayerConnected { name, xuid } = event else {
+                    continue;
+                };
+
+                // Recarrega a cada conexão para enxergar banimentos feitos pela CLI
+                let mut bans = match BanList::load(&work_dir) {
+                    Ok(bans) => bans,
+                    Err(e) => {
+                        println!("Erro ao carregar lista de banimentos: {}", e);
+                        continue;
+                    }
+                };
+
+                let Some(entry) = bans.find_active(&name, &xuid).cloned() else {
+                    continue;
+                };
+
+                if let Err(e) = bans.learn_xuid(&name, &xuid) {
+                    println!("Erro ao atualizar lista de banimentos: {}", e);
+                }
+                println!("Jogador banido tentou entrar: {} ({})", name, xuid);
+                if let Err(e) = admin.kick_player(&name, &entry.kick_message()) {
+                    println!("Erro ao expulsar jogador banido: {}", e);
+                }
+            }
+        })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn durations_are_parsed() {
+        assert_eq!(parse_duration("30m"), Ok(Duration::minutes(30)));
+        assert_eq!(parse_duration(" 12h "), Ok(Duration::hours(12)));
+        assert_eq!(parse_duration("7d"), Ok(Duration::days(7)));
+        assert_eq!(parse_duration("2w"), Ok(Duration::weeks(2)));
+        for input in [
+            "",
+            "m",
+            "30",
+            "0d",
+            "-1h",
+            "1.5h",
+            "10s",
+            "5é",
+            "é",
+            "99999999999999w",
+        ] {
+            assert!(parse_duration(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn tempban_expires() {
+        let dir = test_util::temp_dir("bans");
+        let mut bans = BanList::load(&dir).unwrap();
+        bans.ban("Steve", Some("123"), "", "admin", None).unwrap();
+        bans.ban("Alex", None, "spam", "admin", Some(Duration::hours(1)))
+            .unwrap();
+        assert!(bans.find_active("Alex", "").is_some());
+        // Pelo XUID o banimento vale mesmo com outro nome
+        assert!(bans.find_active("OutroNome", "123").is_some());
+
+        // Um banimento vencido continua salvo, mas não conta mais
+        bans.entries[1].expires_at = Some(Utc::now() - Duration::seconds(1));
+        assert!(bans.entries[1].is_expired());
+        assert!(bans.find_active("Alex", "").is_none());
+        let active: Vec<&str> = bans
+            .active()
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect();
+        assert_eq!(active, ["Steve"]);
+
+        assert!(bans.unban("steve").unwrap());
+        assert!(BanList::load(&dir)
+            .unwrap()
+            .find_active("Steve", "")
+            .is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
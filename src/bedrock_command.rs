@@ -0,0 +1,894 @@
+use crate::command::{validate_identifier, validate_player_name, validate_text, CommandBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt;
+use std::str::FromStr;
+
+// Modelo tipado dos comandos do Bedrock. Cada argumento é validado ao ser
+// construído, então a serialização para texto nunca falha.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectorKind {
+    AllPlayers,
+    NearestPlayer,
+    RandomPlayer,
+    AllEntities,
+    Executor,
+    Initiator,
+}
+
+impl SelectorKind {
+    fn symbol(&self) -> &'static str {
+        match self {
+            SelectorKind::AllPlayers => "@a",
+            SelectorKind::NearestPlayer => "@p",
+            SelectorKind::RandomPlayer => "@r",
+            SelectorKind::AllEntities => "@e",
+            SelectorKind::Executor => "@s",
+            SelectorKind::Initiator => "@initiator",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TargetSelector {
+    Player(String),
+    Selector {
+        kind: SelectorKind,
+        arguments: Vec<(String, String)>,
+    },
+}
+
+impl TargetSelector {
+    pub fn player(name: &str) -> Result<Self, String> {
+        let name = name.trim();
+        validate_player_name(name)?;
+        Ok(TargetSelector::Player(name.to_string()))
+    }
+
+    fn parse_selector(input: &str) -> Result<Self, String> {
+        validate_text(input)?;
+        let (base, arguments) = match input.find('[') {
+            Some(pos) => (&input[..pos], Some(&input[pos..])),
+            None => (input, None),
+        };
+
+        let kind = match base {
+            "@a" => SelectorKind::AllPlayers,
+            "@p" => SelectorKind::NearestPlayer,
+            "@r" => SelectorKind::RandomPlayer,
+            "@e" => SelectorKind::AllEntities,
+            "@s" => SelectorKind::Executor,
+            "@initiator" => SelectorKind::Initiator,
+            _ => return Err(format!("Seletor de alvo inválido: {}", base)),
+        };
+
+        let arguments = match arguments {
+            Some(arguments) => parse_selector_arguments(arguments)?,
+            None => Vec::new(),
+        };
+
+        Ok(TargetSelector::Selector { kind, arguments })
+    }
+}
+
+// Interpreta `[r=10,name="Steve Alex"]`, respeitando vírgulas entre aspas
+fn parse_selector_arguments(input: &str) -> Result<Vec<(String, String)>, String> {
+    let invalid = || format!("Argumentos de seletor inválidos: {}", input);
+    let inner = input
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in inner.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            ',' if !in_quotes => pieces.push(std::mem::take(&mut current)),
+            '[' | ']' | '\\' | '^' | '$' | '/' => {
+                return Err(format!("Caractere inválido '{}' no seletor", c))
+            }
+            _ => current.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(format!("Aspas não fechadas no seletor: {}", input));
+    }
+    if !current.is_empty() || !pieces.is_empty() {
+        pieces.push(current);
+    }
+
+    pieces
+        .into_iter()
+        .map(|piece| {
+            let (key, value) = piece.split_once('=').ok_or_else(invalid)?;
+            let key = key.trim();
+            let value = value.trim();
+            if key.is_empty()
+                || value.is_empty()
+                || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                return Err(invalid());
+            }
+            Ok((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+impl FromStr for TargetSelector {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        if input.starts_with('@') {
+            TargetSelector::parse_selector(input)
+        } else {
+            TargetSelector::player(input)
+        }
+    }
+}
+
+impl fmt::Display for TargetSelector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            // Nomes com espaço precisam de aspas
+            TargetSelector::Player(name) if name.contains(' ') => write!(f, "\"{}\"", name),
+            TargetSelector::Player(name) => write!(f, "{}", name),
+            TargetSelector::Selector { kind, arguments } => {
+                write!(f, "{}", kind.symbol())?;
+                if !arguments.is_empty() {
+                    let arguments: Vec<String> = arguments
+                        .iter()
+                        .map(|(key, value)| format!("{}={}", key, value))
+                        .collect();
+                    write!(f, "[{}]", arguments.join(","))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coordinate {
+    Absolute(f64),
+    // `~`: relativa à posição do alvo
+    Relative(f64),
+    // `^`: relativa à direção em que o alvo olha
+    Local(f64),
+}
+
+impl FromStr for Coordinate {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, String> {
+        let parse_offset = |value: &str| -> Result<f64, String> {
+            if value.is_empty() {
+                return Ok(0.0);
+            }
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .ok_or_else(|| format!("Coordenada inválida: {}", input))
+        };
+
+        if let Some(offset) = input.strip_prefix('~') {
+            Ok(Coordinate::Relative(parse_offset(offset)?))
+        } else if let Some(offset) = input.strip_prefix('^') {
+            Ok(Coordinate::Local(parse_offset(offset)?))
+        } else if input.is_empty() {
+            Err("Coordenada vazia".to_string())
+        } else {
+            Ok(Coordinate::Absolute(parse_offset(input)?))
+        }
+    }
+}
+
+impl fmt::Display for Coordinate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Coordinate::Absolute(value) => write!(f, "{}", value),
+            Coordinate::Relative(offset) if *offset == 0.0 => write!(f, "~"),
+            Coordinate::Relative(offset) => write!(f, "~{}", offset),
+            Coordinate::Local(offset) if *offset == 0.0 => write!(f, "^"),
+            Coordinate::Local(offset) => write!(f, "^{}", offset),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub x: Coordinate,
+    pub y: Coordinate,
+    pub z: Coordinate,
+}
+
+impl FromStr for Coordinates {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, String> {
+        let tokens: Vec<&str> = input.split_whitespace().collect();
+        let [x, y, z] = tokens[..] else {
+            return Err(format!("Coordenadas devem ter 3 valores: {}", input));
+        };
+        let coordinates = Coordinates {
+            x: x.parse()?,
+            y: y.parse()?,
+            z: z.parse()?,
+        };
+
+        // O Bedrock não aceita misturar `^` com outros tipos de coordenada
+        let locals = [coordinates.x, coordinates.y, coordinates.z]
+            .iter()
+            .filter(|c| matches!(c, Coordinate::Local(_)))
+            .count();
+        if locals != 0 && locals != 3 {
+            return Err(format!(
+                "Coordenadas locais (^) não podem ser misturadas: {}",
+                input
+            ));
+        }
+        Ok(coordinates)
+    }
+}
+
+impl fmt::Display for Coordinates {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.x, self.y, self.z)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TeleportDestination {
+    Target(TargetSelector),
+    Coordinates(Coordinates),
+}
+
+impl FromStr for TeleportDestination {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, String> {
+        match input.parse::<Coordinates>() {
+            Ok(coordinates) => Ok(TeleportDestination::Coordinates(coordinates)),
+            Err(_) => Ok(TeleportDestination::Target(input.parse()?)),
+        }
+    }
+}
+
+impl fmt::Display for TeleportDestination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TeleportDestination::Target(target) => write!(f, "{}", target),
+            TeleportDestination::Coordinates(coordinates) => write!(f, "{}", coordinates),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+    Default,
+}
+
+impl FromStr for GameMode {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, String> {
+        match input.trim().to_lowercase().as_str() {
+            "survival" | "s" | "0" => Ok(GameMode::Survival),
+            "creative" | "c" | "1" => Ok(GameMode::Creative),
+            "adventure" | "a" | "2" => Ok(GameMode::Adventure),
+            "spectator" => Ok(GameMode::Spectator),
+            "default" | "d" => Ok(GameMode::Default),
+            _ => Err(format!(
+                "Modo de jogo inválido: '{}' (use survival, creative, adventure, spectator ou default)",
+                input
+            )),
+        }
+    }
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            GameMode::Survival => "survival",
+            GameMode::Creative => "creative",
+            GameMode::Adventure => "adventure",
+            GameMode::Spectator => "spectator",
+            GameMode::Default => "default",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Difficulty {
+    Peaceful,
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, String> {
+        match input.trim().to_lowercase().as_str() {
+            "peaceful" | "p" | "0" => Ok(Difficulty::Peaceful),
+            "easy" | "e" | "1" => Ok(Difficulty::Easy),
+            "normal" | "n" | "2" => Ok(Difficulty::Normal),
+            "hard" | "h" | "3" => Ok(Difficulty::Hard),
+            _ => Err(format!(
+                "Dificuldade inválida: '{}' (use peaceful, easy, normal ou hard)",
+                input
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Difficulty::Peaceful => "peaceful",
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemStack {
+    item: String,
+    amount: u32,
+    data: Option<u32>,
+}
+
+impl ItemStack {
+    pub fn new(item: &str, amount: u32) -> Result<Self, String> {
+        if !(1..=32767).contains(&amount) {
+            return Err(format!("Quantidade inválida: {}", amount));
+        }
+        Ok(ItemStack {
+            item: validate_identifier(item, "item")?,
+            amount,
+            data: None,
+        })
+    }
+
+    // Valor de dados (variante do item, como a cor da lã)
+    pub fn with_data(mut self, data: u32) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
+
+impl fmt::Display for ItemStack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.item, self.amount)?;
+        if let Some(data) = self.data {
+            write!(f, " {}", data)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeOfDay {
+    Day,
+    Noon,
+    Sunset,
+    Night,
+    Midnight,
+    Sunrise,
+    Ticks(u32),
+}
+
+impl FromStr for TimeOfDay {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, String> {
+        match input.trim().to_lowercase().as_str() {
+            "day" | "dia" => Ok(TimeOfDay::Day),
+            "noon" | "meio-dia" => Ok(TimeOfDay::Noon),
+            "sunset" => Ok(TimeOfDay::Sunset),
+            "night" | "noite" => Ok(TimeOfDay::Night),
+            "midnight" | "meia-noite" => Ok(TimeOfDay::Midnight),
+            "sunrise" => Ok(TimeOfDay::Sunrise),
+            other => other.parse().map(TimeOfDay::Ticks).map_err(|_| {
+                format!(
+                    "Horário inválido: '{}' (use day, noon, sunset, night, midnight, sunrise ou ticks)",
+                    input
+                )
+            }),
+        }
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeOfDay::Day => write!(f, "day"),
+            TimeOfDay::Noon => write!(f, "noon"),
+            TimeOfDay::Sunset => write!(f, "sunset"),
+            TimeOfDay::Night => write!(f, "night"),
+            TimeOfDay::Midnight => write!(f, "midnight"),
+            TimeOfDay::Sunrise => write!(f, "sunrise"),
+            TimeOfDay::Ticks(ticks) => write!(f, "{}", ticks),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Weather {
+    Clear,
+    Rain,
+    Thunder,
+}
+
+impl FromStr for Weather {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, String> {
+        match input.trim().to_lowercase().as_str() {
+            "clear" | "limpo" => Ok(Weather::Clear),
+            "rain" | "chuva" => Ok(Weather::Rain),
+            "thunder" | "tempestade" => Ok(Weather::Thunder),
+            _ => Err(format!(
+                "Clima inválido: '{}' (use clear, rain ou thunder)",
+                input
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Weather {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Weather::Clear => "clear",
+            Weather::Rain => "rain",
+            Weather::Thunder => "thunder",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Effect {
+    effect: String,
+    seconds: u32,
+    amplifier: u8,
+    hide_particles: bool,
+}
+
+impl Effect {
+    pub fn new(effect: &str, seconds: u32, amplifier: u8) -> Result<Self, String> {
+        if seconds == 0 || seconds > 1_000_000 {
+            return Err(format!("Duração de efeito inválida: {}", seconds));
+        }
+        Ok(Effect {
+            effect: validate_identifier(effect, "efeito")?,
+            seconds,
+            amplifier,
+            hide_particles: false,
+        })
+    }
+
+    pub fn hide_particles(mut self) -> Self {
+        self.hide_particles = true;
+        self
+    }
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.effect, self.seconds, self.amplifier, self.hide_particles
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GameruleValue {
+    Bool(bool),
+    Int(i32),
+}
+
+impl FromStr for GameruleValue {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        match input.to_lowercase().as_str() {
+            "true" => Ok(GameruleValue::Bool(true)),
+            "false" => Ok(GameruleValue::Bool(false)),
+            other => other
+                .parse()
+                .map(GameruleValue::Int)
+                .map_err(|_| format!("Valor de gamerule inválido: '{}'", input)),
+        }
+    }
+}
+
+impl fmt::Display for GameruleValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameruleValue::Bool(value) => write!(f, "{}", value),
+            GameruleValue::Int(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+pub fn validate_gamerule_name(rule: &str) -> Result<String, String> {
+    let rule = rule.trim();
+    if rule.is_empty() || !rule.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!("Nome de gamerule inválido: '{}'", rule));
+    }
+    Ok(rule.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TitleKind {
+    Title,
+    Subtitle,
+    Actionbar,
+}
+
+impl fmt::Display for TitleKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            TitleKind::Title => "title",
+            TitleKind::Subtitle => "subtitle",
+            TitleKind::Actionbar => "actionbar",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BedrockCommand {
+    GameMode {
+        mode: GameMode,
+        target: TargetSelector,
+    },
+    Op(TargetSelector),
+    Deop(TargetSelector),
+    Kick {
+        target: TargetSelector,
+        reason: String,
+    },
+    WhitelistAdd(TargetSelector),
+    WhitelistRemove(TargetSelector),
+    List,
+    Teleport {
+        target: TargetSelector,
+        destination: TeleportDestination,
+    },
+    Give {
+        target: TargetSelector,
+        item: ItemStack,
+    },
+    Difficulty(Difficulty),
+    Say(String),
+    // Mensagem sem o prefixo "[Server]" do /say
+    Tellraw {
+        target: TargetSelector,
+        text: String,
+    },
+    Tell {
+        target: TargetSelector,
+        message: String,
+    },
+    TimeSet(TimeOfDay),
+    Weather {
+        weather: Weather,
+        // Duração em segundos; sem valor o servidor escolhe aleatoriamente
+        duration: Option<u32>,
+    },
+    Effect {
+        target: TargetSelector,
+        effect: Effect,
+    },
+    EffectClear(TargetSelector),
+    Clear {
+        target: TargetSelector,
+        item: Option<String>,
+    },
+    Gamerule {
+        rule: String,
+        value: GameruleValue,
+    },
+    SetWorldSpawn(Option<Coordinates>),
+    SpawnPoint {
+        target: TargetSelector,
+        position: Option<Coordinates>,
+    },
+    Xp {
+        target: TargetSelector,
+        amount: i32,
+        // Em níveis (`10L`) em vez de pontos de experiência
+        levels: bool,
+    },
+    Title {
+        target: TargetSelector,
+        kind: TitleKind,
+        text: String,
+    },
+    TitleClear(TargetSelector),
+}
+
+impl fmt::Display for BedrockCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let command = match self {
+            BedrockCommand::GameMode { mode, target } => {
+                CommandBuilder::new("gamemode").arg(mode).arg(target)
+            }
+            BedrockCommand::Op(target) => CommandBuilder::new("op").arg(target),
+            BedrockCommand::Deop(target) => CommandBuilder::new("deop").arg(target),
+            BedrockCommand::Kick { target, reason } => {
+                CommandBuilder::new("kick").arg(target).text(reason)
+            }
+            BedrockCommand::WhitelistAdd(target) => {
+                CommandBuilder::new("whitelist").arg("add").arg(target)
+            }
+            BedrockCommand::WhitelistRemove(target) => {
+                CommandBuilder::new("whitelist").arg("remove").arg(target)
+            }
+            BedrockCommand::List => CommandBuilder::new("list"),
+            BedrockCommand::Teleport {
+                target,
+                destination,
+            } => CommandBuilder::new("tp").arg(target).arg(destination),
+            BedrockCommand::Give { target, item } => {
+                CommandBuilder::new("give").arg(target).arg(item)
+            }
+            BedrockCommand::Difficulty(difficulty) => {
+                CommandBuilder::new("difficulty").arg(difficulty)
+            }
+            BedrockCommand::Say(message) => CommandBuilder::new("say").text(message),
+            BedrockCommand::Tellraw { target, text } => CommandBuilder::new("tellraw")
+                .arg(target)
+                .arg(json!({ "rawtext": [{ "text": text }] })),
+            BedrockCommand::Tell { target, message } => {
+                CommandBuilder::new("tell").arg(target).text(message)
+            }
+            BedrockCommand::TimeSet(time) => CommandBuilder::new("time").arg("set").arg(time),
+            BedrockCommand::Weather { weather, duration } => {
+                let command = CommandBuilder::new("weather").arg(weather);
+                match duration {
+                    Some(duration) => command.arg(duration),
+                    None => command,
+                }
+            }
+            BedrockCommand::Effect { target, effect } => {
+                CommandBuilder::new("effect").arg(target).arg(effect)
+            }
+            BedrockCommand::EffectClear(target) => {
+                CommandBuilder::new("effect").arg(target).arg("clear")
+            }
+            BedrockCommand::Clear { target, item } => {
+                let command = CommandBuilder::new("clear").arg(target);
+                match item {
+                    Some(item) => command.arg(item),
+                    None => command,
+                }
+            }
+            BedrockCommand::Gamerule { rule, value } => {
+                CommandBuilder::new("gamerule").arg(rule).arg(value)
+            }
+            BedrockCommand::SetWorldSpawn(position) => {
+                let command = CommandBuilder::new("setworldspawn");
+                match position {
+                    Some(position) => command.arg(position),
+                    None => command,
+                }
+            }
+            BedrockCommand::SpawnPoint { target, position } => {
+                let command = CommandBuilder::new("spawnpoint").arg(target);
+                match position {
+                    Some(position) => command.arg(position),
+                    None => command,
+                }
+            }
+            BedrockCommand::Xp {
+                target,
+                amount,
+                levels,
+            } => {
+                let amount = if *levels {
+                    format!("{}L", amount)
+                } else {
+                    amount.to_string()
+                };
+                CommandBuilder::new("xp").arg(amount).arg(target)
+            }
+            BedrockCommand::Title { target, kind, text } => CommandBuilder::new("title")
+                .arg(target)
+                .arg(kind)
+                .text(text),
+            BedrockCommand::TitleClear(target) => {
+                CommandBuilder::new("title").arg(target).arg("clear")
+            }
+        };
+        write!(f, "{}", command.build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(input: &str) -> TargetSelector {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn player_names_with_spaces_are_quoted() {
+        assert_eq!(target("Steve").to_string(), "Steve");
+        assert_eq!(target("  Steve Alex ").to_string(), "\"Steve Alex\"");
+        assert_eq!(
+            BedrockCommand::Op(target("Steve Alex")).to_string(),
+            "/op \"Steve Alex\""
+        );
+    }
+
+    #[test]
+    fn player_names_reject_quotes_and_control_characters() {
+        assert!("Steve\"".parse::<TargetSelector>().is_err());
+        assert!("Steve\nstop".parse::<TargetSelector>().is_err());
+        assert!("Steve  Alex".parse::<TargetSelector>().is_err());
+        assert!("".parse::<TargetSelector>().is_err());
+        assert!("a".repeat(33).parse::<TargetSelector>().is_err());
+    }
+
+    #[test]
+    fn selectors_keep_their_arguments() {
+        assert_eq!(target("@a").to_string(), "@a");
+        assert_eq!(
+            target("@e[type=cow, r=10]").to_string(),
+            "@e[type=cow,r=10]"
+        );
+        assert_eq!(
+            target("@a[name=\"Steve, Alex\"]").to_string(),
+            "@a[name=\"Steve, Alex\"]"
+        );
+    }
+
+    #[test]
+    fn selectors_reject_invalid_input() {
+        for input in [
+            "@x",
+            "@a[",
+            "@a[r]",
+            "@a[=10]",
+            "@a[name=\"Steve]",
+            "@a[name=$HOME]",
+            "@a[r=1]\nstop",
+        ] {
+            assert!(input.parse::<TargetSelector>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn tellraw_escapes_json() {
+        let command = BedrockCommand::Tellraw {
+            target: target("@a"),
+            text: "diga \"oi\" \\ tchau".to_string(),
+        };
+        assert_eq!(
+            command.to_string(),
+            r#"/tellraw @a {"rawtext":[{"text":"diga \"oi\" \\ tchau"}]}"#
+        );
+    }
+
+    #[test]
+    fn free_text_is_trimmed_and_omitted_when_empty() {
+        let kick = |reason: &str| BedrockCommand::Kick {
+            target: target("Steve"),
+            reason: reason.to_string(),
+        };
+        assert_eq!(kick("  spam ").to_string(), "/kick Steve spam");
+        assert_eq!(kick("   ").to_string(), "/kick Steve");
+    }
+
+    #[test]
+    fn coordinates_parse_and_format() {
+        let coordinates: Coordinates = "~ ~1.5 -20".parse().unwrap();
+        assert_eq!(coordinates.to_string(), "~ ~1.5 -20");
+        assert_eq!(
+            "^ ^ ^2".parse::<Coordinates>().unwrap().to_string(),
+            "^ ^ ^2"
+        );
+        assert!("^ ~ 0".parse::<Coordinates>().is_err());
+        assert!("1 2".parse::<Coordinates>().is_err());
+        assert!("1 2 inf".parse::<Coordinates>().is_err());
+    }
+
+    #[test]
+    fn teleport_accepts_coordinates_or_target() {
+        let teleport = |destination: &str| BedrockCommand::Teleport {
+            target: target("Steve"),
+            destination: destination.parse().unwrap(),
+        };
+        assert_eq!(teleport("0 64 0").to_string(), "/tp Steve 0 64 0");
+        assert_eq!(teleport("Alex Jr").to_string(), "/tp Steve \"Alex Jr\"");
+    }
+
+    #[test]
+    fn item_amount_is_range_checked() {
+        assert!(ItemStack::new("diamond", 0).is_err());
+        assert!(ItemStack::new("diamond", 32768).is_err());
+        assert!(ItemStack::new("minecraft:Diamond Sword", 1).is_err());
+        let stack = ItemStack::new("Minecraft:Wool", 64).unwrap().with_data(14);
+        assert_eq!(
+            BedrockCommand::Give {
+                target: target("Steve"),
+                item: stack,
+            }
+            .to_string(),
+            "/give Steve minecraft:wool 64 14"
+        );
+    }
+
+    #[test]
+    fn effect_duration_is_range_checked() {
+        assert!(Effect::new("speed", 0, 1).is_err());
+        assert!(Effect::new("speed", 1_000_001, 1).is_err());
+        let effect = Effect::new("speed", 30, 2).unwrap().hide_particles();
+        assert_eq!(
+            BedrockCommand::Effect {
+                target: target("@p"),
+                effect,
+            }
+            .to_string(),
+            "/effect @p speed 30 2 true"
+        );
+    }
+
+    #[test]
+    fn enums_accept_aliases() {
+        assert_eq!("c".parse::<GameMode>().unwrap(), GameMode::Creative);
+        assert_eq!("3".parse::<Difficulty>().unwrap(), Difficulty::Hard);
+        assert_eq!("noite".parse::<TimeOfDay>().unwrap(), TimeOfDay::Night);
+        assert_eq!("6000".parse::<TimeOfDay>().unwrap(), TimeOfDay::Ticks(6000));
+        assert!("-1".parse::<TimeOfDay>().is_err());
+        assert_eq!("chuva".parse::<Weather>().unwrap(), Weather::Rain);
+        assert!("hardcore".parse::<Difficulty>().is_err());
+    }
+
+    #[test]
+    fn gamerules_validate_name_and_value() {
+        assert!(validate_gamerule_name("keep inventory").is_err());
+        assert_eq!(
+            "TRUE".parse::<GameruleValue>().unwrap(),
+            GameruleValue::Bool(true)
+        );
+        assert_eq!("5".parse::<GameruleValue>().unwrap(), GameruleValue::Int(5));
+        assert!("cinco".parse::<GameruleValue>().is_err());
+    }
+
+    #[test]
+    fn xp_levels_use_suffix() {
+        let xp = |amount, levels| BedrockCommand::Xp {
+            target: target("Steve"),
+            amount,
+            levels,
+        };
+        assert_eq!(xp(10, true).to_string(), "/xp 10L Steve");
+        assert_eq!(xp(-5, true).to_string(), "/xp -5L Steve");
+        assert_eq!(xp(100, false).to_string(), "/xp 100 Steve");
+    }
+}
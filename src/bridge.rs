@@ -0,0 +1,592 @@
+use crate::audit;
+use crate::events::{ConsoleWatcher, EventBus, ServerEvent};
+use crate::instances::Instance;
+use hyper::body::HttpBody;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// Tamanho máximo de uma mensagem repassada ao jogo
+const MAX_INCOMING_CHARS: usize = 256;
+
+// Corpo máximo de uma mensagem recebida, o mesmo limite dos frames do
+// WebSocket; corpos maiores são recusados sem ser lidos até o fim
+const MAX_BODY_LEN: usize = 64 * 1024;
+
+// Acontecimento do jogo repassado ao chat externo
+pub enum Outgoing {
+    Joined { name: String },
+    Left { name: String },
+    Chat { name: String, message: String },
+}
+
+// Mensagem recebida do chat externo
+pub struct Incoming {
+    pub author: String,
+    pub content: String,
+}
+
+// Formato das mensagens trocadas com o chat externo. Cada formato sabe montar
+// o corpo enviado e interpretar o corpo recebido.
+pub trait WireFormat: Send + Sync {
+    fn encode(&self, message: &Outgoing) -> Value;
+    fn decode(&self, body: &[u8]) -> Result<Incoming, String>;
+}
+
+// Webhook do Discord na saída; na entrada, um objeto de mensagem do Discord
+// como os repassados por bots ({"author": {"username": ...}, "content": ...})
+struct DiscordFormat {
+    username: String,
+}
+
+impl WireFormat for DiscordFormat {
+    fn encode(&self, message: &Outgoing) -> Value {
+        let content = match message {
+            Outgoing::Joined { name } => {
+                format!("**{}** entrou no servidor", escape_markdown(name))
+            }
+            Outgoing::Left { name } => format!("**{}** saiu do servidor", escape_markdown(name)),
+            Outgoing::Chat { name, message } => {
+                format!(
+                    "**{}**: {}",
+                    escape_markdown(name),
+                    escape_markdown(message)
+                )
+            }
+        };
+        // Mensagens do jogo não devem mencionar @everyone ou cargos
+        json!({
+            "username": self.username,
+            "content": content,
+            "allowed_mentions": { "parse": [] },
+        })
+    }
+
+    fn decode(&self, body: &[u8]) -> Result<Incoming, String> {
+        let message: Value =
+            serde_json::from_slice(body).map_err(|e| format!("JSON inválido: {}", e))?;
+        let author = message
+            .pointer("/author/global_name")
+            .or_else(|| message.pointer("/author/username"))
+            .or_else(|| message.get("username"))
+            .and_then(Value::as_str)
+            .ok_or("Mensagem sem autor")?;
+        let content = message
+            .get("content")
+            .and_then(Value::as_str)
+            .ok_or("Mensagem sem conteúdo")?;
+        Ok(Incoming {
+            author: author.to_string(),
+            content: content.to_string(),
+        })
+    }
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '*' | '_' | '~' | '`' | '|' | '>' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// JSON simples nos dois sentidos, para integrações próprias e testes:
+// saída {"type": "join|leave|chat", "name": ..., "message": ...},
+// entrada {"author": ..., "message": ...}
+struct JsonFormat;
+
+#[derive(Deserialize)]
+struct JsonIncoming {
+    author: String,
+    message: String,
+}
+
+impl WireFormat for JsonFormat {
+    fn encode(&self, message: &Outgoing) -> Value {
+        match message {
+            Outgoing::Joined { name } => json!({ "type": "join", "name": name }),
+            Outgoing::Left { name } => json!({ "type": "leave", "name": name }),
+            Outgoing::Chat { name, message } => {
+                json!({ "type": "chat", "name": name, "message": message })
+            }
+        }
+    }
+
+    fn decode(&self, body: &[u8]) -> Result<Incoming, String> {
+        let message: JsonIncoming =
+            serde_json::from_slice(body).map_err(|e| format!("JSON inválido: {}", e))?;
+        Ok(Incoming {
+            author: message.author,
+            content: message.message,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum FormatKind {
+    Discord,
+    Json,
+}
+
+// Como as mensagens recebidas aparecem no jogo
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Delivery {
+    // /say, com o prefixo "[Server]"
+    Say,
+    // /tellraw @a, só com o texto
+    Tellraw,
+}
+
+// Configuração da ponte, em bridge.json no diretório da instância. Exemplo:
+// { "outgoing_url": "https://discord.com/api/webhooks/...",
+//   "listen": "127.0.0.1:8090", "token": "segredo" }
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct BridgeConfig {
+    // Para onde vão entradas, saídas e chat; sem URL nada é enviado
+    pub outgoing_url: Option<String>,
+    pub format: FormatKind,
+    // Nome exibido nas mensagens enviadas pelo formato discord
+    pub username: String,
+    pub relay_joins: bool,
+    pub relay_chat: bool,
+    // Endereço do endpoint que recebe mensagens; sem endereço nada é recebido
+    pub listen: Option<String>,
+    pub path: String,
+    // Exigido como `Authorization: Bearer <token>` nas mensagens recebidas
+    pub token: Option<String>,
+    pub delivery: Delivery,
+    pub prefix: String,
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        BridgeConfig {
+            outgoing_url: None,
+            format: FormatKind::Discord,
+            username: "Minecraft".to_string(),
+            relay_joins: true,
+            relay_chat: true,
+            listen: None,
+            path: "/chat".to_string(),
+            token: None,
+            delivery: Delivery::Tellraw,
+            prefix: "[Discord]".to_string(),
+        }
+    }
+}
+
+impl BridgeConfig {
+    pub fn load(work_dir: &Path) -> Result<Self, String> {
+        let path = work_dir.join("bridge.json");
+        if !path.exists() {
+            return Err(format!("Ponte não configurada: crie {}", path.display()));
+        }
+        let content =
+            fs::read_to_string(&path).map_err(|e| format!("Erro ao ler bridge.json: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Erro ao ler bridge.json: {}", e))
+    }
+
+    fn wire_format(&self) -> Arc<dyn WireFormat> {
+        match self.format {
+            FormatKind::Discord => Arc::new(DiscordFormat {
+                username: self.username.clone(),
+            }),
+            FormatKind::Json => Arc::new(JsonFormat),
+        }
+    }
+
+    fn outgoing(&self, event: ServerEvent) -> Option<Outgoing> {
+        match event {
+            ServerEvent::PlayerConnected { name, .. } if self.relay_joins => {
+                Some(Outgoing::Joined { name })
+            }
+            ServerEvent::PlayerDisconnected { name, .. } if self.relay_joins => {
+                Some(Outgoing::Left { name })
+            }
+            ServerEvent::ChatMessage { name, message } if self.relay_chat => {
+                Some(Outgoing::Chat { name, message })
+            }
+            _ => None,
+        }
+    }
+}
+
+// Ponte entre o chat do jogo e um chat externo. Roda em primeiro plano até o
+// processo ser encerrado.
+pub fn run(instance: Instance) -> Result<(), String> {
+    let config = Arc::new(BridgeConfig::load(&instance.work_dir)?);
+    if config.outgoing_url.is_none() && config.listen.is_none() {
+        return Err("bridge.json não define outgoing_url nem listen".to_string());
+    }
+    let listen: Option<SocketAddr> = match &config.listen {
+        Some(bind) => Some(
+            bind.parse()
+                .map_err(|_| format!("Endereço inválido: {}", bind))?,
+        ),
+        None => None,
+    };
+    if let Some(addr) = listen {
+        if !addr.ip().is_loopback() && config.token.is_none() {
+            println!(
+                "Atenção: a ponte em {} aceita mensagens de outras máquinas sem token",
+                addr
+            );
+        }
+    }
+
+    let events = EventBus::new();
+    let _watcher = ConsoleWatcher::spawn(&instance.work_dir, events.clone());
+    let format = config.wire_format();
+
+    let runtime =
+        tokio::runtime::Runtime::new().map_err(|e| format!("Erro ao iniciar a ponte: {}", e))?;
+    runtime.block_on(async move {
+        if let Some(url) = config.outgoing_url.clone() {
+            println!("Ponte enviando eventos do jogo para {}", url);
+            tokio::spawn(relay_outgoing(
+                url,
+                events.clone(),
+                Arc::clone(&config),
+                Arc::clone(&format),
+            ));
+        }
+
+        let Some(addr) = listen else {
+            // Só saída: fica rodando até o processo ser encerrado
+            std::future::pending::<()>().await;
+            return Ok(());
+        };
+        let make_service = make_service_fn(move |_| {
+            let instance = instance.clone();
+            let config = Arc::clone(&config);
+            let format = Arc::clone(&format);
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle_incoming(
+                        request,
+                        instance.clone(),
+                        Arc::clone(&config),
+                        Arc::clone(&format),
+                    )
+                }))
+            }
+        });
+        let server = hyper::Server::try_bind(&addr)
+            .map_err(|e| format!("Erro ao abrir {}: {}", addr, e))?
+            .serve(make_service);
+        println!("Ponte recebendo mensagens em http://{}", addr);
+        server.await.map_err(|e| format!("Erro na ponte: {}", e))
+    })
+}
+
+async fn relay_outgoing(
+    url: String,
+    events: EventBus,
+    config: Arc<BridgeConfig>,
+    format: Arc<dyn WireFormat>,
+) {
+    // O barramento usa canais bloqueantes; uma thread repassa os eventos
+    // relevantes para o canal assíncrono
+    let (sender, mut receiver) = mpsc::channel::<Outgoing>(256);
+    let subscription = events.subscribe();
+    let relay_config = Arc::clone(&config);
+    tokio::task::spawn_blocking(move || {
+        for event in subscription {
+            let Some(message) = relay_config.outgoing(event) else {
+                continue;
+            };
+            if sender.blocking_send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let client = reqwest::Client::new();
+    while let Some(message) = receiver.recv().await {
+        if let Err(e) = post(&client, &url, &format.encode(&message)).await {
+            println!("Erro ao enviar mensagem da ponte: {}", e);
+        }
+    }
+}
+
+// Envia uma mensagem, esperando e tentando de novo quando o destino limita a
+// taxa (429) ou falha temporariamente
+async fn post(client: &reqwest::Client, url: &str, body: &Value) -> Result<(), String> {
+    const ATTEMPTS: u32 = 3;
+    let mut attempt = 1;
+    loop {
+        let response = client
+            .post(url)
+            .json(body)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await;
+        let (wait, error) = match response {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) if response.status().as_u16() == 429 => {
+                let wait = response
+                    .headers()
+                    .get("Retry-After")
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse::<f64>().ok())
+                    .unwrap_or(1.0);
+                (wait, "HTTP 429".to_string())
+            }
+            Ok(response) if response.status().is_server_error() => {
+                (attempt as f64, format!("HTTP {}", response.status()))
+            }
+            Ok(response) => return Err(format!("HTTP {}", response.status())),
+            Err(e) => (attempt as f64, e.to_string()),
+        };
+        if attempt == ATTEMPTS {
+            return Err(error);
+        }
+        tokio::time::sleep(Duration::from_secs_f64(wait.clamp(0.0, 30.0))).await;
+        attempt += 1;
+    }
+}
+
+fn reply(status: StatusCode, value: Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(value.to_string()))
+        .expect("Resposta HTTP inválida")
+}
+
+async fn handle_incoming(
+    request: Request<Body>,
+    instance: Instance,
+    config: Arc<BridgeConfig>,
+    format: Arc<dyn WireFormat>,
+) -> Result<Response<Body>, Infallible> {
+    if request.method() != Method::POST || request.uri().path() != config.path {
+        return Ok(reply(
+            StatusCode::NOT_FOUND,
+            json!({ "error": "Rota não encontrada" }),
+        ));
+    }
+    if let Some(token) = &config.token {
+        let authorized = request
+            .headers()
+            .get("Authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            == Some(token.as_str());
+        if !authorized {
+            return Ok(reply(
+                StatusCode::UNAUTHORIZED,
+                json!({ "error": "Token ausente ou inválido" }),
+            ));
+        }
+    }
+
+    let body = match read_body(request.into_body()).await {
+        Ok(body) => body,
+        Err((status, e)) => return Ok(reply(status, json!({ "error": e }))),
+    };
+    let incoming = match format.decode(&body) {
+        Ok(incoming) => incoming,
+        Err(e) => return Ok(reply(StatusCode::BAD_REQUEST, json!({ "error": e }))),
+    };
+
+    let result = tokio::task::spawn_blocking(move || deliver(&instance, &config, incoming))
+        .await
+        .unwrap_or_else(|e| Err(format!("Erro interno: {}", e)));
+    Ok(match result {
+        Ok(()) => Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Body::empty())
+            .expect("Resposta HTTP inválida"),
+        Err(e) => reply(StatusCode::CONFLICT, json!({ "error": e })),
+    })
+}
+
+async fn read_body(mut body: Body) -> Result<Vec<u8>, (StatusCode, String)> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk =
+            chunk.map_err(|e| (StatusCode::BAD_REQUEST, format!("Erro ao ler corpo: {}", e)))?;
+        if bytes.len() + chunk.len() > MAX_BODY_LEN {
+            return Err((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("Corpo maior que {} bytes", MAX_BODY_LEN),
+            ));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+// Quebras de linha e outros caracteres de controle viram espaços para a
+// mensagem não injetar comandos no console
+fn sanitize(text: &str) -> String {
+    let text: String = text
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .take(MAX_INCOMING_CHARS)
+        .collect();
+    text.trim().to_string()
+}
+
+fn deliver(instance: &Instance, config: &BridgeConfig, incoming: Incoming) -> Result<(), String> {
+    let author = sanitize(&incoming.author);
+    let content = sanitize(&incoming.content);
+    if content.is_empty() {
+        return Err("Mensagem vazia".to_string());
+    }
+    if !instance.is_running() {
+        return Err("O servidor não está em execução".to_string());
+    }
+
+    let text = format!("{} <{}> {}", config.prefix, author, content);
+    let admin = instance.admin();
+    audit::with_actor(&format!("bridge:{}", author), || match config.delivery {
+        Delivery::Say => admin.say(&text),
+        Delivery::Tellraw => admin.tellraw("@a", &text),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    fn chat() -> Outgoing {
+        Outgoing::Chat {
+            name: "Steve_1".to_string(),
+            message: "oi *todos*".to_string(),
+        }
+    }
+
+    #[test]
+    fn discord_format_escapes_and_blocks_mentions() {
+        let format = DiscordFormat {
+            username: "Minecraft".to_string(),
+        };
+        let body = format.encode(&chat());
+        assert_eq!(body["username"], "Minecraft");
+        assert_eq!(body["content"], r"**Steve\_1**: oi \*todos\*");
+        assert_eq!(body["allowed_mentions"]["parse"], json!([]));
+
+        let incoming = format
+            .decode(br#"{"author": {"username": "alex", "global_name": "Alex"}, "content": "ola"}"#)
+            .unwrap();
+        assert_eq!(incoming.author, "Alex");
+        assert_eq!(incoming.content, "ola");
+        assert!(format.decode(br#"{"content": "sem autor"}"#).is_err());
+        assert!(format.decode(b"nada").is_err());
+    }
+
+    #[test]
+    fn json_format_round_trip() {
+        assert_eq!(
+            JsonFormat.encode(&chat()),
+            json!({ "type": "chat", "name": "Steve_1", "message": "oi *todos*" })
+        );
+        assert_eq!(
+            JsonFormat.encode(&Outgoing::Left {
+                name: "Alex".to_string()
+            }),
+            json!({ "type": "leave", "name": "Alex" })
+        );
+        let incoming = JsonFormat
+            .decode(br#"{"author": "Alex", "message": "ola"}"#)
+            .unwrap();
+        assert_eq!(
+            (incoming.author.as_str(), incoming.content.as_str()),
+            ("Alex", "ola")
+        );
+        assert!(JsonFormat.decode(br#"{"author": "Alex"}"#).is_err());
+    }
+
+    #[test]
+    fn markdown_is_escaped() {
+        assert_eq!(
+            escape_markdown(r"a*b_c~d`e|f>g\h"),
+            r"a\*b\_c\~d\`e\|f\>g\\h"
+        );
+        assert_eq!(escape_markdown("texto comum"), "texto comum");
+    }
+
+    #[test]
+    fn sanitize_strips_control_characters() {
+        assert_eq!(sanitize("oi\nstop\r\tfim"), "oi stop  fim");
+        assert_eq!(sanitize("  \u{7}aviso\u{0}  "), "aviso");
+        assert_eq!(sanitize(&"a".repeat(1000)).len(), MAX_INCOMING_CHARS);
+    }
+
+    fn incoming_status(authorization: Option<&str>, body: Vec<u8>) -> StatusCode {
+        let work_dir = test_util::temp_dir("bridge-incoming");
+        let instance = Instance {
+            name: "ponte".to_string(),
+            work_dir: work_dir.clone(),
+        };
+        let config = Arc::new(BridgeConfig {
+            token: Some("segredo".to_string()),
+            ..BridgeConfig::default()
+        });
+        let mut request = Request::builder().method(Method::POST).uri("/chat");
+        if let Some(value) = authorization {
+            request = request.header("Authorization", value);
+        }
+        let request = request.body(Body::from(body)).unwrap();
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let response = runtime
+            .block_on(handle_incoming(
+                request,
+                instance,
+                config,
+                Arc::new(JsonFormat),
+            ))
+            .unwrap();
+        let _ = fs::remove_dir_all(&work_dir);
+        response.status()
+    }
+
+    #[test]
+    fn incoming_requires_bearer_token() {
+        let body = br#"{"author": "Alex", "message": "ola"}"#.to_vec();
+        assert_eq!(
+            incoming_status(None, body.clone()),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            incoming_status(Some("Bearer errado"), body.clone()),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            incoming_status(Some("segredo"), body.clone()),
+            StatusCode::UNAUTHORIZED
+        );
+        // Autorizada, a mensagem só esbarra no servidor parado
+        assert_eq!(
+            incoming_status(Some("Bearer segredo"), body),
+            StatusCode::CONFLICT
+        );
+    }
+
+    #[test]
+    fn incoming_body_is_capped() {
+        let body = vec![b' '; MAX_BODY_LEN + 1];
+        assert_eq!(
+            incoming_status(Some("Bearer segredo"), body),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+    }
+}
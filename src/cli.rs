@@ -0,0 +1,677 @@
+use crate::api;
+use crate::audit::{self, AuditQuery};
+use crate::auth::{self, Scope};
+use crate::backup;
+use crate::ban_list::{parse_duration, BanList};
+use crate::bridge;
+use crate::config::Config;
+use crate::config_history;
+use crate::daemon::{self, DaemonRequest};
+use crate::gamerules::{self, DesiredGamerules};
+use crate::instances::{InstanceRegistry, DEFAULT_INSTANCE};
+use crate::manager_config;
+use crate::players::{PlayerDatabase, PlayerSort};
+use crate::profiles;
+use crate::properties;
+use crate::scheduler::{self, JobSchedule};
+use crate::server_lock::ServerStatus;
+use crate::watchdog;
+use crate::webhooks;
+use chrono::Local;
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let (args, instance_name) = split_instance_flag(args)?;
+    let Some(command) = args.first() else {
+        print_help();
+        return Err("Nenhum comando informado".to_string());
+    };
+
+    let mut registry = InstanceRegistry::load()?;
+    let instance = registry
+        .get(instance_name.as_deref().unwrap_or(DEFAULT_INSTANCE))?
+        .clone();
+    let work_dir = instance.work_dir.clone();
+
+    match command.as_str() {
+        "ban" | "tempban" => {
+            let temporary = args[0] == "tempban";
+            let (positional, xuid) = split_xuid_flag(&args[1..])?;
+            let min_args = if temporary { 2 } else { 1 };
+            if positional.len() < min_args {
+                return Err(if temporary {
+                    "Uso: tempban <jogador> <duração> [motivo] [--xuid <xuid>]".to_string()
+                } else {
+                    "Uso: ban <jogador> [motivo] [--xuid <xuid>]".to_string()
+                });
+            }
+
+            let player = &positional[0];
+            let duration = if temporary {
+                Some(parse_duration(&positional[1])?)
+            } else {
+                None
+            };
+            let reason = positional[min_args..].join(" ");
+
+            let mut bans = BanList::load(&work_dir)?;
+            let entry = bans.ban(player, xuid.as_deref(), &reason, "cli", duration)?;
+            println!("Jogador {} banido: {}", entry.name, entry.reason);
+
+            // Se o jogador estiver online, expulsa imediatamente
+            let admin = instance.admin();
+            if admin.kick_player(player, &entry.kick_message()).is_err() {
+                println!("Servidor não está em execução; o banimento valerá na próxima conexão");
+            }
+            Ok(())
+        }
+        "unban" => {
+            let Some(player) = args.get(1) else {
+                return Err("Uso: unban <jogador|xuid>".to_string());
+            };
+            let mut bans = BanList::load(&work_dir)?;
+            if bans.unban(player)? {
+                println!("Jogador {} desbanido", player);
+            } else {
+                println!("Jogador {} não está banido", player);
+            }
+            Ok(())
+        }
+        "banlist" => {
+            BanList::load(&work_dir)?.print();
+            Ok(())
+        }
+        "players" => {
+            let sort = match args.get(1).map(|s| s.as_str()) {
+                None => PlayerSort::Playtime,
+                Some("--sort") => PlayerSort::parse(
+                    args.get(2)
+                        .ok_or("--sort requer um valor (playtime ou last-seen)")?,
+                )?,
+                Some(other) => return Err(format!("Argumento desconhecido: {}", other)),
+            };
+            PlayerDatabase::load(&work_dir)?.print(sort);
+            Ok(())
+        }
+        "gamerules" => {
+            let admin = instance.admin();
+            let mut desired = DesiredGamerules::load(&work_dir)?;
+            match args.get(1).map(|s| s.as_str()).unwrap_or("diff") {
+                "show" => gamerules::print_current(&gamerules::query_current(&admin)?),
+                "diff" => gamerules::print_diff(&desired.diff(&gamerules::query_current(&admin)?)),
+                "apply" => println!("{} gamerules aplicadas", desired.apply(&admin)?),
+                "set" => {
+                    let (Some(rule), Some(value)) = (args.get(2), args.get(3)) else {
+                        return Err("Uso: gamerules set <regra> <valor>".to_string());
+                    };
+                    desired.set(rule, value)?;
+                    println!("Gamerule {} = {} salva em gamerules.json", rule, value);
+                }
+                "unset" => {
+                    let Some(rule) = args.get(2) else {
+                        return Err("Uso: gamerules unset <regra>".to_string());
+                    };
+                    if desired.unset(rule)? {
+                        println!("Gamerule {} removida do estado desejado", rule);
+                    } else {
+                        println!("Gamerule {} não estava definida", rule);
+                    }
+                }
+                other => return Err(format!("Subcomando desconhecido: {}", other)),
+            }
+            Ok(())
+        }
+        "instance" => run_instance_command(&mut registry, &args[1..]),
+        "api" => {
+            let bind = match args.get(1).map(|s| s.as_str()) {
+                None => api::DEFAULT_BIND,
+                Some("--bind") => args.get(2).ok_or("--bind requer um endereço")?,
+                Some(other) => return Err(format!("Argumento desconhecido: {}", other)),
+            };
+            api::run(instance, bind)
+        }
+        "bridge" => bridge::run(instance),
+        "audit" => {
+            let mut query = AuditQuery::default();
+            let mut iter = args[1..].iter();
+            while let Some(arg) = iter.next() {
+                let mut value = || {
+                    iter.next()
+                        .cloned()
+                        .ok_or_else(|| format!("{} requer um valor", arg))
+                };
+                match arg.as_str() {
+                    "--since" => query.since = Some(audit::parse_since(&value()?)?),
+                    "--actor" => query.actor = Some(value()?),
+                    "--action" => query.action = Some(value()?),
+                    other => return Err(format!("Argumento desconhecido: {}", other)),
+                }
+            }
+            audit::print(&audit::query(&query)?);
+            Ok(())
+        }
+        "token" => match args.get(1).map(|s| s.as_str()).unwrap_or("list") {
+            "list" => auth::print_tokens(),
+            "create" => {
+                let (Some(name), Some(scope)) = (args.get(2), args.get(3)) else {
+                    return Err("Uso: token create <nome> <read|moderator|admin|owner>".to_string());
+                };
+                let scope: Scope = scope.parse()?;
+                let token = auth::create_token(name, scope)?;
+                println!("Token {} criado com escopo {}:", name, scope);
+                println!("{}", token);
+                println!("Guarde-o agora; ele não poderá ser exibido novamente.");
+                Ok(())
+            }
+            "revoke" => {
+                let Some(name) = args.get(2) else {
+                    return Err("Uso: token revoke <nome>".to_string());
+                };
+                if auth::revoke_token(name)? {
+                    println!("Token {} revogado", name);
+                } else {
+                    println!("Token {} não encontrado", name);
+                }
+                Ok(())
+            }
+            other => Err(format!("Subcomando desconhecido: {}", other)),
+        },
+        "backup" => {
+            match args.get(1).map(|s| s.as_str()).unwrap_or("list") {
+                "list" => {
+                    let backups = backup::list_backups(&work_dir)?;
+                    if backups.is_empty() {
+                        println!("Nenhum backup encontrado.");
+                    }
+                    for info in backups {
+                        println!(
+                            "{}  {:.1} MB  {}",
+                            info.name,
+                            info.size_bytes as f64 / (1024.0 * 1024.0),
+                            info.created_at
+                                .with_timezone(&Local)
+                                .format("%d/%m/%Y %H:%M")
+                        );
+                    }
+                }
+                "create" => {
+                    backup::create_backup(&instance)?;
+                }
+                "restore" => {
+                    let Some(name) = args.get(2) else {
+                        return Err("Uso: backup restore <arquivo>".to_string());
+                    };
+                    backup::restore_backup(&instance, name)?;
+                }
+                other => return Err(format!("Subcomando desconhecido: {}", other)),
+            }
+            Ok(())
+        }
+        "update" => match args.get(1).map(|s| s.as_str()).unwrap_or("check") {
+            "check" => {
+                match Config::new(work_dir).check_for_update()? {
+                    Some(version) => println!("Nova versão disponível: {}", version),
+                    None => println!("O servidor já está na versão mais recente"),
+                }
+                Ok(())
+            }
+            "apply" => {
+                if instance.is_running() {
+                    return Err("Pare o servidor antes de atualizar".to_string());
+                }
+                Config::new(work_dir).install()
+            }
+            other => Err(format!("Subcomando desconhecido: {}", other)),
+        },
+        "webhook" => match args.get(1).map(|s| s.as_str()).unwrap_or("list") {
+            "list" => webhooks::print_list(),
+            "add" => {
+                let (Some(name), Some(url)) = (args.get(2), args.get(3)) else {
+                    return Err("Uso: webhook add <nome> <url> [eventos...]".to_string());
+                };
+                webhooks::add(name, url, args[4..].to_vec())?;
+                println!("Webhook {} adicionado", name);
+                Ok(())
+            }
+            "remove" => {
+                let Some(name) = args.get(2) else {
+                    return Err("Uso: webhook remove <nome>".to_string());
+                };
+                if webhooks::remove(name)? {
+                    println!("Webhook {} removido", name);
+                } else {
+                    println!("Webhook {} não encontrado", name);
+                }
+                Ok(())
+            }
+            "test" => {
+                let Some(name) = args.get(2) else {
+                    return Err("Uso: webhook test <nome> [evento]".to_string());
+                };
+                let event = args.get(3).map(|s| s.as_str()).unwrap_or("server_started");
+                webhooks::test(name, event, &instance)?;
+                println!("Evento {} entregue ao webhook {}", event, name);
+                Ok(())
+            }
+            other => Err(format!("Subcomando desconhecido: {}", other)),
+        },
+        "schedule" => match args.get(1).map(|s| s.as_str()).unwrap_or("list") {
+            "list" => scheduler::print_jobs(&work_dir),
+            "restart" => match args.get(2).map(|s| s.as_str()) {
+                None => Err("Uso: schedule restart <30m|HH:MM> | cancel".to_string()),
+                Some("cancel") => {
+                    scheduler::cancel_restart(&work_dir)?;
+                    println!("Reinício agendado cancelado");
+                    Ok(())
+                }
+                Some(when) => {
+                    let at = scheduler::parse_when(when)?;
+                    scheduler::schedule_restart(&work_dir, at, "manutenção")?;
+                    println!(
+                        "Reinício agendado para {}",
+                        at.with_timezone(&Local).format("%d/%m/%Y %H:%M")
+                    );
+                    warn_without_services();
+                    Ok(())
+                }
+            },
+            action @ ("pause" | "resume" | "run") => {
+                let Some(name) = args.get(2) else {
+                    return Err(format!("Uso: schedule {} <tarefa>", action));
+                };
+                let mut schedule = JobSchedule::load(&work_dir)?;
+                if action == "run" {
+                    scheduler::run_job(&instance, schedule.get(name)?)?;
+                    println!("Tarefa {} executada", name);
+                } else {
+                    schedule.set_paused(&work_dir, name, action == "pause")?;
+                    println!(
+                        "Tarefa {} {}",
+                        name,
+                        if action == "pause" {
+                            "pausada"
+                        } else {
+                            "retomada"
+                        }
+                    );
+                }
+                Ok(())
+            }
+            other => Err(format!("Subcomando desconhecido: {}", other)),
+        },
+        "config" => match args.get(1).map(|s| s.as_str()).unwrap_or("history") {
+            "history" => config_history::print_history(&work_dir, args.get(2).map(|s| s.as_str())),
+            "diff" => match args.get(2).map(|s| s.as_str()) {
+                None => config_history::print_pending_diff(&work_dir),
+                Some("defaults") => config_history::print_defaults_diff(&work_dir),
+                Some(number) => {
+                    config_history::print_version_diff(&work_dir, parse_version(number)?)
+                }
+            },
+            "revert" => {
+                let Some(number) = args.get(2) else {
+                    return Err("Uso: config revert <versão>".to_string());
+                };
+                config_history::revert(&instance, parse_version(number)?)
+            }
+            other => Err(format!("Subcomando desconhecido: {}", other)),
+        },
+        "profile" => match args.get(1).map(|s| s.as_str()).unwrap_or("list") {
+            "list" => profiles::print_list(&work_dir),
+            "show" => {
+                let Some(name) = args.get(2) else {
+                    return Err("Uso: profile show <nome>".to_string());
+                };
+                profiles::print_profile(&work_dir, name)
+            }
+            "save" => {
+                let Some(name) = args.get(2) else {
+                    return Err("Uso: profile save <nome>".to_string());
+                };
+                let dir = profiles::save(&work_dir, name)?;
+                println!("Perfil {} criado em {}", name, dir.display());
+                Ok(())
+            }
+            "apply" => {
+                let Some(name) = args.get(2) else {
+                    return Err("Uso: profile apply <nome> [--warning <segundos>]".to_string());
+                };
+                profiles::apply(&instance, name, warning_secs(&args[3..])?)
+            }
+            "restore" => {
+                let stamp = args.get(2).filter(|arg| !arg.starts_with("--"));
+                let rest = &args[if stamp.is_some() { 3 } else { 2 }..];
+                profiles::restore(&instance, stamp.map(|s| s.as_str()), warning_secs(rest)?)
+            }
+            other => Err(format!("Subcomando desconhecido: {}", other)),
+        },
+        "watchdog" => match args.get(1).map(|s| s.as_str()).unwrap_or("status") {
+            "status" => watchdog::print_status(&instance),
+            other => Err(format!("Subcomando desconhecido: {}", other)),
+        },
+        "settings" => manager_config::print_settings(),
+        "properties" => match args.get(1).map(|s| s.as_str()).unwrap_or("defaults") {
+            "defaults" => properties::print_defaults(args.get(2).map(|s| s.as_str())),
+            "profiles" => properties::print_profiles(),
+            other => Err(format!("Subcomando desconhecido: {}", other)),
+        },
+        "daemon" => match args.get(1).map(|s| s.as_str()) {
+            None | Some("run") => daemon::run(),
+            Some("status") => {
+                daemon::print_status(&daemon::request(&DaemonRequest::Status)?);
+                Ok(())
+            }
+            Some("stop") => {
+                println!("{}", daemon::request(&DaemonRequest::Shutdown)?.message);
+                Ok(())
+            }
+            Some(other) => Err(format!("Subcomando desconhecido: {}", other)),
+        },
+        "help" | "--help" | "-h" => {
+            print_help();
+            Ok(())
+        }
+        other => {
+            print_help();
+            Err(format!("Comando desconhecido: {}", other))
+        }
+    }
+}
+
+fn run_instance_command(registry: &mut InstanceRegistry, args: &[String]) -> Result<(), String> {
+    let usage = "Uso: instance list|create|clone|delete|start|stop|status";
+    match args.first().map(|s| s.as_str()).unwrap_or("list") {
+        "list" => registry.print_status(),
+        "status" => match args.get(1) {
+            Some(name) => {
+                let instance = registry.get(name)?;
+                let (port, port_v6) = instance.ports();
+                println!("Instância: {}", instance.name);
+                println!("Diretório: {}", instance.work_dir.display());
+                println!("Portas: {}/{}", port, port_v6);
+                println!("Sessão: {}", instance.session_name());
+                match instance.server_status() {
+                    ServerStatus::Running(lock) => {
+                        println!("Estado: em execução");
+                        println!("PID: {}", lock.pid);
+                        println!(
+                            "Iniciada em: {}",
+                            lock.started_at
+                                .with_timezone(&Local)
+                                .format("%d/%m/%Y %H:%M")
+                        );
+                        println!(
+                            "Versão: {}",
+                            lock.version.as_deref().unwrap_or("desconhecida")
+                        );
+                    }
+                    ServerStatus::StaleLock(lock) => println!(
+                        "Estado: parada (lock obsoleto do PID {} removido)",
+                        lock.pid
+                    ),
+                    ServerStatus::Stopped => println!("Estado: parada"),
+                }
+            }
+            None => registry.print_status(),
+        },
+        "create" => {
+            let Some(name) = args.get(1) else {
+                return Err(
+                    "Uso: instance create <nome> [--port <porta>] [--defaults <perfil>]"
+                        .to_string(),
+                );
+            };
+            let mut port = None;
+            let mut defaults = None;
+            let mut rest = args[2..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--port" => {
+                        port = Some(
+                            rest.next()
+                                .ok_or("--port requer um valor")?
+                                .parse::<u16>()
+                                .map_err(|_| "Porta inválida".to_string())?,
+                        )
+                    }
+                    "--defaults" => {
+                        defaults = Some(rest.next().ok_or("--defaults requer um perfil")?.as_str())
+                    }
+                    other => return Err(format!("Argumento desconhecido: {}", other)),
+                }
+            }
+            let instance = registry.create(name, port, defaults)?;
+            let (port, port_v6) = instance.ports();
+            println!(
+                "Instância {} criada em {} (portas {}/{})",
+                instance.name,
+                instance.work_dir.display(),
+                port,
+                port_v6
+            );
+            println!("Baixe o servidor pelo menu de configuração antes de iniciá-la.");
+        }
+        "clone" => {
+            let (Some(source), Some(name)) = (args.get(1), args.get(2)) else {
+                return Err("Uso: instance clone <origem> <nome>".to_string());
+            };
+            let instance = registry.clone_instance(source, name)?;
+            let (port, port_v6) = instance.ports();
+            println!(
+                "Instância {} clonada de {} (portas {}/{})",
+                instance.name, source, port, port_v6
+            );
+        }
+        "delete" => {
+            let Some(name) = args.get(1) else {
+                return Err("Uso: instance delete <nome>".to_string());
+            };
+            registry.delete(name)?;
+            println!("Instância {} excluída", name);
+        }
+        "start" => {
+            let Some(name) = args.get(1) else {
+                return Err("Uso: instance start <nome>".to_string());
+            };
+            let instance = registry.get(name)?;
+            if instance.is_running() {
+                return Err(format!("A instância {} já está em execução", name));
+            }
+            // O servidor continua rodando na sessão screen após a CLI sair
+            daemon::start_instance(instance)?;
+            println!("Instância {} iniciada", name);
+            warn_without_services();
+        }
+        "stop" => {
+            let Some(name) = args.get(1) else {
+                return Err("Uso: instance stop <nome>".to_string());
+            };
+            let instance = registry.get(name)?;
+            if !instance.is_running() {
+                return Err(format!("A instância {} não está em execução", name));
+            }
+            daemon::stop_instance(instance)?;
+            println!("Instância {} parada", name);
+        }
+        other => return Err(format!("Subcomando desconhecido: {}\n{}", other, usage)),
+    }
+    Ok(())
+}
+
+// Sem o daemon, ninguém roda os serviços da instância depois que a CLI sai
+fn warn_without_services() {
+    if !daemon::is_running() {
+        println!(
+            "Atenção: sem o daemon (ou o menu ou a API abertos), banimentos, estatísticas, \
+            gamerules, watchdog, webhooks e tarefas agendadas não são executados"
+        );
+    }
+}
+
+// Remove `--instance <nome>` de qualquer posição dos argumentos
+fn split_instance_flag(args: &[String]) -> Result<(Vec<String>, Option<String>), String> {
+    let mut rest = Vec::new();
+    let mut instance = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--instance" {
+            instance = Some(iter.next().ok_or("--instance requer um valor")?.to_string());
+        } else {
+            rest.push(arg.clone());
+        }
+    }
+    Ok((rest, instance))
+}
+
+fn split_xuid_flag(args: &[String]) -> Result<(Vec<String>, Option<String>), String> {
+    let mut positional = Vec::new();
+    let mut xuid = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--xuid" {
+            xuid = Some(iter.next().ok_or("--xuid requer um valor")?.to_string());
+        } else {
+            positional.push(arg.clone());
+        }
+    }
+    Ok((positional, xuid))
+}
+
+fn parse_version(number: &str) -> Result<u32, String> {
+    number
+        .parse()
+        .map_err(|_| format!("Versão inválida: {}", number))
+}
+
+// Lê `--warning <segundos>`, o aviso aos jogadores antes de reiniciar
+fn warning_secs(args: &[String]) -> Result<u64, String> {
+    match args {
+        [] => Ok(60),
+        [flag, value] if flag == "--warning" => value
+            .parse()
+            .map_err(|_| format!("Tempo de aviso inválido: {}", value)),
+        [other, ..] => Err(format!("Argumento desconhecido: {}", other)),
+    }
+}
+
+const COMMANDS: &[(&str, &str)] = &[
+    ("ban <jogador> [motivo] [--xuid <xuid>]", "Bane um jogador"),
+    (
+        "tempban <jogador> <duração> [motivo] [--xuid <xuid>]",
+        "Bane temporariamente (30m, 12h, 7d, 2w)",
+    ),
+    ("unban <jogador|xuid>", "Remove um banimento"),
+    ("banlist", "Lista os banimentos ativos"),
+    (
+        "players [--sort playtime|last-seen]",
+        "Estatísticas de tempo de jogo",
+    ),
+    (
+        "gamerules [show|diff|apply]",
+        "Consulta, compara ou aplica as gamerules desejadas",
+    ),
+    (
+        "gamerules set <regra> <valor> | unset <regra>",
+        "Edita o estado desejado (gamerules.json)",
+    ),
+    (
+        "instance list | status [nome]",
+        "Lista as instâncias e seus estados",
+    ),
+    (
+        "instance create <nome> [--port <porta>] [--defaults <perfil>]",
+        "Cria uma instância com portas próprias",
+    ),
+    (
+        "instance clone <origem> <nome>",
+        "Copia uma instância parada com novas portas",
+    ),
+    ("instance delete <nome>", "Exclui uma instância parada"),
+    (
+        "instance start <nome> | stop <nome>",
+        "Inicia ou para uma instância em segundo plano",
+    ),
+    (
+        "backup [list|create|restore <arquivo>]",
+        "Lista, cria ou restaura backups dos mundos",
+    ),
+    (
+        "update [check|apply]",
+        "Procura uma versão nova ou a instala (servidor parado)",
+    ),
+    (
+        "api [--bind <endereço:porta>]",
+        "Inicia a API HTTP (padrão: 127.0.0.1:8080)",
+    ),
+    (
+        "bridge",
+        "Liga o chat do jogo a um chat externo (bridge.json)",
+    ),
+    (
+        "audit [--since <t>] [--actor <a>] [--action <a>]",
+        "Consulta a auditoria (--since 7d ou AAAA-MM-DD)",
+    ),
+    (
+        "token list | create <nome> <escopo> | revoke <nome>",
+        "Gerencia os tokens da API (read, moderator, admin, owner)",
+    ),
+    (
+        "schedule [list] | pause|resume|run <tarefa>",
+        "Lista, pausa, retoma ou executa tarefas agendadas",
+    ),
+    (
+        "schedule restart <30m|HH:MM> | cancel",
+        "Agenda um reinício único, com avisos nos últimos 5 minutos",
+    ),
+    (
+        "config [history [arquivo]] | diff [versão|defaults]",
+        "Histórico das configurações e diferenças para o arquivo atual",
+    ),
+    (
+        "config revert <versão>",
+        "Restaura um arquivo de configuração para a versão informada",
+    ),
+    (
+        "profile [list] | show|save|apply <nome>",
+        "Lista, mostra, grava ou aplica perfis de configuração",
+    ),
+    (
+        "profile restore [carimbo]",
+        "Volta à configuração anterior a um profile apply",
+    ),
+    (
+        "watchdog [status]",
+        "Mostra os limites do watchdog e as medições atuais",
+    ),
+    (
+        "webhook list | add <nome> <url> [eventos...]",
+        "Lista ou cadastra webhooks (sem eventos: todos)",
+    ),
+    (
+        "webhook remove <nome> | test <nome> [evento]",
+        "Remove um webhook ou envia um evento de teste",
+    ),
+    (
+        "settings",
+        "Mostra as configurações efetivas do gerenciador",
+    ),
+    (
+        "properties [defaults [perfil]] | profiles",
+        "Mostra o server.properties padrão ou os perfis disponíveis",
+    ),
+    (
+        "daemon [run|status|stop]",
+        "Executa, consulta ou encerra o daemon supervisor",
+    ),
+];
+
+fn print_help() {
+    println!("Uso: minecraft_bedrock_server [--instance <nome>] [comando]");
+    println!();
+    println!("Sem comando, abre o menu interativo.");
+    println!("--instance escolhe a instância usada pelos comandos (padrão: default).");
+    println!();
+    println!("Comandos:");
+    for (usage, description) in COMMANDS {
+        println!("  {:<52} {}", usage, description);
+    }
+}
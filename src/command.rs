@@ -0,0 +1,137 @@
+// Montagem segura de comandos do console. Todo argumento vindo do usuário
+// passa por validação aqui antes de chegar à sessão screen do servidor.
+
+const MAX_NAME_LENGTH: usize = 32;
+
+pub struct CommandBuilder {
+    parts: Vec<String>,
+}
+
+impl CommandBuilder {
+    pub fn new(name: &str) -> Self {
+        CommandBuilder {
+            parts: vec![format!("/{}", name)],
+        }
+    }
+
+    // Argumento já validado (seletor, coordenadas, item...) ou palavra fixa
+    // do próprio comando, como `add` em `/whitelist add`
+    pub fn arg<T: std::fmt::Display>(mut self, value: T) -> Self {
+        self.parts.push(value.to_string());
+        self
+    }
+
+    // Texto livre no fim do comando (motivo, mensagem); caracteres de
+    // controle são barrados em `ServerAdmin::execute_command`
+    pub fn text(mut self, text: &str) -> Self {
+        let text = text.trim();
+        if !text.is_empty() {
+            self.parts.push(text.to_string());
+        }
+        self
+    }
+
+    pub fn build(self) -> String {
+        self.parts.join(" ")
+    }
+}
+
+// Caracteres de controle (quebras de linha, ESC...) permitiriam injetar
+// comandos adicionais no console
+pub fn validate_text(text: &str) -> Result<(), String> {
+    if let Some(c) = text.chars().find(|c| c.is_control()) {
+        return Err(format!(
+            "Caractere de controle não permitido: {}",
+            c.escape_default()
+        ));
+    }
+    Ok(())
+}
+
+pub fn validate_player_name(name: &str) -> Result<(), String> {
+    validate_text(name)?;
+    if name.is_empty() {
+        return Err("Nome do jogador não pode ser vazio".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!("Nome do jogador muito longo: {}", name));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !(c.is_alphanumeric() || matches!(c, ' ' | '_' | '-' | '.' | '#')))
+    {
+        return Err(format!("Caractere inválido '{}' no nome do jogador", c));
+    }
+    if name.starts_with(' ') || name.ends_with(' ') || name.contains("  ") {
+        return Err(format!("Espaços inválidos no nome do jogador: '{}'", name));
+    }
+    Ok(())
+}
+
+// IDs no formato `diamond` ou `minecraft:diamond_sword`; `kind` descreve o
+// que está sendo validado (item, efeito...) na mensagem de erro
+pub fn validate_identifier(value: &str, kind: &str) -> Result<String, String> {
+    let value = value.trim().to_lowercase();
+    let valid_part = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '.'))
+    };
+
+    let valid = match value.split_once(':') {
+        Some((namespace, name)) => valid_part(namespace) && valid_part(name),
+        None => valid_part(&value),
+    };
+
+    if valid {
+        Ok(value)
+    } else {
+        Err(format!("ID de {} inválido: '{}'", kind, value))
+    }
+}
+
+// O screen interpreta `\`, `^` e `$` dentro do `stuff`; escapamos para que
+// cheguem literalmente ao servidor
+pub fn escape_for_screen(command: &str) -> String {
+    let mut escaped = String::with_capacity(command.len());
+    for c in command.chars() {
+        if matches!(c, '\\' | '^' | '$') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screen_metacharacters_are_escaped() {
+        assert_eq!(
+            escape_for_screen("say $HOME ^C \\n"),
+            "say \\$HOME \\^C \\\\n"
+        );
+        assert_eq!(escape_for_screen("say oi"), "say oi");
+    }
+
+    #[test]
+    fn control_characters_are_rejected() {
+        assert!(validate_text("say oi").is_ok());
+        assert!(validate_text("say oi\nstop").is_err());
+        assert!(validate_text("say oi\r").is_err());
+        assert!(validate_text("say \u{1b}[2J").is_err());
+    }
+
+    #[test]
+    fn identifiers_are_normalized() {
+        assert_eq!(
+            validate_identifier(" Minecraft:Diamond_Sword ", "item").unwrap(),
+            "minecraft:diamond_sword"
+        );
+        assert!(validate_identifier("minecraft:", "item").is_err());
+        assert!(validate_identifier("diamond sword", "item").is_err());
+    }
+}
@@ -1,18 +1,38 @@
+use crate::audit;
+use crate::events::{self, ServerEvent};
+use crate::instance_state::InstanceState;
+use crate::manager_config;
+use crate::webhooks;
+use chrono::Utc;
 use rand::Rng;
-use reqwest;
+use serde_json::json;
 use std::env;
 use std::fs;
 use std::io::{self, Write};
-use std::path::PathBuf;
-use tokio;
+use std::path::{Path, PathBuf};
+use std::thread;
 
 pub struct Config {
     work_dir: PathBuf,
 }
 
+// bedrock-server-1.21.51.02.zip -> 1.21.51.02
+fn version_from_filename(filename: &str) -> Option<&str> {
+    filename
+        .strip_prefix("bedrock-server-")
+        .and_then(|name| name.strip_suffix(".zip"))
+}
+
+// Versão registrada na última extração (ex: "1.21.51.02")
+pub fn installed_version(work_dir: &Path) -> Option<String> {
+    fs::read_to_string(work_dir.join("version.txt"))
+        .ok()
+        .map(|version| version.trim().to_string())
+        .filter(|version| !version.is_empty())
+}
+
 impl Config {
-    pub fn new() -> Self {
-        let work_dir = PathBuf::from("server");
+    pub fn new(work_dir: PathBuf) -> Self {
         Config { work_dir }
     }
 
@@ -50,8 +70,75 @@ impl Config {
 
     fn configurar_servidor(&self) {
         println!("Configurando servidor...");
+        if let Err(e) = self.install() {
+            println!("Erro ao instalar servidor: {}", e);
+        }
+    }
+
+    // Cria os diretórios da instância e baixa a versão mais recente do
+    // servidor. O download roda em uma thread com runtime próprio para que a
+    // função também possa ser chamada de dentro da API.
+    pub fn install(&self) -> Result<(), String> {
+        let before = installed_version(&self.work_dir);
+        InstanceState::update(&self.work_dir, |state| state.update_in_progress = true);
+        let result = self.download_and_extract();
+        InstanceState::update(&self.work_dir, |state| {
+            state.update_in_progress = false;
+            state.last_update_at = Some(Utc::now());
+            state.last_update_success = Some(result.is_ok());
+        });
+        audit::record(
+            &self.work_dir,
+            "server.update",
+            json!({ "before": before, "after": installed_version(&self.work_dir) }),
+            &result,
+        );
+        if result.is_ok() {
+            let version =
+                installed_version(&self.work_dir).unwrap_or_else(|| "desconhecida".to_string());
+            InstanceState::update(&self.work_dir, |state| state.update_available = None);
+            let event = ServerEvent::UpdateApplied { version };
+            events::announce(&self.work_dir, &event);
+            webhooks::notify(&self.work_dir, event);
+        }
+        result
+    }
+
+    // Compara a versão publicada com a instalada, devolvendo a nova versão se
+    // houver. Os webhooks são avisados uma vez por versão encontrada.
+    pub fn check_for_update(&self) -> Result<Option<String>, String> {
+        let work_dir = self.work_dir.clone();
+        let url = thread::spawn(move || {
+            tokio::runtime::Runtime::new()
+                .map_err(|e| format!("Erro ao verificar atualizações: {}", e))?
+                .block_on(Config::new(work_dir).latest_download_url())
+        })
+        .join()
+        .map_err(|_| "A verificação de atualizações foi interrompida".to_string())??;
+
+        let filename = url.split('/').next_back().unwrap_or_default();
+        let latest = version_from_filename(filename)
+            .ok_or_else(|| format!("Versão não reconhecida na URL {}", url))?
+            .to_string();
+        if installed_version(&self.work_dir).as_deref() == Some(latest.as_str()) {
+            return Ok(None);
+        }
+
+        let state = InstanceState::load(&self.work_dir);
+        if state.update_available.as_deref() != Some(latest.as_str()) {
+            InstanceState::update(&self.work_dir, |state| {
+                state.update_available = Some(latest.clone())
+            });
+            let event = ServerEvent::UpdateAvailable {
+                version: latest.clone(),
+            };
+            events::announce(&self.work_dir, &event);
+            webhooks::notify(&self.work_dir, event);
+        }
+        Ok(Some(latest))
+    }
 
-        // Criar diretórios necessários
+    fn download_and_extract(&self) -> Result<(), String> {
         let dirs = [
             &self.work_dir,
             &self.work_dir.join("downloads"),
@@ -62,18 +149,29 @@ impl Config {
 
         for dir in dirs.iter() {
             if !dir.exists() {
-                fs::create_dir_all(dir).expect(&format!("Erro ao criar diretório {:?}", dir));
+                fs::create_dir_all(dir)
+                    .map_err(|e| format!("Erro ao criar diretório {:?}: {}", dir, e))?;
                 println!("Diretório {:?} criado com sucesso", dir);
             }
         }
 
-        // Iniciar download assíncrono do servidor
-        tokio::runtime::Runtime::new()
-            .unwrap()
-            .block_on(self.download_server());
+        let work_dir = self.work_dir.clone();
+        thread::spawn(move || {
+            tokio::runtime::Runtime::new()
+                .map_err(|e| format!("Erro ao iniciar download: {}", e))?
+                .block_on(Config::new(work_dir).download_server())
+        })
+        .join()
+        .map_err(|_| "A instalação do servidor foi interrompida".to_string())?
     }
 
-    async fn download_server(&self) {
+    async fn download_server(&self) -> Result<(), String> {
+        let download_url = self.latest_download_url().await?;
+        println!("URL de download encontrada: {}", download_url);
+        self.download_server_files(&download_url).await
+    }
+
+    async fn latest_download_url(&self) -> Result<String, String> {
         let rand_num: u32 = rand::thread_rng().gen_range(1..5000);
         let user_agent = format!(
             "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
@@ -89,25 +187,20 @@ impl Config {
 
         let client = reqwest::Client::new();
         let response = client
-            .get("https://www.minecraft.net/pt-br/download/server/bedrock")
+            .get(&manager_config::settings().download_page_url)
             .header("Accept-Encoding", "identity")
             .header("Accept-Language", "en")
             .header("User-Agent", &user_agent)
             .send()
             .await;
 
-        match response {
-            Ok(resp) => {
-                let body = resp.text().await.unwrap();
-                if let Some(download_url) = self.extract_download_url(&body, os_type) {
-                    println!("URL de download encontrada: {}", download_url);
-                    self.download_server_files(&download_url).await;
-                } else {
-                    println!("Não foi possível encontrar a URL de download do servidor");
-                }
-            }
-            Err(e) => println!("Erro ao verificar atualizações: {}", e),
-        }
+        let body = response
+            .map_err(|e| format!("Erro ao verificar atualizações: {}", e))?
+            .text()
+            .await
+            .map_err(|e| format!("Erro ao verificar atualizações: {}", e))?;
+        self.extract_download_url(&body, os_type)
+            .ok_or_else(|| "Não foi possível encontrar a URL de download do servidor".to_string())
     }
 
     fn extract_download_url(&self, html: &str, os_type: &str) -> Option<String> {
@@ -138,25 +231,29 @@ impl Config {
         }
     }
 
-    async fn download_server_files(&self, url: &str) {
+    async fn download_server_files(&self, url: &str) -> Result<(), String> {
         println!("Baixando servidor Minecraft Bedrock...");
         println!("URL de download: {}", url);
 
-        let response = reqwest::get(url).await;
-        match response {
-            Ok(resp) => {
-                let bytes = resp.bytes().await.unwrap();
-                let filename = url.split('/').last().unwrap_or("server.zip");
-                let download_path = self.work_dir.join("downloads").join(filename);
+        let bytes = reqwest::get(url)
+            .await
+            .map_err(|e| format!("Erro ao baixar servidor: {}", e))?
+            .bytes()
+            .await
+            .map_err(|e| format!("Erro ao baixar servidor: {}", e))?;
+        let filename = url.split('/').next_back().unwrap_or("server.zip");
+        let download_path = self.work_dir.join("downloads").join(filename);
 
-                fs::write(&download_path, bytes).expect("Erro ao salvar arquivo");
-                println!("Download concluído! Arquivo salvo em: {:?}", download_path);
-                println!("Extraindo arquivos...");
+        fs::write(&download_path, bytes).map_err(|e| format!("Erro ao salvar arquivo: {}", e))?;
+        println!("Download concluído! Arquivo salvo em: {:?}", download_path);
+        println!("Extraindo arquivos...");
 
-                self.extract_server_files(download_path.to_str().unwrap());
-            }
-            Err(e) => println!("Erro ao baixar servidor: {}", e),
+        self.extract_server_files(download_path.to_str().unwrap());
+
+        if let Some(version) = version_from_filename(filename) {
+            let _ = fs::write(self.work_dir.join("version.txt"), version);
         }
+        Ok(())
     }
 
     fn extract_server_files(&self, zip_path: &str) {
@@ -216,8 +313,8 @@ impl Config {
 
     fn atualizar_servidor(&self) {
         println!("Verificando atualizações...");
-        tokio::runtime::Runtime::new()
-            .unwrap()
-            .block_on(self.download_server());
+        if let Err(e) = self.install() {
+            println!("Erro ao atualizar servidor: {}", e);
+        }
     }
 }
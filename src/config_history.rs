@@ -0,0 +1,418 @@
+use crate::audit;
+use crate::config_manager::ConfigManager;
+use crate::file_lock::FileLock;
+use crate::instances::Instance;
+use crate::properties;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Arquivos versionados a cada gravação pelo gerenciador
+const TRACKED_FILES: &[&str] = &[
+    "server.properties",
+    "allowlist.json",
+    "permissions.json",
+    "world_behavior_packs.json",
+    "world_resource_packs.json",
+];
+
+// Versões mais antigas que isso são descartadas
+const MAX_VERSIONS: usize = 200;
+
+// Uma versão gravada em history/, com o conteúdo em history/<n>-<arquivo>
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ConfigVersion {
+    pub version: u32,
+    pub file: String,
+    pub timestamp: DateTime<Utc>,
+    pub actor: String,
+}
+
+fn history_dir(work_dir: &Path) -> PathBuf {
+    work_dir.join("history")
+}
+
+fn content_path(work_dir: &Path, version: &ConfigVersion) -> PathBuf {
+    history_dir(work_dir).join(format!("{}-{}", version.version, version.file))
+}
+
+// Caminho atual do arquivo; as listas de pacotes ficam na pasta do mundo
+pub fn file_path(work_dir: &Path, file: &str) -> PathBuf {
+    if file.starts_with("world_") {
+        ConfigManager::new(work_dir.to_path_buf())
+            .world_dir()
+            .join(file)
+    } else {
+        work_dir.join(file)
+    }
+}
+
+pub fn load(work_dir: &Path) -> Result<Vec<ConfigVersion>, String> {
+    let path = history_dir(work_dir).join("index.json");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Erro ao ler o histórico: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Erro ao ler o histórico: {}", e))
+}
+
+fn save(work_dir: &Path, versions: &[ConfigVersion]) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(versions)
+        .map_err(|e| format!("Erro ao serializar o histórico: {}", e))?;
+    fs::write(history_dir(work_dir).join("index.json"), content)
+        .map_err(|e| format!("Erro ao salvar o histórico: {}", e))
+}
+
+fn read_version(work_dir: &Path, version: &ConfigVersion) -> Result<String, String> {
+    fs::read_to_string(content_path(work_dir, version))
+        .map_err(|e| format!("Erro ao ler a versão {}: {}", version.version, e))
+}
+
+// Guarda `content` como nova versão de `file`. Se o conteúdo anterior não
+// estava no histórico (arquivo antigo ou editado à mão), ele entra antes,
+// para poder ser restaurado. Uma falha é apenas avisada, já que o arquivo
+// foi gravado.
+pub fn record(work_dir: &Path, file: &str, previous: Option<&str>, content: &str) {
+    if let Err(e) = try_record(work_dir, file, previous, content) {
+        println!("Aviso: não foi possível versionar {}: {}", file, e);
+    }
+}
+
+fn try_record(
+    work_dir: &Path,
+    file: &str,
+    previous: Option<&str>,
+    content: &str,
+) -> Result<(), String> {
+    fs::create_dir_all(history_dir(work_dir))
+        .map_err(|e| format!("Erro ao criar o histórico: {}", e))?;
+    // Gravações de processos diferentes (daemon, menu, API) não podem
+    // numerar versões iguais
+    let _lock = FileLock::acquire(&history_dir(work_dir).join(".lock"), "o histórico")?;
+    let mut versions = load(work_dir)?;
+
+    let last = versions
+        .iter()
+        .rev()
+        .find(|version| version.file == file)
+        .and_then(|version| read_version(work_dir, version).ok());
+    let mut pending = Vec::new();
+    if let Some(previous) = previous.filter(|previous| *previous != content) {
+        if last.as_deref() != Some(previous) {
+            pending.push(previous);
+        }
+    }
+    if pending.is_empty() && last.as_deref() == Some(content) {
+        return Ok(());
+    }
+    pending.push(content);
+
+    for content in pending {
+        let version = ConfigVersion {
+            version: versions.last().map_or(1, |last| last.version + 1),
+            file: file.to_string(),
+            timestamp: Utc::now(),
+            actor: audit::current_actor(),
+        };
+        fs::write(content_path(work_dir, &version), content)
+            .map_err(|e| format!("Erro ao gravar a versão {}: {}", version.version, e))?;
+        versions.push(version);
+    }
+
+    if versions.len() > MAX_VERSIONS {
+        let removed: Vec<ConfigVersion> = versions.drain(..versions.len() - MAX_VERSIONS).collect();
+        for version in &removed {
+            let _ = fs::remove_file(content_path(work_dir, version));
+        }
+    }
+    save(work_dir, &versions)
+}
+
+fn find(work_dir: &Path, number: u32) -> Result<ConfigVersion, String> {
+    load(work_dir)?
+        .into_iter()
+        .find(|version| version.version == number)
+        .ok_or_else(|| format!("Versão {} não encontrada no histórico", number))
+}
+
+// Restaura o conteúdo da versão `number`, que vira uma nova versão. Com o
+// servidor rodando, o que aceita comando (allowlist, permissões e parte das
+// propriedades) vale na hora.
+pub fn revert(instance: &Instance, number: u32) -> Result<(), String> {
+    let work_dir = &instance.work_dir;
+    let version = find(work_dir, number)?;
+    let before = read_current(work_dir, &version.file);
+    let result = read_version(work_dir, &version).and_then(|content| {
+        instance
+            .config_manager()
+            .write_config_file(&version.file, &content)
+            .map(|_| content)
+    });
+    audit::record(
+        work_dir,
+        "config.revert",
+        json!({ "version": number, "file": version.file }),
+        &result,
+    );
+    let content = result?;
+    println!("{} restaurado para a versão {}", version.file, number);
+
+    if !instance.is_running() {
+        return Ok(());
+    }
+    let restart = match version.file.as_str() {
+        "allowlist.json" => {
+            instance.admin().send_command("allowlist reload")?;
+            Vec::new()
+        }
+        "permissions.json" => {
+            instance.admin().send_command("permission reload")?;
+            Vec::new()
+        }
+        "server.properties" => {
+            properties::apply_live(instance, &changed_properties(&before, &content))?
+        }
+        file => vec![file.to_string()],
+    };
+    if !restart.is_empty() {
+        println!(
+            "Exigem reinício para valer: {} (use `schedule restart <quando>`)",
+            restart.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local)
+        .format("%d/%m/%Y %H:%M:%S")
+        .to_string()
+}
+
+pub fn print_history(work_dir: &Path, file: Option<&str>) -> Result<(), String> {
+    let versions = load(work_dir)?;
+    println!("\n=== Histórico de Configuração ===");
+    let mut shown = 0;
+    for version in versions
+        .iter()
+        .rev()
+        .filter(|version| file.is_none_or(|file| version.file == file))
+    {
+        println!(
+            "{:>4}  {}  {:<26} {}",
+            version.version,
+            format_time(version.timestamp),
+            version.file,
+            version.actor
+        );
+        shown += 1;
+    }
+    if shown == 0 {
+        println!("Nenhuma versão registrada");
+    }
+    Ok(())
+}
+
+fn parse_properties(content: &str) -> BTreeMap<String, String> {
+    content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+// Chaves presentes em `new` com valor diferente do de `old`
+fn changed_properties(old: &str, new: &str) -> Vec<String> {
+    let old = parse_properties(old);
+    parse_properties(new)
+        .into_iter()
+        .filter(|(key, value)| old.get(key) != Some(value))
+        .map(|(key, _)| key)
+        .collect()
+}
+
+// Diferença entre dois server.properties, chave a chave
+fn diff_properties(old: &str, new: &str) -> Vec<String> {
+    let (old, new) = (parse_properties(old), parse_properties(new));
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+
+    let mut lines = Vec::new();
+    for key in keys {
+        let (before, after) = (old.get(key), new.get(key));
+        if before == after {
+            continue;
+        }
+        if let Some(value) = before {
+            lines.push(format!("- {}={}", key, value));
+        }
+        if let Some(value) = after {
+            lines.push(format!("+ {}={}", key, value));
+        }
+    }
+    lines
+}
+
+// Diferença linha a linha pela maior subsequência comum
+fn diff_lines(old: &str, new: &str) -> Vec<String> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let mut common = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i][j] = if a[i] == b[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            lines.push(format!("- {}", a[i]));
+            i += 1;
+        } else {
+            lines.push(format!("+ {}", b[j]));
+            j += 1;
+        }
+    }
+    lines.extend(a[i..].iter().map(|line| format!("- {}", line)));
+    lines.extend(b[j..].iter().map(|line| format!("+ {}", line)));
+    lines
+}
+
+fn diff(file: &str, old: &str, new: &str) -> Vec<String> {
+    if file.ends_with(".properties") {
+        diff_properties(old, new)
+    } else {
+        diff_lines(old, new)
+    }
+}
+
+fn print_diff(file: &str, old_label: &str, old: &str, new: &str) {
+    println!("\n--- {} ({})", file, old_label);
+    println!("+++ {} (atual)", file);
+    let lines = diff(file, old, new);
+    if lines.is_empty() {
+        println!("Sem diferenças");
+    }
+    for line in lines {
+        println!("{}", line);
+    }
+}
+
+fn read_current(work_dir: &Path, file: &str) -> String {
+    fs::read_to_string(file_path(work_dir, file)).unwrap_or_default()
+}
+
+// Compara o arquivo da versão `number` com o atual
+pub fn print_version_diff(work_dir: &Path, number: u32) -> Result<(), String> {
+    let version = find(work_dir, number)?;
+    let old = read_version(work_dir, &version)?;
+    let label = format!(
+        "versão {}, {}",
+        version.version,
+        format_time(version.timestamp)
+    );
+    print_diff(
+        &version.file,
+        &label,
+        &old,
+        &read_current(work_dir, &version.file),
+    );
+    Ok(())
+}
+
+// Compara o server.properties atual com os padrões do gerenciador
+pub fn print_defaults_diff(work_dir: &Path) -> Result<(), String> {
+    let defaults = properties::default_file_content(None)?;
+    print_diff(
+        "server.properties",
+        "padrão",
+        &defaults,
+        &read_current(work_dir, "server.properties"),
+    );
+    Ok(())
+}
+
+// Mostra o que mudou em cada arquivo desde a última versão registrada, como
+// edições feitas à mão
+pub fn print_pending_diff(work_dir: &Path) -> Result<(), String> {
+    let versions = load(work_dir)?;
+    let mut changed = false;
+    for file in TRACKED_FILES {
+        let Some(last) = versions.iter().rev().find(|version| version.file == *file) else {
+            continue;
+        };
+        let old = read_version(work_dir, last)?;
+        let current = read_current(work_dir, file);
+        if diff(file, &old, &current).is_empty() {
+            continue;
+        }
+        changed = true;
+        print_diff(file, &format!("versão {}", last.version), &old, &current);
+    }
+    if !changed {
+        println!("Nenhuma alteração desde a última versão registrada");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn concurrent_records_get_distinct_versions() {
+        let dir = test_util::temp_dir("history");
+        let handles: Vec<_> = (0..8)
+            .map(|n| {
+                let dir = dir.clone();
+                thread::spawn(move || {
+                    let content = format!("versão {}", n);
+                    try_record(&dir, "allowlist.json", None, &content).unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let versions = load(&dir).unwrap();
+        let numbers: BTreeSet<u32> = versions.iter().map(|version| version.version).collect();
+        assert_eq!(versions.len(), 8);
+        assert_eq!(numbers.len(), 8);
+        for version in &versions {
+            assert!(read_version(&dir, version).unwrap().starts_with("versão "));
+        }
+        assert!(!history_dir(&dir).join(".lock").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn stale_lock_is_replaced() {
+        let dir = test_util::temp_dir("history-stale");
+        fs::create_dir_all(history_dir(&dir)).unwrap();
+        let lock = history_dir(&dir).join(".lock");
+        let file = fs::File::create(&lock).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(60))
+            .unwrap();
+
+        try_record(&dir, "allowlist.json", None, "[]").unwrap();
+        assert_eq!(load(&dir).unwrap().len(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
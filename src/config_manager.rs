@@ -1,50 +1,27 @@
+use crate::audit;
+use crate::config_history;
+use crate::instances::InstanceRegistry;
+use crate::properties::{self, PropertyKind};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
 use std::fs;
-use std::io::{self, BufRead, BufReader};
+use std::io;
 use std::path::PathBuf;
 
-#[derive(Serialize, Deserialize)]
-pub struct ServerProperties {
-    #[serde(rename = "server-name")]
-    server_name: String,
-    #[serde(rename = "server-port")]
-    server_port: u16,
-    gamemode: String,
-    difficulty: String,
-    #[serde(rename = "allow-cheats")]
-    allow_cheats: bool,
-    #[serde(rename = "max-players")]
-    max_players: u32,
-    #[serde(rename = "online-mode")]
-    online_mode: bool,
-    #[serde(rename = "white-list")]
-    white_list: bool,
-    #[serde(rename = "view-distance")]
-    view_distance: u32,
-    #[serde(rename = "tick-distance")]
-    tick_distance: u32,
-    #[serde(rename = "player-idle-timeout")]
-    player_idle_timeout: u32,
-    #[serde(rename = "max-threads")]
-    max_threads: u32,
-    #[serde(rename = "default-player-permission-level")]
-    default_player_permission_level: String,
-    #[serde(rename = "texturepack-required")]
-    texturepack_required: bool,
-}
-
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Permission {
-    permission: String,
-    xuid: String,
+    pub permission: String,
+    pub xuid: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct AllowlistEntry {
-    name: String,
-    xuid: String,
-    #[serde(rename = "ignoresPlayerLimit")]
-    ignores_player_limit: bool,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub xuid: String,
+    #[serde(rename = "ignoresPlayerLimit", default)]
+    pub ignores_player_limit: bool,
 }
 
 pub struct ConfigManager {
@@ -56,64 +33,263 @@ impl ConfigManager {
         ConfigManager { work_dir }
     }
 
+    // Cria apenas os arquivos que ainda não existem, para não apagar as
+    // configurações (e as portas) de cada instância a cada início
     pub fn initialize_configs(&self) -> Result<(), String> {
-        self.create_default_server_properties()?;
-        self.create_default_permissions()?;
-        self.create_default_allowlist()?;
+        self.initialize_with_defaults(None)
+    }
+
+    // Como `initialize_configs`, mas cria o server.properties com os valores
+    // do perfil de propriedades informado
+    pub fn initialize_with_defaults(&self, profile: Option<&str>) -> Result<(), String> {
+        if !self.work_dir.join("server.properties").exists() {
+            self.create_default_server_properties(profile)?;
+        }
+        if !self.work_dir.join("permissions.json").exists() {
+            self.create_default_permissions()?;
+        }
+        if !self.work_dir.join("allowlist.json").exists() {
+            self.create_default_allowlist()?;
+        }
         Ok(())
     }
 
-    fn create_default_server_properties(&self) -> Result<(), String> {
-        let properties = ServerProperties {
-            server_name: "Dedicated Server".to_string(),
-            server_port: 19132,
-            gamemode: "survival".to_string(),
-            difficulty: "normal".to_string(),
-            allow_cheats: false,
-            max_players: 10,
-            online_mode: true,
-            white_list: false,
-            view_distance: 32,
-            tick_distance: 4,
-            player_idle_timeout: 30,
-            max_threads: 8,
-            default_player_permission_level: "member".to_string(),
-            texturepack_required: false,
-        };
+    pub fn read_property(&self, key: &str) -> Option<String> {
+        let content = fs::read_to_string(self.work_dir.join("server.properties")).ok()?;
+        content.lines().find_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            (name.trim() == key).then(|| value.trim().to_string())
+        })
+    }
 
-        let content = self.properties_to_string(&properties)?;
-        let path = self.work_dir.join("server.properties");
-        fs::write(&path, content).map_err(|e| format!("Erro ao criar server.properties: {}", e))?;
-        println!("Arquivo server.properties criado com configurações padrão");
+    // Todas as propriedades do server.properties, inclusive as que o
+    // gerenciador não edita
+    pub fn read_all_properties(&self) -> Result<BTreeMap<String, String>, String> {
+        let content = fs::read_to_string(self.work_dir.join("server.properties"))
+            .map_err(|e| format!("Erro ao ler server.properties: {}", e))?;
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect())
+    }
+
+    pub fn read_allowlist(&self) -> Result<Vec<AllowlistEntry>, String> {
+        self.read_json("allowlist.json")
+    }
+
+    pub fn save_allowlist(&self, entries: &[AllowlistEntry]) -> Result<(), String> {
+        let before = self.read_allowlist().unwrap_or_default();
+        let result = self.write_json("allowlist.json", entries);
+        audit::record(
+            &self.work_dir,
+            "config.allowlist",
+            json!({ "before": before, "after": entries }),
+            &result,
+        );
+        result
+    }
+
+    pub fn read_permissions(&self) -> Result<Vec<Permission>, String> {
+        self.read_json("permissions.json")
+    }
+
+    pub fn save_permissions(&self, permissions: &[Permission]) -> Result<(), String> {
+        let before = self.read_permissions().unwrap_or_default();
+        let result = self.write_json("permissions.json", permissions);
+        audit::record(
+            &self.work_dir,
+            "config.permissions",
+            json!({ "before": before, "after": permissions }),
+            &result,
+        );
+        result
+    }
+
+    fn read_json<T: serde::de::DeserializeOwned>(&self, file: &str) -> Result<Vec<T>, String> {
+        let path = self.work_dir.join(file);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content =
+            fs::read_to_string(&path).map_err(|e| format!("Erro ao ler {}: {}", file, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Erro ao ler {}: {}", file, e))
+    }
+
+    fn write_json<T: Serialize>(&self, file: &str, entries: &[T]) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(entries)
+            .map_err(|e| format!("Erro ao serializar {}: {}", file, e))?;
+        self.write_config_file(file, &content)
+    }
+
+    // Grava um dos arquivos versionados, guardando a nova versão no histórico
+    pub fn write_config_file(&self, file: &str, content: &str) -> Result<(), String> {
+        let path = config_history::file_path(&self.work_dir, file);
+        let previous = fs::read_to_string(&path).ok();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Erro ao criar {}: {}", dir.display(), e))?;
+        }
+        fs::write(&path, content).map_err(|e| format!("Erro ao salvar {}: {}", file, e))?;
+        config_history::record(&self.work_dir, file, previous.as_deref(), content);
         Ok(())
     }
 
-    fn properties_to_string(&self, props: &ServerProperties) -> Result<String, String> {
-        let mut content = String::new();
-        content.push_str(&format!("server-name={}\n", props.server_name));
-        content.push_str(&format!("server-port={}\n", props.server_port));
-        content.push_str(&format!("gamemode={}\n", props.gamemode));
-        content.push_str(&format!("difficulty={}\n", props.difficulty));
-        content.push_str(&format!("allow-cheats={}\n", props.allow_cheats));
-        content.push_str(&format!("max-players={}\n", props.max_players));
-        content.push_str(&format!("online-mode={}\n", props.online_mode));
-        content.push_str(&format!("white-list={}\n", props.white_list));
-        content.push_str(&format!("view-distance={}\n", props.view_distance));
-        content.push_str(&format!("tick-distance={}\n", props.tick_distance));
-        content.push_str(&format!(
-            "player-idle-timeout={}\n",
-            props.player_idle_timeout
-        ));
-        content.push_str(&format!("max-threads={}\n", props.max_threads));
-        content.push_str(&format!(
-            "default-player-permission-level={}\n",
-            props.default_player_permission_level
-        ));
-        content.push_str(&format!(
-            "texturepack-required={}\n",
-            props.texturepack_required
-        ));
-        Ok(content)
+    // Pasta do mundo ativo, worlds/<level-name>
+    pub fn world_dir(&self) -> PathBuf {
+        let level = self
+            .read_property("level-name")
+            .unwrap_or_else(|| "Bedrock level".to_string());
+        self.work_dir.join("worlds").join(level)
+    }
+
+    // Portas IPv4 e IPv6 configuradas no server.properties
+    pub fn ports(&self) -> (u16, u16) {
+        let port: u16 = self
+            .read_property("server-port")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(19132);
+        let port_v6 = self
+            .read_property("server-portv6")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(port.saturating_add(1));
+        (port, port_v6)
+    }
+
+    // Atualiza chaves do server.properties mantendo comentários e as demais
+    // linhas; chaves ausentes são adicionadas ao final. Só as chaves cujo
+    // valor mudou entram na auditoria, com o valor anterior e o novo, e são
+    // devolvidas.
+    pub fn set_properties(&self, updates: &[(&str, String)]) -> Result<Vec<String>, String> {
+        let before = self.read_all_properties().unwrap_or_default();
+        let changes: BTreeMap<&str, Value> = updates
+            .iter()
+            .filter(|(key, value)| before.get(*key) != Some(value))
+            .map(|(key, value)| (*key, json!({ "before": before.get(*key), "after": value })))
+            .collect();
+
+        let result = self
+            .check_ports(updates)
+            .and_then(|_| self.write_properties(updates));
+        if !changes.is_empty() {
+            audit::record(&self.work_dir, "config.properties", json!(changes), &result);
+        }
+        result.map(|_| changes.keys().map(|key| key.to_string()).collect())
+    }
+
+    // Troca o server.properties inteiro por `values`: chaves ausentes saem do
+    // arquivo. Devolve e audita as chaves alteradas ou removidas.
+    pub fn replace_properties(
+        &self,
+        values: &BTreeMap<String, String>,
+    ) -> Result<Vec<String>, String> {
+        let before = self.read_all_properties().unwrap_or_default();
+        let changes: BTreeMap<&str, Value> = before
+            .keys()
+            .chain(values.keys())
+            .filter(|key| before.get(*key) != values.get(*key))
+            .map(|key| {
+                let change = json!({ "before": before.get(key), "after": values.get(key) });
+                (key.as_str(), change)
+            })
+            .collect();
+
+        let updates: Vec<(&str, String)> = values
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.clone()))
+            .collect();
+        let content: String = values
+            .iter()
+            .map(|(key, value)| format!("{}={}\n", key, value))
+            .collect();
+        let result = self
+            .check_ports(&updates)
+            .and_then(|_| self.write_config_file("server.properties", &content));
+        if !changes.is_empty() {
+            audit::record(&self.work_dir, "config.properties", json!(changes), &result);
+        }
+        result.map(|_| changes.keys().map(|key| key.to_string()).collect())
+    }
+
+    // Portas alteradas não podem coincidir com as de outra instância
+    fn check_ports(&self, updates: &[(&str, String)]) -> Result<(), String> {
+        let update = |key: &str| {
+            updates
+                .iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| {
+                    value
+                        .parse::<u16>()
+                        .map_err(|_| format!("Porta inválida: {}", value))
+                })
+        };
+        let (port, port_v6) = match (update("server-port"), update("server-portv6")) {
+            (None, None) => return Ok(()),
+            (port, port_v6) => {
+                let (current, current_v6) = self.ports();
+                (
+                    port.unwrap_or(Ok(current))?,
+                    port_v6.unwrap_or(Ok(current_v6))?,
+                )
+            }
+        };
+        if port == port_v6 {
+            return Err(format!(
+                "server-port e server-portv6 não podem ser iguais ({})",
+                port
+            ));
+        }
+
+        let registry = InstanceRegistry::load()?;
+        let name = registry
+            .list()
+            .into_iter()
+            .find(|instance| instance.work_dir == self.work_dir)
+            .map(|instance| instance.name.clone())
+            .unwrap_or_default();
+        registry.ensure_ports_free(&name, port, port_v6)
+    }
+
+    fn write_properties(&self, updates: &[(&str, String)]) -> Result<(), String> {
+        let content =
+            fs::read_to_string(self.work_dir.join("server.properties")).unwrap_or_default();
+        let mut pending: Vec<&(&str, String)> = updates.iter().collect();
+
+        let mut lines: Vec<String> = content
+            .lines()
+            .map(|line| {
+                let Some((name, _)) = line.split_once('=') else {
+                    return line.to_string();
+                };
+                if line.trim_start().starts_with('#') {
+                    return line.to_string();
+                }
+                match pending.iter().position(|(key, _)| *key == name.trim()) {
+                    Some(index) => {
+                        let (key, value) = pending.remove(index);
+                        format!("{}={}", key, value)
+                    }
+                    None => line.to_string(),
+                }
+            })
+            .collect();
+        lines.extend(
+            pending
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value)),
+        );
+
+        let mut content = lines.join("\n");
+        content.push('\n');
+        self.write_config_file("server.properties", &content)
+    }
+
+    fn create_default_server_properties(&self, profile: Option<&str>) -> Result<(), String> {
+        let content = properties::default_file_content(profile)?;
+        self.write_config_file("server.properties", &content)?;
+        println!("Arquivo server.properties criado com configurações padrão");
+        Ok(())
     }
 
     fn create_default_permissions(&self) -> Result<(), String> {
@@ -122,8 +298,7 @@ impl ConfigManager {
             .map_err(|e| format!("Erro ao serializar permissions.json: {}", e))?;
 
         // Criar permissions.json na raiz
-        let path = self.work_dir.join("permissions.json");
-        fs::write(&path, &content).map_err(|e| format!("Erro ao criar permissions.json: {}", e))?;
+        self.write_config_file("permissions.json", &content)?;
 
         // Criar diretório config/default se não existir
         let default_config_dir = self.work_dir.join("config").join("default");
@@ -144,112 +319,66 @@ impl ConfigManager {
         let content = serde_json::to_string_pretty(&allowlist)
             .map_err(|e| format!("Erro ao serializar allowlist.json: {}", e))?;
 
-        let path = self.work_dir.join("allowlist.json");
-        fs::write(&path, content).map_err(|e| format!("Erro ao criar allowlist.json: {}", e))?;
+        self.write_config_file("allowlist.json", &content)?;
 
         println!("Arquivo allowlist.json criado com configuração padrão");
         Ok(())
     }
 
-    pub fn configure_server(&self) -> Result<(), String> {
-        let properties = self.read_server_properties()?;
-        let updated_properties = self.interactive_config(properties)?;
-        self.save_server_properties(&updated_properties)
+    // Devolve as propriedades que mudaram, para quem chama aplicá-las
+    pub fn configure_server(&self) -> Result<Vec<String>, String> {
+        let current = self.read_server_properties()?;
+        let updates = self.interactive_config(&current)?;
+        self.save_server_properties(&updates)
     }
 
-    fn read_server_properties(&self) -> Result<ServerProperties, String> {
-        let path = self.work_dir.join("server.properties");
-        let file =
-            fs::File::open(&path).map_err(|e| format!("Erro ao abrir server.properties: {}", e))?;
-        let reader = BufReader::new(file);
-        let mut props = ServerProperties {
-            server_name: "Dedicated Server".to_string(),
-            server_port: 19132,
-            gamemode: "survival".to_string(),
-            difficulty: "normal".to_string(),
-            allow_cheats: false,
-            max_players: 10,
-            online_mode: true,
-            white_list: false,
-            view_distance: 32,
-            tick_distance: 4,
-            player_idle_timeout: 30,
-            max_threads: 8,
-            default_player_permission_level: "member".to_string(),
-            texturepack_required: false,
-        };
-
-        for line in reader.lines() {
-            let line = line.map_err(|e| format!("Erro ao ler linha: {}", e))?;
-            if let Some((key, value)) = line.split_once('=') {
-                match key {
-                    "server-name" => props.server_name = value.to_string(),
-                    "server-port" => props.server_port = value.parse().unwrap_or(19132),
-                    "gamemode" => props.gamemode = value.to_string(),
-                    "difficulty" => props.difficulty = value.to_string(),
-                    "allow-cheats" => props.allow_cheats = value.parse().unwrap_or(false),
-                    "max-players" => props.max_players = value.parse().unwrap_or(10),
-                    "online-mode" => props.online_mode = value.parse().unwrap_or(true),
-                    "white-list" => props.white_list = value.parse().unwrap_or(false),
-                    "view-distance" => props.view_distance = value.parse().unwrap_or(32),
-                    "tick-distance" => props.tick_distance = value.parse().unwrap_or(4),
-                    "player-idle-timeout" => {
-                        props.player_idle_timeout = value.parse().unwrap_or(30)
-                    }
-                    "max-threads" => props.max_threads = value.parse().unwrap_or(8),
-                    "default-player-permission-level" => {
-                        props.default_player_permission_level = value.to_string()
-                    }
-                    "texturepack-required" => {
-                        props.texturepack_required = value.parse().unwrap_or(false)
-                    }
-                    _ => {}
-                }
-            }
-        }
-
-        Ok(props)
+    // Propriedades do arquivo, com o valor padrão para as conhecidas que
+    // estiverem ausentes
+    fn read_server_properties(&self) -> Result<BTreeMap<String, String>, String> {
+        let mut values: BTreeMap<String, String> = properties::PROPERTIES
+            .iter()
+            .map(|property| (property.key.to_string(), property.default.to_string()))
+            .collect();
+        values.extend(self.read_all_properties()?);
+        Ok(values)
     }
 
-    fn interactive_config(&self, mut props: ServerProperties) -> Result<ServerProperties, String> {
+    fn interactive_config(
+        &self,
+        current: &BTreeMap<String, String>,
+    ) -> Result<Vec<(&'static str, String)>, String> {
         println!("\n=== Configuração do Servidor ===\n");
 
-        props.server_name = self.prompt_string(
-            "Nome do Servidor",
-            &props.server_name,
-            "Digite o nome do servidor",
-        )?;
-
-        props.server_port = self.prompt_number(
-            "Porta do Servidor",
-            props.server_port,
-            "Digite a porta do servidor (1-65535)",
-        )?;
-
-        props.gamemode = self.prompt_options(
-            "Modo de Jogo",
-            &props.gamemode,
-            &["survival", "creative", "adventure"],
-            "Escolha o modo de jogo",
-        )?;
-
-        props.difficulty = self.prompt_options(
-            "Dificuldade",
-            &props.difficulty,
-            &["peaceful", "easy", "normal", "hard"],
-            "Escolha a dificuldade",
-        )?;
-
-        props.allow_cheats = self.prompt_bool("Permitir Cheats", props.allow_cheats)?;
-        props.max_players = self.prompt_number(
-            "Máximo de Jogadores",
-            props.max_players,
-            "Digite o número máximo de jogadores",
-        )?;
-        props.online_mode = self.prompt_bool("Modo Online", props.online_mode)?;
-        props.white_list = self.prompt_bool("Usar Whitelist", props.white_list)?;
-
-        Ok(props)
+        let mut updates = Vec::new();
+        for property in properties::PROPERTIES {
+            let Some(label) = property.label else {
+                continue;
+            };
+            let label = if properties::needs_restart(property.key) {
+                format!("{} (exige reinício)", label)
+            } else {
+                label.to_string()
+            };
+            let label = label.as_str();
+            let value = current
+                .get(property.key)
+                .map(|value| value.as_str())
+                .unwrap_or(property.default);
+            let value = match &property.kind {
+                PropertyKind::Text | PropertyKind::FolderName => {
+                    self.prompt_string(label, value, property.help)?
+                }
+                PropertyKind::Number { .. } | PropertyKind::Decimal => {
+                    self.prompt_number(property.key, label, value, property.help)?
+                }
+                PropertyKind::Bool => self.prompt_bool(label, value == "true")?.to_string(),
+                PropertyKind::Choice(options) => {
+                    self.prompt_options(label, value, options, property.help)?
+                }
+            };
+            updates.push((property.key, value));
+        }
+        Ok(updates)
     }
 
     fn prompt_string(&self, label: &str, current: &str, help: &str) -> Result<String, String> {
@@ -267,10 +396,13 @@ impl ConfigManager {
         })
     }
 
-    fn prompt_number<T>(&self, label: &str, current: T, help: &str) -> Result<T, String>
-    where
-        T: std::str::FromStr + std::fmt::Display,
-    {
+    fn prompt_number(
+        &self,
+        key: &str,
+        label: &str,
+        current: &str,
+        help: &str,
+    ) -> Result<String, String> {
         println!("{} [{}]", label, current);
         println!("({})", help);
         let mut input = String::new();
@@ -278,13 +410,12 @@ impl ConfigManager {
             .read_line(&mut input)
             .map_err(|e| format!("Erro ao ler entrada: {}", e))?;
         let input = input.trim();
-        Ok(if input.is_empty() {
-            current
-        } else {
-            input
-                .parse()
-                .map_err(|_| format!("Valor inválido para {}", label))?
-        })
+        if input.is_empty() {
+            return Ok(current.to_string());
+        }
+        properties::validate(key, input)
+            .map_err(|e| format!("Valor inválido para {}: {}", label, e))?;
+        Ok(input.to_string())
     }
 
     fn prompt_bool(&self, label: &str, current: bool) -> Result<bool, String> {
@@ -336,12 +467,11 @@ impl ConfigManager {
         })
     }
 
-    fn save_server_properties(&self, props: &ServerProperties) -> Result<(), String> {
-        let content = self.properties_to_string(props)?;
-        let path = self.work_dir.join("server.properties");
-        fs::write(&path, content)
-            .map_err(|e| format!("Erro ao salvar server.properties: {}", e))?;
+    fn save_server_properties(&self, updates: &[(&str, String)]) -> Result<Vec<String>, String> {
+        // Reescreve só as chaves editáveis; propriedades que o gerenciador não
+        // conhece continuam no arquivo
+        let changed = self.set_properties(updates)?;
         println!("Configurações salvas com sucesso!");
-        Ok(())
+        Ok(changed)
     }
 }
@@ -0,0 +1,210 @@
+use crate::events::{EventBus, ServerEvent};
+use crate::instances::Instance;
+use crate::players::PlayerDatabase;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, ExternalPrinter, Helper};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const EXIT_COMMAND: &str = ":sair";
+
+// Comandos conhecidos do Bedrock Dedicated Server, usados no autocompletar
+const KNOWN_COMMANDS: &[&str] = &[
+    "allowlist",
+    "changesetting",
+    "clear",
+    "clearspawnpoint",
+    "deop",
+    "difficulty",
+    "effect",
+    "enchant",
+    "gamemode",
+    "gamerule",
+    "give",
+    "kick",
+    "kill",
+    "list",
+    "me",
+    "op",
+    "permission",
+    "reload",
+    "save",
+    "say",
+    "scoreboard",
+    "setmaxplayers",
+    "setworldspawn",
+    "spawnpoint",
+    "summon",
+    "tag",
+    "tell",
+    "tellraw",
+    "time",
+    "title",
+    "tp",
+    "weather",
+    "whitelist",
+    "xp",
+];
+
+struct ConsoleHelper {
+    work_dir: PathBuf,
+}
+
+impl ConsoleHelper {
+    fn online_players(&self) -> Vec<String> {
+        PlayerDatabase::load(&self.work_dir)
+            .map(|db| {
+                db.online_players()
+                    .into_iter()
+                    .map(|record| record.name.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Completer for ConsoleHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let before_cursor = &line[..pos];
+        let start = before_cursor.rfind(' ').map(|i| i + 1).unwrap_or(0);
+        let word = &before_cursor[start..];
+
+        // A primeira palavra é o comando; as demais costumam ser jogadores
+        let candidates: Vec<String> = if start == 0 {
+            let (prefix, word) = match word.strip_prefix('/') {
+                Some(word) => ("/", word),
+                None => ("", word),
+            };
+            KNOWN_COMMANDS
+                .iter()
+                .filter(|command| command.starts_with(word))
+                .map(|command| format!("{}{}", prefix, command))
+                .collect()
+        } else {
+            self.online_players()
+                .into_iter()
+                .filter(|name| name.to_lowercase().starts_with(&word.to_lowercase()))
+                .map(|name| {
+                    if name.contains(' ') {
+                        format!("\"{}\"", name)
+                    } else {
+                        name
+                    }
+                })
+                .collect()
+        };
+
+        let pairs = candidates
+            .into_iter()
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: candidate,
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for ConsoleHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ConsoleHelper {}
+
+impl Validator for ConsoleHelper {}
+
+impl Helper for ConsoleHelper {}
+
+// Modo console: mostra a saída do servidor em tempo real e envia o que for
+// digitado como comando. Sair do console não para o servidor.
+pub fn run_console(instance: &Instance, events: &EventBus) -> Result<(), String> {
+    let work_dir = &instance.work_dir;
+    let admin = instance.admin();
+    let mut editor: Editor<ConsoleHelper, DefaultHistory> =
+        Editor::new().map_err(|e| format!("Erro ao abrir console: {}", e))?;
+    editor.set_helper(Some(ConsoleHelper {
+        work_dir: work_dir.clone(),
+    }));
+
+    let history_path = work_dir.join("logs").join("console_history.txt");
+    let _ = editor.load_history(&history_path);
+
+    // Imprime as linhas do console sem atrapalhar o que está sendo digitado
+    let mut printer = editor
+        .create_external_printer()
+        .map_err(|e| format!("Erro ao abrir console: {}", e))?;
+    let receiver = events.subscribe();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_flag = Arc::clone(&stop);
+    let output_thread = thread::spawn(move || {
+        while !stop_flag.load(Ordering::Relaxed) {
+            match receiver.recv_timeout(Duration::from_millis(200)) {
+                Ok(ServerEvent::ConsoleLine(line)) => {
+                    if printer.print(line).is_err() {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    });
+
+    println!("\n=== Console do Servidor ===");
+    println!("Digite comandos do servidor (Tab completa comandos e jogadores online).");
+    println!(
+        "Use {} ou Ctrl+D para voltar ao menu sem parar o servidor.\n",
+        EXIT_COMMAND
+    );
+
+    loop {
+        match editor.readline("> ") {
+            Ok(line) => {
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                if line == EXIT_COMMAND {
+                    break;
+                }
+                // Parar pelo console deixaria o gerenciador achando que o
+                // servidor ainda está em execução
+                if line.trim_start_matches('/').eq_ignore_ascii_case("stop") {
+                    println!("Use a opção 'Parar Servidor' do menu para parar o servidor.");
+                    continue;
+                }
+                let _ = editor.add_history_entry(line);
+                if let Err(e) = admin.run_raw(line) {
+                    println!("Erro ao enviar comando: {}", e);
+                }
+            }
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                println!("Erro ao ler entrada: {}", e);
+                break;
+            }
+        }
+    }
+
+    let _ = editor.save_history(&history_path);
+    stop.store(true, Ordering::Relaxed);
+    let _ = output_thread.join();
+    Ok(())
+}
@@ -0,0 +1,472 @@
+use crate::audit;
+use crate::events::{self, ServerEvent};
+use crate::instances::{self, Instance, InstanceRegistry};
+use crate::server::Server;
+use crate::service_lock::Claim;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::path::PathBuf;
+
+#[cfg(unix)]
+use std::fs;
+#[cfg(unix)]
+use std::io::{BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(unix)]
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+#[cfg(unix)]
+use std::time::Instant;
+
+// Protocolo do socket de controle: uma requisição JSON por linha, respondida
+// com uma linha JSON. Ex: {"request":"start","instance":"survival"}
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum DaemonRequest {
+    Status,
+    Start { instance: String },
+    Stop { instance: String },
+    Shutdown,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DaemonResponse {
+    pub ok: bool,
+    pub message: String,
+    #[serde(default)]
+    pub instances: Vec<InstanceStatus>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InstanceStatus {
+    pub name: String,
+    pub running: bool,
+    pub supervised: bool,
+    pub pid: Option<u32>,
+    pub port: u16,
+    pub port_v6: u16,
+}
+
+impl DaemonResponse {
+    fn ok(message: impl Into<String>) -> Self {
+        DaemonResponse {
+            ok: true,
+            message: message.into(),
+            instances: Vec::new(),
+        }
+    }
+
+    fn error(message: impl Into<String>) -> Self {
+        DaemonResponse {
+            ok: false,
+            message: message.into(),
+            instances: Vec::new(),
+        }
+    }
+}
+
+pub fn socket_path() -> PathBuf {
+    env::current_dir()
+        .expect("Erro ao obter diretório atual")
+        .join("manager.sock")
+}
+
+// Servidores acompanhados pelo daemon, cada um com seus serviços (banimentos,
+// estatísticas e gamerules) ligados aos eventos do console
+struct Supervisor {
+    servers: BTreeMap<String, Server>,
+    // Instâncias sendo iniciadas ou paradas fora da trava do supervisor
+    busy: BTreeSet<String>,
+    // Servidores que caíram, mantidos até o próximo ciclo para os serviços
+    // receberem o aviso de queda anunciado em events.jsonl
+    crashed: Vec<Server>,
+}
+
+impl Supervisor {
+    // Passa a acompanhar servidores iniciados fora do daemon e esquece os que
+    // encerraram
+    fn refresh(&mut self) -> Result<(), String> {
+        self.crashed.clear();
+        let registry = InstanceRegistry::load()?;
+        for instance in registry.list() {
+            if self.busy.contains(&instance.name) {
+                continue;
+            }
+            let running = instance.is_running();
+            let supervised = self.servers.contains_key(&instance.name);
+            if running && !supervised {
+                let mut server = Server::for_instance(instance);
+                match server.attach_to_existing() {
+                    Ok(_) => {
+                        instances::spawn_services(server.events(), instance, Claim::TakeOver);
+                        self.servers.insert(instance.name.clone(), server);
+                    }
+                    Err(e) => println!("Não foi possível acompanhar {}: {}", instance.name, e),
+                }
+            } else if !running && supervised {
+                let server = self.servers.remove(&instance.name);
+                if events::stopped_cleanly(&events::console_log_path(&instance.work_dir)) {
+                    println!("Instância {} encerrou", instance.name);
+                } else if let Some(server) = server {
+                    println!("Instância {} encerrou inesperadamente", instance.name);
+                    events::announce(&instance.work_dir, &ServerEvent::ServerCrashed);
+                    self.crashed.push(server);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn status(&self) -> Result<DaemonResponse, String> {
+        let registry = InstanceRegistry::load()?;
+        let instances = registry
+            .list()
+            .into_iter()
+            .map(|instance| {
+                let lock = instance.running_server();
+                let (port, port_v6) = instance.ports();
+                InstanceStatus {
+                    name: instance.name.clone(),
+                    running: lock.is_some(),
+                    supervised: self.servers.contains_key(&instance.name),
+                    pid: lock.map(|lock| lock.pid),
+                    port,
+                    port_v6,
+                }
+            })
+            .collect();
+        Ok(DaemonResponse {
+            instances,
+            ..DaemonResponse::ok("ok")
+        })
+    }
+
+    // Reserva a instância para uma operação demorada
+    fn begin(&mut self, name: &str) -> Result<(), String> {
+        if !self.busy.insert(name.to_string()) {
+            return Err(format!(
+                "A instância {} já está sendo iniciada ou parada",
+                name
+            ));
+        }
+        Ok(())
+    }
+}
+
+// Iniciar e parar podem levar dezenas de segundos enquanto o mundo carrega ou
+// é salvo; o supervisor só fica travado para separar e devolver o servidor
+#[cfg(unix)]
+fn handle(
+    supervisor: &Mutex<Supervisor>,
+    request: DaemonRequest,
+) -> Result<DaemonResponse, String> {
+    match request {
+        DaemonRequest::Status => supervisor.lock().unwrap().status(),
+        DaemonRequest::Start { instance } => {
+            let registry = InstanceRegistry::load()?;
+            let instance = registry.get(&instance)?;
+            supervisor.lock().unwrap().begin(&instance.name)?;
+            let result = start_supervised(&registry, instance);
+            let mut supervisor = supervisor.lock().unwrap();
+            supervisor.busy.remove(&instance.name);
+            supervisor.servers.insert(instance.name.clone(), result?);
+            Ok(DaemonResponse::ok(format!(
+                "Instância {} iniciada",
+                instance.name
+            )))
+        }
+        DaemonRequest::Stop { instance } => {
+            let registry = InstanceRegistry::load()?;
+            let instance = registry.get(&instance)?;
+            let mut server = {
+                let mut supervisor = supervisor.lock().unwrap();
+                supervisor.begin(&instance.name)?;
+                supervisor.servers.remove(&instance.name)
+            };
+            let result = stop_supervised(instance, &mut server);
+            let mut supervisor = supervisor.lock().unwrap();
+            supervisor.busy.remove(&instance.name);
+            // Se a parada falhou, o servidor continua acompanhado
+            if let Some(server) = server {
+                supervisor.servers.insert(instance.name.clone(), server);
+            }
+            result?;
+            Ok(DaemonResponse::ok(format!(
+                "Instância {} parada",
+                instance.name
+            )))
+        }
+        DaemonRequest::Shutdown => Ok(DaemonResponse::ok(
+            "Daemon encerrado; os servidores continuam em execução",
+        )),
+    }
+}
+
+#[cfg(unix)]
+fn start_supervised(registry: &InstanceRegistry, instance: &Instance) -> Result<Server, String> {
+    if instance.is_running() {
+        return Err(format!("A instância {} já está em execução", instance.name));
+    }
+    registry.check_instance_ports(instance)?;
+    // Os serviços assinam os eventos antes do início para receber o
+    // "Server started"; se o início falhar, somem junto com o servidor
+    let mut server = Server::for_instance(instance);
+    instances::spawn_services(server.events(), instance, Claim::TakeOver);
+    server.start()?;
+    Ok(server)
+}
+
+// Para o servidor, tirando-o de `server` só se a parada der certo
+#[cfg(unix)]
+fn stop_supervised(instance: &Instance, server: &mut Option<Server>) -> Result<(), String> {
+    if !instance.is_running() {
+        return Err(format!(
+            "A instância {} não está em execução",
+            instance.name
+        ));
+    }
+    match server {
+        Some(supervised) => {
+            supervised.stop()?;
+            *server = None;
+            Ok(())
+        }
+        None => Server::for_instance(instance).stop(),
+    }
+}
+
+// Executa o daemon em primeiro plano até receber `shutdown`. Encerrar o daemon
+// não para os servidores; na próxima execução ele volta a acompanhá-los.
+#[cfg(unix)]
+pub fn run() -> Result<(), String> {
+    let path = socket_path();
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            return Err("O daemon já está em execução".to_string());
+        }
+        // Socket deixado por um daemon que não encerrou corretamente
+        fs::remove_file(&path).map_err(|e| format!("Erro ao remover socket antigo: {}", e))?;
+    }
+
+    let listener = UnixListener::bind(&path)
+        .map_err(|e| format!("Erro ao abrir socket de controle: {}", e))?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("Erro ao definir permissões do socket: {}", e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Erro ao configurar socket de controle: {}", e))?;
+
+    audit::set_default_actor("daemon");
+    let supervisor = Arc::new(Mutex::new(Supervisor {
+        servers: BTreeMap::new(),
+        busy: BTreeSet::new(),
+        crashed: Vec::new(),
+    }));
+    let shutdown = Arc::new(AtomicBool::new(false));
+    println!("Daemon ouvindo em {}", path.display());
+
+    let mut last_refresh: Option<Instant> = None;
+    while !shutdown.load(Ordering::Relaxed) {
+        if last_refresh.is_none_or(|at| at.elapsed() >= Duration::from_secs(5)) {
+            if let Err(e) = supervisor.lock().unwrap().refresh() {
+                println!("Erro ao verificar instâncias: {}", e);
+            }
+            last_refresh = Some(Instant::now());
+        }
+
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let supervisor = Arc::clone(&supervisor);
+                let shutdown = Arc::clone(&shutdown);
+                thread::spawn(move || handle_client(stream, &supervisor, &shutdown));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) => println!("Erro ao aceitar conexão: {}", e),
+        }
+    }
+
+    let _ = fs::remove_file(&path);
+    println!("Daemon encerrado");
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn run() -> Result<(), String> {
+    Err("O modo daemon está disponível apenas no Linux".to_string())
+}
+
+#[cfg(unix)]
+fn handle_client(stream: UnixStream, supervisor: &Mutex<Supervisor>, shutdown: &AtomicBool) {
+    let _ = stream.set_nonblocking(false);
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<DaemonRequest>(&line) {
+            Ok(request) => {
+                let is_shutdown = matches!(request, DaemonRequest::Shutdown);
+                let response = handle(supervisor, request).unwrap_or_else(DaemonResponse::error);
+                if is_shutdown {
+                    shutdown.store(true, Ordering::Relaxed);
+                }
+                response
+            }
+            Err(e) => DaemonResponse::error(format!("Requisição inválida: {}", e)),
+        };
+
+        let Ok(content) = serde_json::to_string(&response) else {
+            break;
+        };
+        if writeln!(writer, "{}", content).is_err() {
+            break;
+        }
+    }
+}
+
+// Envia uma requisição ao daemon e devolve a resposta; erros do daemon viram Err
+#[cfg(unix)]
+pub fn request(request: &DaemonRequest) -> Result<DaemonResponse, String> {
+    let mut stream = UnixStream::connect(socket_path())
+        .map_err(|e| format!("Não foi possível conectar ao daemon: {}", e))?;
+    // Parar um servidor pode levar até 30 segundos enquanto o mundo é salvo
+    stream
+        .set_read_timeout(Some(Duration::from_secs(60)))
+        .map_err(|e| format!("Erro ao configurar conexão: {}", e))?;
+
+    let content = serde_json::to_string(request)
+        .map_err(|e| format!("Erro ao serializar requisição: {}", e))?;
+    writeln!(stream, "{}", content).map_err(|e| format!("Erro ao enviar requisição: {}", e))?;
+
+    let mut line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut line)
+        .map_err(|e| format!("Erro ao ler resposta do daemon: {}", e))?;
+    let response: DaemonResponse =
+        serde_json::from_str(&line).map_err(|e| format!("Resposta inválida do daemon: {}", e))?;
+
+    if response.ok {
+        Ok(response)
+    } else {
+        Err(response.message)
+    }
+}
+
+#[cfg(windows)]
+pub fn request(_request: &DaemonRequest) -> Result<DaemonResponse, String> {
+    Err("O modo daemon está disponível apenas no Linux".to_string())
+}
+
+#[cfg(unix)]
+pub fn is_running() -> bool {
+    UnixStream::connect(socket_path()).is_ok()
+}
+
+#[cfg(windows)]
+pub fn is_running() -> bool {
+    false
+}
+
+// Inicia a instância pelo daemon, se ele estiver ativo, ou diretamente
+pub fn start_instance(instance: &Instance) -> Result<(), String> {
+    let result = request_start(instance);
+    audit::record(&instance.work_dir, "server.start", json!({}), &result);
+    result
+}
+
+fn request_start(instance: &Instance) -> Result<(), String> {
+    if is_running() {
+        request(&DaemonRequest::Start {
+            instance: instance.name.clone(),
+        })?;
+        return Ok(());
+    }
+    InstanceRegistry::load()?.check_instance_ports(instance)?;
+    Server::for_instance(instance).start()
+}
+
+pub fn stop_instance(instance: &Instance) -> Result<(), String> {
+    let result = request_stop(instance);
+    audit::record(&instance.work_dir, "server.stop", json!({}), &result);
+    result
+}
+
+fn request_stop(instance: &Instance) -> Result<(), String> {
+    if is_running() {
+        request(&DaemonRequest::Stop {
+            instance: instance.name.clone(),
+        })?;
+        return Ok(());
+    }
+    Server::for_instance(instance).stop()
+}
+
+// Avisa os jogadores com antecedência e reinicia a instância. Usado pelo
+// watchdog e pelas tarefas agendadas.
+pub fn restart_with_warnings(
+    instance: &Instance,
+    warning_secs: u64,
+    reason: &str,
+) -> Result<(), String> {
+    let admin = instance.admin();
+    let marks: Vec<u64> = std::iter::once(warning_secs)
+        .chain(
+            [300, 60, 30, 10, 5]
+                .into_iter()
+                .filter(|mark| *mark < warning_secs),
+        )
+        .collect();
+    for (i, mark) in marks.iter().enumerate() {
+        let _ = admin.say(&format!(
+            "O servidor será reiniciado em {} segundos ({})",
+            mark, reason
+        ));
+        let next = marks.get(i + 1).copied().unwrap_or(0);
+        thread::sleep(Duration::from_secs(mark - next));
+    }
+
+    if instance.is_running() {
+        stop_instance(instance)?;
+    }
+    start_instance(instance)
+}
+
+pub fn print_status(response: &DaemonResponse) {
+    println!("\n=== Daemon ===");
+    for instance in &response.instances {
+        let status = match (instance.running, instance.pid) {
+            (true, Some(pid)) => format!("em execução, PID {}", pid),
+            (true, None) => "em execução".to_string(),
+            (false, _) => "parada".to_string(),
+        };
+        println!(
+            "- {} [{}] portas {}/{}{}",
+            instance.name,
+            status,
+            instance.port,
+            instance.port_v6,
+            if instance.supervised {
+                " (supervisionada)"
+            } else {
+                ""
+            }
+        );
+    }
+}
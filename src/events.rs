@@ -0,0 +1,393 @@
+use serde_json::{json, Value};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub enum ServerEvent {
+    ConsoleLine(String),
+    ServerStarted,
+    ServerStopped,
+    PlayerConnected { name: String, xuid: String },
+    PlayerDisconnected { name: String, xuid: String },
+    ChatMessage { name: String, message: String },
+    BackupCompleted { name: String },
+    BackupFailed { error: String },
+    // O processo encerrou sem o "Quit correctly" no console
+    ServerCrashed,
+    UpdateAvailable { version: String },
+    UpdateApplied { version: String },
+    // Limite do watchdog ultrapassado (resource: memory, cpu ou disk)
+    ResourceAlert { resource: String, message: String },
+}
+
+impl ServerEvent {
+    // Representação usada pelo WebSocket e pelos webhooks
+    pub fn to_json(&self) -> Value {
+        match self {
+            ServerEvent::ConsoleLine(line) => json!({ "type": "console", "line": line }),
+            ServerEvent::ServerStarted => json!({ "type": "server_started" }),
+            ServerEvent::ServerStopped => json!({ "type": "server_stopped" }),
+            ServerEvent::ServerCrashed => json!({ "type": "server_crashed" }),
+            ServerEvent::PlayerConnected { name, xuid } => {
+                json!({ "type": "player_joined", "name": name, "xuid": xuid })
+            }
+            ServerEvent::PlayerDisconnected { name, xuid } => {
+                json!({ "type": "player_left", "name": name, "xuid": xuid })
+            }
+            ServerEvent::ChatMessage { name, message } => {
+                json!({ "type": "chat", "name": name, "message": message })
+            }
+            ServerEvent::BackupCompleted { name } => {
+                json!({ "type": "backup_completed", "name": name })
+            }
+            ServerEvent::BackupFailed { error } => {
+                json!({ "type": "backup_failed", "error": error })
+            }
+            ServerEvent::UpdateAvailable { version } => {
+                json!({ "type": "update_available", "version": version })
+            }
+            ServerEvent::UpdateApplied { version } => {
+                json!({ "type": "update_applied", "version": version })
+            }
+            ServerEvent::ResourceAlert { resource, message } => {
+                json!({ "type": "resource_alert", "resource": resource, "message": message })
+            }
+        }
+    }
+
+    // Inverso de to_json, usado para ler os eventos anunciados em events.jsonl
+    pub fn from_json(value: &Value) -> Option<ServerEvent> {
+        let field = |key: &str| value[key].as_str().map(str::to_string);
+        let event = match value["type"].as_str()? {
+            "console" => ServerEvent::ConsoleLine(field("line")?),
+            "server_started" => ServerEvent::ServerStarted,
+            "server_stopped" => ServerEvent::ServerStopped,
+            "server_crashed" => ServerEvent::ServerCrashed,
+            "player_joined" => ServerEvent::PlayerConnected {
+                name: field("name")?,
+                xuid: field("xuid").unwrap_or_default(),
+            },
+            "player_left" => ServerEvent::PlayerDisconnected {
+                name: field("name")?,
+                xuid: field("xuid").unwrap_or_default(),
+            },
+            "chat" => ServerEvent::ChatMessage {
+                name: field("name")?,
+                message: field("message")?,
+            },
+            "backup_completed" => ServerEvent::BackupCompleted {
+                name: field("name")?,
+            },
+            "backup_failed" => ServerEvent::BackupFailed {
+                error: field("error")?,
+            },
+            "update_available" => ServerEvent::UpdateAvailable {
+                version: field("version")?,
+            },
+            "update_applied" => ServerEvent::UpdateApplied {
+                version: field("version")?,
+            },
+            "resource_alert" => ServerEvent::ResourceAlert {
+                resource: field("resource")?,
+                message: field("message")?,
+            },
+            _ => return None,
+        };
+        Some(event)
+    }
+}
+
+// Interpreta uma linha do console do Bedrock Dedicated Server, por exemplo:
+// [2024-01-01 12:00:00:000 INFO] Player connected: Steve, xuid: 2535400000000000
+pub fn parse_console_line(line: &str) -> Option<ServerEvent> {
+    let message = match line.find("] ") {
+        Some(pos) if line.starts_with('[') => &line[pos + 2..],
+        _ => line,
+    };
+
+    if message.starts_with("Server started.") {
+        return Some(ServerEvent::ServerStarted);
+    }
+
+    if message.starts_with("Quit correctly") {
+        return Some(ServerEvent::ServerStopped);
+    }
+
+    if let Some(rest) = message.strip_prefix("Player connected: ") {
+        let (name, xuid) = parse_player_fields(rest)?;
+        return Some(ServerEvent::PlayerConnected { name, xuid });
+    }
+
+    if let Some(rest) = message.strip_prefix("Player disconnected: ") {
+        let (name, xuid) = parse_player_fields(rest)?;
+        return Some(ServerEvent::PlayerDisconnected { name, xuid });
+    }
+
+    // Chat dos jogadores, quando o servidor o registra no console:
+    // "<Steve> olá" ou "[Chat] <Steve> olá"
+    let chat = message.strip_prefix("[Chat] ").unwrap_or(message);
+    if let Some((name, text)) = chat
+        .strip_prefix('<')
+        .and_then(|rest| rest.split_once("> "))
+    {
+        if !name.is_empty() {
+            return Some(ServerEvent::ChatMessage {
+                name: name.to_string(),
+                message: text.trim().to_string(),
+            });
+        }
+    }
+
+    None
+}
+
+fn parse_player_fields(rest: &str) -> Option<(String, String)> {
+    let (name, fields) = rest.split_once(", xuid: ")?;
+    let xuid = fields.split(',').next().unwrap_or("").trim();
+    Some((name.trim().to_string(), xuid.to_string()))
+}
+
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Sender<ServerEvent>>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        EventBus::default()
+    }
+
+    pub fn subscribe(&self) -> Receiver<ServerEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    pub fn publish(&self, event: ServerEvent) {
+        // Remove assinantes cujo receptor já foi descartado
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
+
+pub fn console_log_path(work_dir: &Path) -> PathBuf {
+    work_dir.join("logs").join("console.log")
+}
+
+pub fn events_log_path(work_dir: &Path) -> PathBuf {
+    work_dir.join("logs").join("events.jsonl")
+}
+
+// Eventos gerados pelo próprio gerenciador (backups, atualizações, alertas do
+// watchdog, quedas) não passam pelo console: vão para logs/events.jsonl, que
+// os ConsoleWatcher de todos os processos da instância (daemon, menu, API)
+// acompanham e repassam aos seus barramentos
+pub fn announce(work_dir: &Path, event: &ServerEvent) {
+    let path = events_log_path(work_dir);
+    let result = fs::create_dir_all(work_dir.join("logs"))
+        .and_then(|_| OpenOptions::new().create(true).append(true).open(&path))
+        .and_then(|mut file| file.write_all(format!("{}\n", event.to_json()).as_bytes()));
+    if let Err(e) = result {
+        println!("Erro ao registrar evento: {}", e);
+    }
+}
+
+// Acompanha o log do console (como `tail -f`) e publica cada linha no
+// barramento, junto com os eventos anunciados em events.jsonl
+pub struct ConsoleWatcher {
+    stop: Arc<AtomicBool>,
+}
+
+impl ConsoleWatcher {
+    pub fn spawn(work_dir: &Path, bus: EventBus) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = Arc::clone(&stop);
+        let log_path = console_log_path(work_dir);
+        let events_path = events_log_path(work_dir);
+
+        thread::spawn(move || {
+            // Começa do fim dos arquivos para não reprocessar sessões anteriores
+            let end = |path: &Path| fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            let mut position = end(&log_path);
+            let mut events_position = end(&events_path);
+
+            while !stop_flag.load(Ordering::Relaxed) {
+                if let Ok(new_position) = read_new_lines(&log_path, position, &bus) {
+                    position = new_position;
+                }
+                if let Ok(new_position) = read_new_events(&events_path, events_position, &bus) {
+                    events_position = new_position;
+                }
+                thread::sleep(Duration::from_millis(250));
+            }
+        });
+
+        ConsoleWatcher { stop }
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for ConsoleWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn read_new_lines(log_path: &Path, position: u64, bus: &EventBus) -> Result<u64, String> {
+    let (lines, position) = read_console_since(log_path, position)?;
+    for line in lines {
+        let event = parse_console_line(&line);
+        bus.publish(ServerEvent::ConsoleLine(line));
+        if let Some(event) = event {
+            bus.publish(event);
+        }
+    }
+    Ok(position)
+}
+
+fn read_new_events(events_path: &Path, position: u64, bus: &EventBus) -> Result<u64, String> {
+    let (lines, position) = read_console_since(events_path, position)?;
+    for line in lines {
+        let event = serde_json::from_str(&line)
+            .ok()
+            .and_then(|value| ServerEvent::from_json(&value));
+        if let Some(event) = event {
+            bus.publish(event);
+        }
+    }
+    Ok(position)
+}
+
+// Diz se a última sessão registrada no log terminou com "Quit correctly".
+// Sem nenhuma das duas marcas não há como saber, e a parada é tida como normal.
+pub fn stopped_cleanly(log_path: &Path) -> bool {
+    for line in read_console_tail(log_path, 200).iter().rev() {
+        match parse_console_line(line) {
+            Some(ServerEvent::ServerStopped) => return true,
+            Some(ServerEvent::ServerStarted) => return false,
+            _ => {}
+        }
+    }
+    true
+}
+
+// Últimas `count` linhas do log, usadas para mostrar o histórico recente a
+// quem acabou de se conectar
+pub fn read_console_tail(log_path: &Path, count: usize) -> Vec<String> {
+    const MAX_TAIL_BYTES: u64 = 256 * 1024;
+    let len = std::fs::metadata(log_path).map(|m| m.len()).unwrap_or(0);
+    let Ok((mut lines, _)) = read_console_since(log_path, len.saturating_sub(MAX_TAIL_BYTES))
+    else {
+        return Vec::new();
+    };
+    // A primeira linha pode ter começado antes do trecho lido
+    if len > MAX_TAIL_BYTES && !lines.is_empty() {
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(count);
+    lines.split_off(skip)
+}
+
+// Lê as linhas completas escritas no log a partir de `position`, devolvendo
+// também a nova posição para a próxima leitura
+pub fn read_console_since(log_path: &Path, position: u64) -> Result<(Vec<String>, u64), String> {
+    let mut file = File::open(log_path).map_err(|e| format!("Erro ao abrir log: {}", e))?;
+    let len = file
+        .metadata()
+        .map_err(|e| format!("Erro ao ler log: {}", e))?
+        .len();
+
+    // O arquivo foi truncado ou recriado: recomeça do início
+    let position = if len < position { 0 } else { position };
+    if len == position {
+        return Ok((Vec::new(), position));
+    }
+
+    file.seek(SeekFrom::Start(position))
+        .map_err(|e| format!("Erro ao ler log: {}", e))?;
+    let mut reader = BufReader::new(file);
+    let mut consumed = position;
+    let mut buffer = Vec::new();
+    let mut lines = Vec::new();
+
+    loop {
+        buffer.clear();
+        let read = reader
+            .read_until(b'\n', &mut buffer)
+            .map_err(|e| format!("Erro ao ler log: {}", e))?;
+        // Linhas incompletas ficam para a próxima leitura
+        if read == 0 || !buffer.ends_with(b"\n") {
+            break;
+        }
+        consumed += read as u64;
+
+        let line = String::from_utf8_lossy(&buffer);
+        let line = line.trim_end_matches(['\r', '\n']);
+        if !line.is_empty() {
+            lines.push(line.to_string());
+        }
+    }
+
+    Ok((lines, consumed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn json_round_trip() {
+        let events = [
+            ServerEvent::ServerCrashed,
+            ServerEvent::PlayerConnected {
+                name: "Steve".to_string(),
+                xuid: "2535400000000000".to_string(),
+            },
+            ServerEvent::BackupCompleted {
+                name: "worlds-20240101-120000-000.zip".to_string(),
+            },
+            ServerEvent::ResourceAlert {
+                resource: "disk".to_string(),
+                message: "Pouco espaço".to_string(),
+            },
+        ];
+        for event in events {
+            let parsed = ServerEvent::from_json(&event.to_json()).unwrap();
+            assert_eq!(parsed.to_json(), event.to_json());
+        }
+        assert!(ServerEvent::from_json(&json!({ "type": "desconhecido" })).is_none());
+        assert!(ServerEvent::from_json(&json!({ "type": "backup_completed" })).is_none());
+    }
+
+    #[test]
+    fn watcher_publishes_announced_events() {
+        let dir = test_util::temp_dir("events");
+        let bus = EventBus::new();
+        let receiver = bus.subscribe();
+        let _watcher = ConsoleWatcher::spawn(&dir, bus.clone());
+        // Dá tempo ao watcher de registrar o fim atual dos arquivos
+        thread::sleep(Duration::from_millis(300));
+
+        announce(
+            &dir,
+            &ServerEvent::BackupFailed {
+                error: "sem espaço".to_string(),
+            },
+        );
+        let event = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(event, ServerEvent::BackupFailed { error } if error == "sem espaço"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// Travas mais velhas que isso sobraram de um processo que morreu no meio
+const STALE_LOCK: Duration = Duration::from_secs(30);
+
+// Trava entre processos (daemon, menu, API) feita com a criação exclusiva de
+// um arquivo. Espera até 5s por quem a tem e é liberada ao sair de escopo.
+pub struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    // `what` nomeia o que é travado nas mensagens de erro ("o histórico")
+    pub fn acquire(path: &Path, what: &str) -> Result<Self, String> {
+        for _ in 0..200 {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)
+            {
+                Ok(_) => {
+                    return Ok(FileLock {
+                        path: path.to_path_buf(),
+                    })
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                        .is_some_and(|age| age > STALE_LOCK);
+                    if stale {
+                        let _ = fs::remove_file(path);
+                    } else {
+                        thread::sleep(Duration::from_millis(25));
+                    }
+                }
+                Err(e) => return Err(format!("Erro ao travar {}: {}", what, e)),
+            }
+        }
+        Err(format!("Tempo esgotado esperando a trava de {}", what))
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// Grava ao lado e renomeia, para que nenhum leitor veja o arquivo pela metade
+pub fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    fs::write(&temp, content)?;
+    fs::rename(&temp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn lock_is_exclusive_until_dropped() {
+        let dir = test_util::temp_dir("file-lock");
+        let path = dir.join(".lock");
+        let lock = FileLock::acquire(&path, "o teste").unwrap();
+        assert!(path.exists());
+        drop(lock);
+        assert!(!path.exists());
+        assert!(FileLock::acquire(&path, "o teste").is_ok());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn atomic_write_leaves_no_temp_file() {
+        let dir = test_util::temp_dir("file-lock-write");
+        let path = dir.join("state.json");
+        write_atomic(&path, "{}").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+        assert!(!dir.join("state.json.tmp").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::audit;
+use crate::bedrock_command::{validate_gamerule_name, BedrockCommand, GameruleValue};
+use crate::events::{EventBus, ServerEvent};
+use crate::instances::Instance;
+use crate::server_admin::ServerAdmin;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+// Estado desejado das gamerules, aplicado a cada início do servidor.
+// Exemplo de gamerules.json: { "keepInventory": true, "spawnradius": 5 }
+pub struct DesiredGamerules {
+    path: PathBuf,
+    rules: BTreeMap<String, GameruleValue>,
+}
+
+pub struct GameruleDiff {
+    pub rule: String,
+    pub desired: String,
+    pub current: Option<String>,
+}
+
+impl GameruleDiff {
+    pub fn is_in_sync(&self) -> bool {
+        self.current
+            .as_deref()
+            .is_some_and(|current| current.eq_ignore_ascii_case(&self.desired))
+    }
+}
+
+impl DesiredGamerules {
+    pub fn load(work_dir: &Path) -> Result<Self, String> {
+        let path = work_dir.join("gamerules.json");
+        let rules = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| format!("Erro ao ler gamerules.json: {}", e))?;
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Erro ao ler gamerules.json: {}", e))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(DesiredGamerules { path, rules })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(&self.rules)
+            .map_err(|e| format!("Erro ao serializar gamerules.json: {}", e))?;
+        fs::write(&self.path, content).map_err(|e| format!("Erro ao salvar gamerules.json: {}", e))
+    }
+
+    pub fn set(&mut self, rule: &str, value: &str) -> Result<(), String> {
+        let rule = validate_gamerule_name(rule)?;
+        let value: GameruleValue = value.parse()?;
+        let before = self.get(&rule);
+        // O servidor não diferencia maiúsculas; evita duplicar a mesma regra
+        self.rules
+            .retain(|existing, _| !existing.eq_ignore_ascii_case(&rule));
+        self.rules.insert(rule.clone(), value.clone());
+        let result = self.save();
+        audit::record(
+            self.work_dir(),
+            "gamerules.set",
+            json!({ "rule": rule, "before": before, "after": value.to_string() }),
+            &result,
+        );
+        result
+    }
+
+    pub fn unset(&mut self, rule: &str) -> Result<bool, String> {
+        let Some(before) = self.get(rule) else {
+            return Ok(false);
+        };
+        self.rules
+            .retain(|existing, _| !existing.eq_ignore_ascii_case(rule));
+        let result = self.save();
+        audit::record(
+            self.work_dir(),
+            "gamerules.unset",
+            json!({ "rule": rule, "before": before }),
+            &result,
+        );
+        result.map(|_| true)
+    }
+
+    fn get(&self, rule: &str) -> Option<String> {
+        self.rules
+            .iter()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(rule))
+            .map(|(_, value)| value.to_string())
+    }
+
+    fn work_dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("."))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    // Cada comando enviado entra na auditoria em nome do ator atual
+    pub fn apply(&self, admin: &ServerAdmin) -> Result<usize, String> {
+        for (rule, value) in &self.rules {
+            let command = BedrockCommand::Gamerule {
+                rule: rule.clone(),
+                value: value.clone(),
+            }
+            .to_string();
+            let result = admin.send_command(&command);
+            audit::record(
+                self.work_dir(),
+                "gamerule",
+                json!({ "command": command }),
+                &result,
+            );
+            result?;
+            thread::sleep(Duration::from_millis(50));
+        }
+        Ok(self.rules.len())
+    }
+
+    pub fn diff(&self, current: &BTreeMap<String, String>) -> Vec<GameruleDiff> {
+        self.rules
+            .iter()
+            .map(|(rule, value)| GameruleDiff {
+                rule: rule.clone(),
+                desired: value.to_string(),
+                current: current.get(&rule.to_lowercase()).cloned(),
+            })
+            .collect()
+    }
+}
+
+// Consulta os valores atuais com `/gamerule`; as chaves voltam em minúsculas
+pub fn query_current(admin: &ServerAdmin) -> Result<BTreeMap<String, String>, String> {
+    let lines = admin.execute_and_capture("/gamerule", Duration::from_millis(1000))?;
+    let rules = parse_gamerule_output(&lines);
+    if rules.is_empty() {
+        return Err("O servidor não respondeu ao /gamerule".to_string());
+    }
+    Ok(rules)
+}
+
+// O servidor responde em uma linha: "commandblockoutput = true, dodaylightcycle = true, ..."
+fn parse_gamerule_output(lines: &[String]) -> BTreeMap<String, String> {
+    let mut rules = BTreeMap::new();
+    for line in lines {
+        let message = match line.find("] ") {
+            Some(pos) if line.starts_with('[') => &line[pos + 2..],
+            _ => line.as_str(),
+        };
+
+        let pairs: Option<Vec<(String, String)>> = message
+            .split(", ")
+            .map(|pair| {
+                let (rule, value) = pair.split_once(" = ")?;
+                let rule = rule.trim();
+                if rule.is_empty() || !rule.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    return None;
+                }
+                Some((rule.to_lowercase(), value.trim().to_string()))
+            })
+            .collect();
+
+        if let Some(pairs) = pairs {
+            rules.extend(pairs);
+        }
+    }
+    rules
+}
+
+pub fn print_current(current: &BTreeMap<String, String>) {
+    println!("\n=== Gamerules Atuais ===");
+    for (rule, value) in current {
+        println!("{} = {}", rule, value);
+    }
+}
+
+pub fn print_diff(diff: &[GameruleDiff]) {
+    if diff.is_empty() {
+        println!("Nenhuma gamerule desejada definida em gamerules.json.");
+        return;
+    }
+
+    println!("\n=== Gamerules: Desejado x Atual ===");
+    for entry in diff {
+        let status = if entry.is_in_sync() {
+            "ok"
+        } else {
+            "DIFERENTE"
+        };
+        println!(
+            "{:<32} desejado: {:<8} atual: {:<8} [{}]",
+            entry.rule,
+            entry.desired,
+            entry.current.as_deref().unwrap_or("?"),
+            status
+        );
+    }
+}
+
+// Reaplica o estado desejado sempre que o console reporta o início do servidor
+pub fn spawn_applier(events: &EventBus, instance: Instance) {
+    let receiver = events.subscribe();
+
+    thread::spawn(move || {
+        let admin = instance.admin();
+        let work_dir = instance.work_dir;
+        audit::with_actor("gamerules", || {
+            for event in receiver {
+                if !matches!(event, ServerEvent::ServerStarted) {
+                    continue;
+                }
+
+                match DesiredGamerules::load(&work_dir) {
+                    Ok(desired) if desired.is_empty() => {}
+                    Ok(desired) => match desired.apply(&admin) {
+                        Ok(count) => {
+                            println!("{} gamerules aplicadas a partir de gamerules.json", count)
+                        }
+                        Err(e) => println!("Erro ao aplicar gamerules: {}", e),
+                    },
+                    Err(e) => println!("Erro ao carregar gamerules.json: {}", e),
+                }
+            }
+        })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn gamerule_output_is_parsed() {
+        let rules = parse_gamerule_output(&lines(&[
+            "[2024-01-01 12:00:00:000 INFO] commandblockoutput = true, keepInventory = false, spawnradius = 5",
+            "[2024-01-01 12:00:00:001 INFO] Player connected: Steve, xuid: 123",
+            "randomtickspeed = 1",
+        ]));
+        let expected: BTreeMap<String, String> = [
+            ("commandblockoutput", "true"),
+            ("keepinventory", "false"),
+            ("spawnradius", "5"),
+            ("randomtickspeed", "1"),
+        ]
+        .iter()
+        .map(|(rule, value)| (rule.to_string(), value.to_string()))
+        .collect();
+        assert_eq!(rules, expected);
+        assert!(parse_gamerule_output(&lines(&["Unknown command: gamerule"])).is_empty());
+    }
+
+    #[test]
+    fn diff_compares_ignoring_case() {
+        let dir = test_util::temp_dir("gamerules");
+        let mut desired = DesiredGamerules::load(&dir).unwrap();
+        desired.set("keepInventory", "true").unwrap();
+        desired.set("spawnradius", "5").unwrap();
+        desired.set("doFireTick", "false").unwrap();
+
+        let current: BTreeMap<String, String> = [("keepinventory", "TRUE"), ("spawnradius", "10")]
+            .iter()
+            .map(|(rule, value)| (rule.to_string(), value.to_string()))
+            .collect();
+        let in_sync: BTreeMap<String, bool> = desired
+            .diff(&current)
+            .iter()
+            .map(|diff| (diff.rule.clone(), diff.is_in_sync()))
+            .collect();
+        // Regra que o servidor não informou também conta como fora de sincronia
+        assert!(in_sync["keepInventory"]);
+        assert!(!in_sync["spawnradius"]);
+        assert!(!in_sync["doFireTick"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::file_lock::{self, FileLock};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+// Contadores e resultados recentes de uma instância, em state.json. Alimentam
+// as métricas e o painel de status.
+#[derive(Serialize, Deserialize, Default)]
+pub struct InstanceState {
+    #[serde(default)]
+    pub start_count: u64,
+    pub last_backup_at: Option<DateTime<Utc>>,
+    pub last_backup_success: Option<bool>,
+    // Motivo da pausa definida pelo watchdog; sem motivo, backups liberados
+    pub backups_paused: Option<String>,
+    // Recursos (memory, cpu ou disk) cujos alertas mantêm a pausa
+    #[serde(default, deserialize_with = "paused_by")]
+    pub backups_paused_by: BTreeSet<String>,
+    #[serde(default)]
+    pub update_in_progress: bool,
+    pub last_update_at: Option<DateTime<Utc>>,
+    pub last_update_success: Option<bool>,
+    // Versão nova já anunciada aos webhooks
+    pub update_available: Option<String>,
+    // Última execução de cada tarefa agendada
+    #[serde(default)]
+    pub job_runs: BTreeMap<String, DateTime<Utc>>,
+    // Reinício único pedido para um horário, feito pelo agendador
+    #[serde(default)]
+    pub pending_restart: Option<PendingRestart>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PendingRestart {
+    pub at: DateTime<Utc>,
+    pub reason: String,
+}
+
+// Versões anteriores guardavam um único recurso, ou null
+fn paused_by<'de, D>(deserializer: D) -> Result<BTreeSet<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum PausedBy {
+        Set(BTreeSet<String>),
+        Single(Option<String>),
+    }
+    Ok(match PausedBy::deserialize(deserializer)? {
+        PausedBy::Set(resources) => resources,
+        PausedBy::Single(resource) => resource.into_iter().collect(),
+    })
+}
+
+impl InstanceState {
+    // Sem arquivo, estado inicial; um arquivo ilegível vira erro
+    fn read(work_dir: &Path) -> Result<Self, String> {
+        let path = work_dir.join("state.json");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&content).map_err(|e| e.to_string())
+    }
+
+    pub fn load(work_dir: &Path) -> Self {
+        Self::read(work_dir).unwrap_or_default()
+    }
+
+    // Aplica `change` e grava o arquivo. Falhas são só avisadas: o estado é
+    // informativo e não deve interromper a operação que o atualiza. Um
+    // state.json ilegível fica como está, em vez de trocado pelos padrões.
+    pub fn update(work_dir: &Path, change: impl FnOnce(&mut Self)) {
+        // Instância ainda não instalada: não há onde gravar
+        if !work_dir.exists() {
+            return;
+        }
+        if let Err(e) = Self::try_update(work_dir, change) {
+            println!("Erro ao salvar state.json: {}", e);
+        }
+    }
+
+    fn try_update(work_dir: &Path, change: impl FnOnce(&mut Self)) -> Result<(), String> {
+        // Daemon, menu e API atualizam o mesmo arquivo
+        let _lock = FileLock::acquire(&work_dir.join("state.json.lock"), "o state.json")?;
+        let mut state = Self::read(work_dir)?;
+        change(&mut state);
+        let content = serde_json::to_string_pretty(&state).map_err(|e| e.to_string())?;
+        file_lock::write_atomic(&work_dir.join("state.json"), &content).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn update_keeps_unreadable_state() {
+        let dir = test_util::temp_dir("state-unreadable");
+        fs::write(dir.join("state.json"), "{\"start_count\": 7,").unwrap();
+        InstanceState::update(&dir, |state| state.start_count += 1);
+        assert_eq!(
+            fs::read_to_string(dir.join("state.json")).unwrap(),
+            "{\"start_count\": 7,"
+        );
+        assert!(!dir.join("state.json.lock").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn single_paused_resource_is_still_read() {
+        let dir = test_util::temp_dir("state-paused-by");
+        fs::write(
+            dir.join("state.json"),
+            "{\"backups_paused\": \"disco\", \"backups_paused_by\": \"disk\"}",
+        )
+        .unwrap();
+        let state = InstanceState::read(&dir).unwrap();
+        assert_eq!(
+            state.backups_paused_by,
+            BTreeSet::from(["disk".to_string()])
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn concurrent_updates_are_not_lost() {
+        let dir = test_util::temp_dir("state-concurrent");
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let dir = dir.clone();
+                std::thread::spawn(move || {
+                    InstanceState::update(&dir, |state| state.start_count += 1)
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(InstanceState::load(&dir).start_count, 8);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
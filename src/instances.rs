@@ -0,0 +1,518 @@
+use crate::ban_list;
+use crate::config_manager::ConfigManager;
+use crate::events::EventBus;
+use crate::gamerules;
+use crate::manager_config;
+use crate::players::{self, format_playtime};
+use crate::properties;
+use crate::scheduler;
+use crate::server_admin::ServerAdmin;
+use crate::server_lock::{self, ServerLock, ServerStatus};
+use crate::service_lock::{Claim, ServicesLock};
+use crate::watchdog;
+use crate::webhooks;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::process::Command;
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_INSTANCE: &str = "default";
+const DEFAULT_PORT: u16 = 19132;
+
+// Intervalo entre as conferências do services.lock
+const SERVICES_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Instance {
+    pub name: String,
+    pub work_dir: PathBuf,
+}
+
+impl Instance {
+    // A instância padrão mantém o nome de sessão antigo para continuar
+    // encontrando servidores iniciados por versões anteriores
+    pub fn session_name(&self) -> String {
+        let session = &manager_config::settings().screen_session;
+        if self.name == DEFAULT_INSTANCE {
+            session.clone()
+        } else {
+            format!("{}-{}", session, self.name)
+        }
+    }
+
+    pub fn admin(&self) -> ServerAdmin {
+        ServerAdmin::new(self.work_dir.clone(), self.session_name())
+    }
+
+    pub fn config_manager(&self) -> ConfigManager {
+        ConfigManager::new(self.work_dir.clone())
+    }
+
+    pub fn ports(&self) -> (u16, u16) {
+        self.config_manager().ports()
+    }
+
+    pub fn is_installed(&self) -> bool {
+        let executable = if cfg!(windows) {
+            "bedrock_server.exe"
+        } else {
+            "bedrock_server"
+        };
+        self.work_dir.join(executable).exists()
+    }
+
+    pub fn server_status(&self) -> ServerStatus {
+        server_lock::server_status(&self.work_dir)
+    }
+
+    pub fn running_server(&self) -> Option<ServerLock> {
+        server_lock::running_server(&self.work_dir)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running_server().is_some()
+    }
+}
+
+// Liga os serviços que reagem aos eventos do servidor da instância. Eles
+// rodam em um único processo por vez, o dono do services.lock, para não
+// expulsar ou contabilizar o mesmo jogador duas vezes: o daemon toma a trava
+// (Claim::TakeOver); menu e API esperam ela ficar livre. Os serviços ficam em
+// um barramento próprio, descartado quando a trava passa a outro processo,
+// e encerram junto com ele.
+pub fn spawn_services(events: &EventBus, instance: &Instance, claim: Claim) {
+    let receiver = events.subscribe();
+    let instance = instance.clone();
+
+    thread::spawn(move || {
+        let mut claim = claim;
+        loop {
+            let lock = loop {
+                if let Some(lock) = ServicesLock::acquire(&instance.work_dir, claim) {
+                    break lock;
+                }
+                // Os eventos de quando outro processo cuidava da instância
+                // são descartados
+                if let Err(RecvTimeoutError::Disconnected) =
+                    receiver.recv_timeout(SERVICES_CHECK_INTERVAL)
+                {
+                    return;
+                }
+            };
+
+            let services = EventBus::new();
+            ban_list::spawn_enforcer(&services, instance.clone());
+            players::spawn_tracker(&services, instance.work_dir.clone());
+            gamerules::spawn_applier(&services, instance.clone());
+            watchdog::spawn_watchdog(&services, instance.clone());
+            webhooks::spawn_notifier(&services, instance.clone());
+            scheduler::spawn_scheduler(&services, instance.clone());
+
+            let mut checked_at = Instant::now();
+            loop {
+                match receiver.recv_timeout(SERVICES_CHECK_INTERVAL) {
+                    Ok(event) => services.publish(event),
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+                if checked_at.elapsed() >= SERVICES_CHECK_INTERVAL {
+                    checked_at = Instant::now();
+                    if !lock.is_held() {
+                        break;
+                    }
+                }
+            }
+            println!(
+                "[{}] Serviços assumidos por outro processo (PID {})",
+                instance.name,
+                ServicesLock::owner_pid(&instance.work_dir)
+                    .map_or("?".to_string(), |pid| pid.to_string())
+            );
+            // Perdida a trava, só volta a rodar os serviços quando ela ficar livre
+            claim = Claim::WhenFree;
+        }
+    });
+}
+
+// Nomes das sessões screen ativas, extraídos de linhas como
+// "	12345.minecraft-survival	(Detached)"
+#[cfg(unix)]
+pub fn running_sessions() -> Vec<String> {
+    let Ok(output) = Command::new("screen").args(["-ls"]).output() else {
+        return Vec::new();
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let session = line.split_whitespace().next()?;
+            let (pid, name) = session.split_once('.')?;
+            pid.chars()
+                .all(|c| c.is_ascii_digit())
+                .then(|| name.to_string())
+        })
+        .collect()
+}
+
+pub struct InstanceRegistry {
+    path: PathBuf,
+    root: PathBuf,
+    instances: BTreeMap<String, Instance>,
+}
+
+impl InstanceRegistry {
+    pub fn load() -> Result<Self, String> {
+        let root =
+            env::current_dir().map_err(|e| format!("Erro ao obter diretório atual: {}", e))?;
+        let path = root.join("instances.json");
+        let mut instances: BTreeMap<String, Instance> = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| format!("Erro ao ler instances.json: {}", e))?;
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Erro ao ler instances.json: {}", e))?
+        } else {
+            BTreeMap::new()
+        };
+
+        // A instância padrão sempre usa o work_dir do manager.json
+        instances.insert(
+            DEFAULT_INSTANCE.to_string(),
+            Instance {
+                name: DEFAULT_INSTANCE.to_string(),
+                work_dir: root.join(&manager_config::settings().work_dir),
+            },
+        );
+
+        Ok(InstanceRegistry {
+            path,
+            root,
+            instances,
+        })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(&self.instances)
+            .map_err(|e| format!("Erro ao serializar instances.json: {}", e))?;
+        fs::write(&self.path, content).map_err(|e| format!("Erro ao salvar instances.json: {}", e))
+    }
+
+    pub fn list(&self) -> Vec<&Instance> {
+        self.instances.values().collect()
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Instance, String> {
+        self.instances
+            .get(name)
+            .ok_or_else(|| format!("Instância não encontrada: {}", name))
+    }
+
+    pub fn create(
+        &mut self,
+        name: &str,
+        port: Option<u16>,
+        defaults: Option<&str>,
+    ) -> Result<Instance, String> {
+        self.validate_new_name(name)?;
+        let (port, port_v6) = match port {
+            Some(port) => (port, port_v6_for(port)?),
+            None => self.next_free_ports()?,
+        };
+        self.ensure_ports_free(name, port, port_v6)?;
+        // Confere o perfil antes de criar o diretório
+        properties::defaults(defaults)?;
+
+        let instance = Instance {
+            name: name.to_string(),
+            work_dir: self.root.join("instances").join(name),
+        };
+        fs::create_dir_all(&instance.work_dir)
+            .map_err(|e| format!("Erro ao criar diretório da instância: {}", e))?;
+
+        let config = instance.config_manager();
+        config.initialize_with_defaults(defaults)?;
+        set_ports(&config, port, port_v6)?;
+
+        self.instances.insert(name.to_string(), instance.clone());
+        self.save()?;
+        Ok(instance)
+    }
+
+    pub fn clone_instance(&mut self, source: &str, name: &str) -> Result<Instance, String> {
+        self.validate_new_name(name)?;
+        let source = self.get(source)?.clone();
+        if source.is_running() {
+            return Err(format!(
+                "Pare a instância {} antes de cloná-la para não copiar o mundo pela metade",
+                source.name
+            ));
+        }
+
+        let instance = Instance {
+            name: name.to_string(),
+            work_dir: self.root.join("instances").join(name),
+        };
+        if instance.work_dir.exists() {
+            return Err(format!(
+                "Diretório já existe: {}",
+                instance.work_dir.display()
+            ));
+        }
+
+        // Um clone pela metade não fica para trás nem bloqueia o nome
+        let copied = copy_instance(&source.work_dir, &instance.work_dir).and_then(|_| {
+            let (port, port_v6) = self.next_free_ports()?;
+            set_ports(&instance.config_manager(), port, port_v6)
+        });
+        if let Err(e) = copied {
+            let _ = fs::remove_dir_all(&instance.work_dir);
+            return Err(e);
+        }
+
+        self.instances.insert(name.to_string(), instance.clone());
+        self.save()?;
+        Ok(instance)
+    }
+
+    pub fn delete(&mut self, name: &str) -> Result<(), String> {
+        if name == DEFAULT_INSTANCE {
+            return Err("A instância padrão não pode ser excluída".to_string());
+        }
+        let instance = self.get(name)?.clone();
+        if instance.is_running() {
+            return Err(format!("Pare a instância {} antes de excluí-la", name));
+        }
+
+        // Só remove arquivos de instâncias criadas pelo gerenciador
+        if instance.work_dir.starts_with(self.root.join("instances")) && instance.work_dir.exists()
+        {
+            fs::remove_dir_all(&instance.work_dir)
+                .map_err(|e| format!("Erro ao remover diretório da instância: {}", e))?;
+        }
+
+        self.instances.remove(name);
+        self.save()
+    }
+
+    // Impede iniciar uma instância cujas portas coincidam com as de outra
+    pub fn ensure_ports_free(&self, name: &str, port: u16, port_v6: u16) -> Result<(), String> {
+        for other in self.instances.values().filter(|other| other.name != name) {
+            let (other_port, other_port_v6) = other.ports();
+            let used = [other_port, other_port_v6];
+            if used.contains(&port) || used.contains(&port_v6) {
+                return Err(format!(
+                    "Conflito de portas: {}/{} já usadas pela instância {} ({}/{})",
+                    port, port_v6, other.name, other_port, other_port_v6
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn check_instance_ports(&self, instance: &Instance) -> Result<(), String> {
+        let (port, port_v6) = instance.ports();
+        self.ensure_ports_free(&instance.name, port, port_v6)
+    }
+
+    fn next_free_ports(&self) -> Result<(u16, u16), String> {
+        let used: Vec<u16> = self
+            .instances
+            .values()
+            .flat_map(|instance| {
+                let (port, port_v6) = instance.ports();
+                [port, port_v6]
+            })
+            .collect();
+
+        let mut port = DEFAULT_PORT;
+        loop {
+            let port_v6 = port_v6_for(port)?;
+            if !used.contains(&port) && !used.contains(&port_v6) {
+                return Ok((port, port_v6));
+            }
+            port = port
+                .checked_add(2)
+                .ok_or("Nenhum par de portas livre disponível")?;
+        }
+    }
+
+    fn validate_new_name(&self, name: &str) -> Result<(), String> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "Nome de instância inválido: '{}' (use letras minúsculas, números, - e _)",
+                name
+            ));
+        }
+        if self.instances.contains_key(name) {
+            return Err(format!("Já existe uma instância chamada {}", name));
+        }
+        Ok(())
+    }
+
+    pub fn print_status(&self) {
+        println!("\n=== Instâncias ===");
+        for instance in self.list() {
+            let (port, port_v6) = instance.ports();
+            let status = match instance.server_status() {
+                ServerStatus::Running(lock) => format!(
+                    "em execução, PID {}, há {}",
+                    lock.pid,
+                    format_playtime(lock.uptime().num_seconds())
+                ),
+                ServerStatus::StaleLock(lock) => {
+                    format!("parada, lock obsoleto do PID {} removido", lock.pid)
+                }
+                ServerStatus::Stopped if instance.is_installed() => "parada".to_string(),
+                ServerStatus::Stopped => "não instalada".to_string(),
+            };
+            println!(
+                "- {} [{}] portas {}/{} | {}",
+                instance.name,
+                status,
+                port,
+                port_v6,
+                instance.work_dir.display()
+            );
+        }
+    }
+}
+
+// A porta IPv6 fica logo depois da IPv4
+fn port_v6_for(port: u16) -> Result<u16, String> {
+    port.checked_add(1).ok_or_else(|| {
+        format!(
+            "Porta inválida: {} (não sobra porta para o IPv6 depois dela)",
+            port
+        )
+    })
+}
+
+fn set_ports(config: &ConfigManager, port: u16, port_v6: u16) -> Result<(), String> {
+    config
+        .set_properties(&[
+            ("server-port", port.to_string()),
+            ("server-portv6", port_v6.to_string()),
+        ])
+        .map(|_| ())
+}
+
+// Arquivos da instância de origem que não passam para o clone: logs, estado,
+// lock do processo, histórico de configuração, auditoria, estatísticas de
+// jogadores, backups (do mundo e dos perfis) e a ponte de chat, que aponta
+// para o canal da origem. Caminhos com `/` ficam em subpastas.
+const NOT_CLONED: &[&str] = &[
+    "logs",
+    "latest.log",
+    "state.json",
+    "state.json.lock",
+    "state.json.tmp",
+    "server.lock",
+    "history",
+    "audit.jsonl",
+    "players.json",
+    "services.lock",
+    "backups",
+    "profiles/.backups",
+    "bridge.json",
+];
+
+// Copia a instância inteira, exceto o que não passa para o clone
+fn copy_instance(source: &Path, destination: &Path) -> Result<(), String> {
+    copy_dir(source, destination, NOT_CLONED)
+}
+
+// Copia `source` para `destination`, ignorando as entradas de `skip`,
+// relativas a `source`
+fn copy_dir(source: &Path, destination: &Path, skip: &[&str]) -> Result<(), String> {
+    if destination.exists() {
+        return Err(format!("Diretório já existe: {}", destination.display()));
+    }
+    fs::create_dir_all(destination)
+        .map_err(|e| format!("Erro ao criar {}: {}", destination.display(), e))?;
+
+    let entries =
+        fs::read_dir(source).map_err(|e| format!("Erro ao ler {}: {}", source.display(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Erro ao ler {}: {}", source.display(), e))?;
+        let file_name = entry.file_name();
+        let name = file_name.to_string_lossy();
+        if skip.contains(&name.as_ref()) {
+            continue;
+        }
+        // Entradas de `skip` dentro desta subpasta
+        let nested: Vec<&str> = skip
+            .iter()
+            .filter_map(|path| path.strip_prefix(name.as_ref())?.strip_prefix('/'))
+            .collect();
+
+        let target = destination.join(&file_name);
+        let file_type = entry
+            .file_type()
+            .map_err(|e| format!("Erro ao ler {}: {}", entry.path().display(), e))?;
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target, &nested)?;
+        } else {
+            fs::copy(entry.path(), &target)
+                .map_err(|e| format!("Erro ao copiar {}: {}", entry.path().display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    fn registry_with_source() -> (test_util::TempCurrentDir, InstanceRegistry, PathBuf) {
+        let dir = test_util::enter_temp_dir("instances-clone");
+        let mut registry = InstanceRegistry::load().unwrap();
+        let source = registry.create("origem", None, None).unwrap().work_dir;
+        (dir, registry, source)
+    }
+
+    #[test]
+    fn clone_skips_backups_and_bridge() {
+        let (_dir, mut registry, source) = registry_with_source();
+        for file in [
+            "worlds/Mundo/level.dat",
+            "backups/backup.zip",
+            "profiles/evento/server.properties",
+            "profiles/.backups/antes.zip",
+            "bridge.json",
+            "logs/console.log",
+        ] {
+            let path = source.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x").unwrap();
+        }
+
+        let clone = registry.clone_instance("origem", "copia").unwrap().work_dir;
+        assert!(clone.join("worlds/Mundo/level.dat").exists());
+        assert!(clone.join("profiles/evento/server.properties").exists());
+        assert!(!clone.join("backups").exists());
+        assert!(!clone.join("profiles/.backups").exists());
+        assert!(!clone.join("bridge.json").exists());
+        assert!(!clone.join("logs").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_clone_leaves_nothing_behind() {
+        let (_dir, mut registry, source) = registry_with_source();
+        std::os::unix::fs::symlink(source.join("inexistente"), source.join("quebrado")).unwrap();
+
+        assert!(registry.clone_instance("origem", "copia").is_err());
+        assert!(!registry.root.join("instances/copia").exists());
+        assert!(registry.get("copia").is_err());
+    }
+}
@@ -1,11 +1,63 @@
+mod api;
+mod audit;
+mod auth;
+mod backup;
+mod ban_list;
+mod bedrock_command;
+mod bridge;
+mod cli;
+mod command;
 mod config;
+mod config_history;
 mod config_manager;
+mod console;
+mod daemon;
+mod events;
+mod file_lock;
+mod gamerules;
+mod instance_state;
+mod instances;
+mod manager_config;
 mod menu;
+mod metrics;
+mod players;
+mod profiles;
+mod properties;
+mod resources;
+mod scheduler;
 mod server;
 mod server_admin;
+mod server_lock;
+mod service_lock;
+#[cfg(test)]
+mod test_util;
+mod watchdog;
+mod webhooks;
+mod websocket;
+
+use std::env;
 
 fn main() {
+    if let Err(e) = manager_config::init() {
+        println!("Erro nas configurações do gerenciador: {}", e);
+        std::process::exit(1);
+    }
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        audit::set_default_actor("cli");
+        let result = cli::run(&args);
+        // Backups e atualizações feitos pela CLI também avisam os webhooks
+        webhooks::wait_for_deliveries();
+        if let Err(e) = result {
+            eprintln!("Erro: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     println!("Minecraft Bedrock Server");
+    audit::set_default_actor("menu");
     let mut menu = menu::Menu::new();
     menu.run();
 }
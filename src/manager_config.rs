@@ -0,0 +1,187 @@
+use crate::auth::ApiToken;
+use crate::webhooks::Webhook;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+// Prefixo das variáveis de ambiente que sobrescrevem o manager.json
+const ENV_PREFIX: &str = "MBS_";
+
+// Configurações do próprio gerenciador, em manager.json no diretório atual
+// (ou no caminho de MBS_CONFIG). Campos ausentes usam o valor padrão.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct ManagerConfig {
+    // Diretório da instância padrão, relativo ao diretório atual
+    pub work_dir: PathBuf,
+    // Sessão screen da instância padrão; as demais usam "<sessão>-<instância>"
+    pub screen_session: String,
+    // Página consultada para descobrir a versão mais recente do servidor
+    pub download_page_url: String,
+    // Espera entre enviar um comando e capturar a resposta do console
+    pub command_delay_ms: u64,
+    // Valores aplicados ao criar o server.properties de uma instância
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub default_properties: BTreeMap<String, String>,
+    // Perfis de propriedades usados em `instance create --defaults <perfil>`;
+    // um perfil com o nome de um embutido substitui só as chaves informadas
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub property_profiles: BTreeMap<String, BTreeMap<String, String>>,
+    pub api_tokens: Vec<ApiToken>,
+    pub webhooks: Vec<Webhook>,
+}
+
+impl Default for ManagerConfig {
+    fn default() -> Self {
+        ManagerConfig {
+            work_dir: PathBuf::from("server"),
+            screen_session: "minecraft".to_string(),
+            download_page_url: "https://www.minecraft.net/pt-br/download/server/bedrock"
+                .to_string(),
+            command_delay_ms: 100,
+            default_properties: BTreeMap::new(),
+            property_profiles: BTreeMap::new(),
+            api_tokens: Vec::new(),
+            webhooks: Vec::new(),
+        }
+    }
+}
+
+fn config_path() -> Result<PathBuf, String> {
+    if let Ok(path) = env::var("MBS_CONFIG") {
+        return Ok(PathBuf::from(path));
+    }
+    Ok(env::current_dir()
+        .map_err(|e| format!("Erro ao obter diretório atual: {}", e))?
+        .join("manager.json"))
+}
+
+impl ManagerConfig {
+    pub fn load() -> Result<Self, String> {
+        let path = config_path()?;
+        if !path.exists() {
+            return Ok(ManagerConfig::default());
+        }
+        let content =
+            fs::read_to_string(&path).map_err(|e| format!("Erro ao ler manager.json: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Erro ao ler manager.json: {}", e))
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = config_path()?;
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Erro ao serializar manager.json: {}", e))?;
+        fs::write(&path, content).map_err(|e| format!("Erro ao salvar manager.json: {}", e))?;
+
+        // Só o dono do gerenciador deve ler os hashes dos tokens e as URLs dos
+        // webhooks, que costumam embutir segredos
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600))
+                .map_err(|e| format!("Erro ao definir permissões do manager.json: {}", e))?;
+        }
+        Ok(())
+    }
+
+    // Aplica as variáveis MBS_WORK_DIR, MBS_SCREEN_SESSION,
+    // MBS_DOWNLOAD_PAGE_URL, MBS_COMMAND_DELAY_MS e MBS_PROPERTY_<CHAVE>
+    // (ex: MBS_PROPERTY_MAX_PLAYERS=20 vira max-players=20)
+    fn apply_env(&mut self) -> Result<(), String> {
+        for (name, value) in env::vars() {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            match key {
+                "WORK_DIR" => self.work_dir = PathBuf::from(value),
+                "SCREEN_SESSION" => self.screen_session = value,
+                "DOWNLOAD_PAGE_URL" => self.download_page_url = value,
+                "COMMAND_DELAY_MS" => {
+                    self.command_delay_ms = value
+                        .parse()
+                        .map_err(|_| format!("{} inválido: {}", name, value))?
+                }
+                _ => {
+                    if let Some(property) = key.strip_prefix("PROPERTY_") {
+                        let property = property.to_lowercase().replace('_', "-");
+                        self.default_properties.insert(property, value);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.screen_session.is_empty()
+            || !self
+                .screen_session
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        {
+            return Err(format!(
+                "Nome de sessão screen inválido: '{}'",
+                self.screen_session
+            ));
+        }
+        if self.work_dir.as_os_str().is_empty() {
+            return Err("work_dir não pode ser vazio".to_string());
+        }
+        Ok(())
+    }
+}
+
+static SETTINGS: OnceLock<ManagerConfig> = OnceLock::new();
+
+fn load_settings() -> Result<ManagerConfig, String> {
+    let mut config = ManagerConfig::load()?;
+    config.apply_env()?;
+    config.validate()?;
+    Ok(config)
+}
+
+// Lê a configuração efetiva (manager.json mais variáveis de ambiente) no
+// início do processo. Um arquivo inválido, uma variável MBS_* errada ou um
+// valor recusado param o programa, em vez de cair nos padrões (inclusive o
+// work_dir) sem aviso.
+pub fn init() -> Result<(), String> {
+    let config = load_settings()?;
+    let _ = SETTINGS.set(config);
+    Ok(())
+}
+
+// Configuração efetiva, lida uma vez por processo. Tokens e webhooks mudam em
+// tempo de execução e devem ser lidos com `ManagerConfig::load`.
+pub fn settings() -> &'static ManagerConfig {
+    SETTINGS.get_or_init(|| load_settings().unwrap_or_else(|e| panic!("{}", e)))
+}
+
+pub fn print_settings() -> Result<(), String> {
+    let config = settings();
+    println!("\n=== Configurações do Gerenciador ===");
+    println!("Arquivo: {}", config_path()?.display());
+    println!("work_dir: {}", config.work_dir.display());
+    println!("screen_session: {}", config.screen_session);
+    println!("download_page_url: {}", config.download_page_url);
+    println!("command_delay_ms: {}", config.command_delay_ms);
+    if config.default_properties.is_empty() {
+        println!("default_properties: nenhuma");
+    } else {
+        println!("default_properties:");
+        for (key, value) in &config.default_properties {
+            println!("  {}={}", key, value);
+        }
+    }
+    if !config.property_profiles.is_empty() {
+        let names: Vec<&str> = config
+            .property_profiles
+            .keys()
+            .map(|k| k.as_str())
+            .collect();
+        println!("property_profiles: {}", names.join(", "));
+    }
+    Ok(())
+}
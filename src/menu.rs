@@ -1,64 +1,251 @@
+use crate::audit;
+use crate::backup;
+use crate::ban_list::{parse_duration, BanList};
+use crate::bedrock_command::{Effect, ItemStack, TitleKind};
+use crate::config;
+use crate::config::Config;
 use crate::config_manager::ConfigManager;
+use crate::console;
+use crate::daemon::{self, DaemonRequest};
+use crate::gamerules::{self, DesiredGamerules};
+use crate::instance_state::InstanceState;
+use crate::instances::{self, Instance, InstanceRegistry, DEFAULT_INSTANCE};
+use crate::players::{format_playtime, PlayerDatabase, PlayerSort};
+use crate::profiles;
+use crate::properties;
+use crate::resources;
+use crate::scheduler::{self, JobSchedule};
 use crate::server::Server;
 use crate::server_admin::ServerAdmin;
+use crate::server_lock::ServerStatus;
+use crate::service_lock::Claim;
+use chrono::Local;
+use serde_json::json;
 use std::io::{self, Write};
 use std::path::PathBuf;
 
 pub struct Menu {
+    instance: Instance,
     server: Server,
 }
 
 impl Menu {
     pub fn new() -> Self {
-        Menu {
-            server: Server::new(),
+        let instance = InstanceRegistry::load()
+            .and_then(|registry| registry.get(DEFAULT_INSTANCE).cloned())
+            .expect("Erro ao carregar instâncias");
+        let server = Self::open_instance(&instance);
+        Menu { instance, server }
+    }
+
+    // Cria o servidor da instância. O menu roda os serviços ligados aos
+    // eventos só enquanto nenhum outro processo (daemon, API) os rodar.
+    fn open_instance(instance: &Instance) -> Server {
+        let server = Server::for_instance(instance);
+        instances::spawn_services(server.events(), instance, Claim::WhenFree);
+        server
+    }
+
+    fn select_instance(&mut self, instance: Instance) {
+        if instance.name != self.instance.name {
+            self.server = Self::open_instance(&instance);
+            self.instance = instance;
         }
     }
 
     pub fn run(&mut self) {
-        // Verificar servidores existentes
-        match self.server.check_existing_servers() {
-            Ok(servers) if !servers.is_empty() => {
-                println!("\nServidores Minecraft encontrados em execução:");
-                for (i, server) in servers.iter().enumerate() {
-                    println!("{}. {}", i + 1, server);
-                }
-                println!("{}. Iniciar novo servidor", servers.len() + 1);
-                println!("{}. Sair", servers.len() + 2);
-
-                print!("\nEscolha uma opção: ");
-                io::stdout().flush().unwrap();
-
-                let input = self.get_user_input() as usize;
-                if input > 0 && input <= servers.len() {
-                    let server_name = &servers[input - 1];
-                    match self.server.attach_to_existing(server_name) {
+        loop {
+            let registry = match InstanceRegistry::load() {
+                Ok(registry) => registry,
+                Err(e) => {
+                    println!("Erro ao carregar instâncias: {}", e);
+                    return;
+                }
+            };
+            let instances: Vec<Instance> = registry.list().into_iter().cloned().collect();
+
+            println!("\n=== Instâncias ===");
+            for (i, instance) in instances.iter().enumerate() {
+                let (port, port_v6) = instance.ports();
+                let status = if instance.is_running() {
+                    "em execução"
+                } else {
+                    "parada"
+                };
+                println!(
+                    "{}. {} [{}] portas {}/{}",
+                    i + 1,
+                    instance.name,
+                    status,
+                    port,
+                    port_v6
+                );
+            }
+            println!("{}. Gerenciar Instâncias", instances.len() + 1);
+            println!("{}. Sair", instances.len() + 2);
+
+            print!("\nEscolha uma opção: ");
+            io::stdout().flush().unwrap();
+
+            let input = self.get_user_input() as usize;
+            if input > 0 && input <= instances.len() {
+                self.select_instance(instances[input - 1].clone());
+                if self.instance.is_running() {
+                    match self.server.attach_to_existing() {
                         Ok(_) => self.run_server_menu(),
                         Err(e) => println!("Erro ao conectar ao servidor: {}", e),
                     }
-                } else if input == servers.len() + 1 {
-                    // Iniciar novo servidor
-                    self.start_new_server();
-                } else if input == servers.len() + 2 {
-                    println!("Saindo...");
-                    return;
                 } else {
-                    println!("Opção inválida!");
+                    self.start_new_server();
                 }
+                return;
+            } else if input == instances.len() + 1 {
+                self.instances_menu();
+            } else if input == instances.len() + 2 {
+                println!("Saindo...");
+                return;
+            } else {
+                println!("Opção inválida!");
             }
-            Ok(_) => {
-                // Nenhum servidor encontrado, iniciar novo
-                self.start_new_server();
+        }
+    }
+
+    fn instances_menu(&self) {
+        loop {
+            println!("\n=== Gerenciar Instâncias ===");
+            println!("1. Listar Instâncias");
+            println!("2. Criar Instância");
+            println!("3. Clonar Instância");
+            println!("4. Excluir Instância");
+            println!("5. Parar Instância");
+            println!("6. Voltar");
+
+            let choice = self.get_user_input();
+            if choice == 6 {
+                break;
             }
-            Err(e) => {
-                println!("Erro ao verificar servidores existentes: {}", e);
-                self.start_new_server();
+
+            let mut registry = match InstanceRegistry::load() {
+                Ok(registry) => registry,
+                Err(e) => {
+                    println!("Erro ao carregar instâncias: {}", e);
+                    continue;
+                }
+            };
+
+            match choice {
+                1 => registry.print_status(),
+                2 => {
+                    println!("\nDigite o nome da nova instância:");
+                    let name = self.get_input_string();
+                    println!("Digite a porta IPv4 (deixe vazio para escolher automaticamente):");
+                    let port = match self.get_optional_input().map(|p| p.parse::<u16>()) {
+                        None => None,
+                        Some(Ok(port)) => Some(port),
+                        Some(Err(_)) => {
+                            println!("Porta inválida!");
+                            continue;
+                        }
+                    };
+                    println!(
+                        "Perfil de propriedades ({}; deixe vazio para o padrão):",
+                        properties::profile_names().join(", ")
+                    );
+                    let defaults = self.get_optional_input();
+                    match registry.create(&name, port, defaults.as_deref()) {
+                        Ok(instance) => {
+                            let (port, port_v6) = instance.ports();
+                            println!(
+                                "Instância {} criada (portas {}/{}). Baixe o servidor pelo menu de configuração.",
+                                instance.name, port, port_v6
+                            );
+                        }
+                        Err(e) => println!("Erro ao criar instância: {}", e),
+                    }
+                }
+                3 => {
+                    println!("\nDigite o nome da instância de origem:");
+                    let source = self.get_input_string();
+                    println!("Digite o nome da nova instância:");
+                    let name = self.get_input_string();
+                    match registry.clone_instance(&source, &name) {
+                        Ok(instance) => {
+                            let (port, port_v6) = instance.ports();
+                            println!(
+                                "Instância {} clonada (portas {}/{})",
+                                instance.name, port, port_v6
+                            );
+                        }
+                        Err(e) => println!("Erro ao clonar instância: {}", e),
+                    }
+                }
+                4 => {
+                    println!("\nDigite o nome da instância a excluir:");
+                    let name = self.get_input_string();
+                    println!(
+                        "Todos os arquivos e mundos de {} serão apagados. Confirmar? (s/n)",
+                        name
+                    );
+                    if !self.get_input_string().eq_ignore_ascii_case("s") {
+                        continue;
+                    }
+                    match registry.delete(&name) {
+                        Ok(_) => println!("Instância {} excluída", name),
+                        Err(e) => println!("Erro ao excluir instância: {}", e),
+                    }
+                }
+                5 => {
+                    println!("\nDigite o nome da instância a parar:");
+                    let name = self.get_input_string();
+                    let result = registry.get(&name).and_then(daemon::stop_instance);
+                    match result {
+                        Ok(_) => println!("Comando de parada enviado para {}", name),
+                        Err(e) => println!("Erro ao parar instância: {}", e),
+                    }
+                }
+                _ => println!("Opção inválida!"),
             }
         }
     }
 
+    fn start_server(&mut self) -> Result<(), String> {
+        let result = self.start_server_now();
+        audit::record(&self.instance.work_dir, "server.start", json!({}), &result);
+        result
+    }
+
+    // Recusa iniciar se as portas coincidirem com as de outra instância
+    fn start_server_now(&mut self) -> Result<(), String> {
+        if daemon::is_running() {
+            daemon::request(&DaemonRequest::Start {
+                instance: self.instance.name.clone(),
+            })?;
+            return self.server.attach_to_existing();
+        }
+        InstanceRegistry::load()?.check_instance_ports(&self.instance)?;
+        self.server.start()
+    }
+
+    fn stop_server(&mut self) -> Result<(), String> {
+        let result = self.stop_server_now();
+        audit::record(&self.instance.work_dir, "server.stop", json!({}), &result);
+        result
+    }
+
+    fn stop_server_now(&mut self) -> Result<(), String> {
+        if daemon::is_running() {
+            daemon::request(&DaemonRequest::Stop {
+                instance: self.instance.name.clone(),
+            })?;
+            // Descarta o acompanhamento local do console
+            self.server = Self::open_instance(&self.instance);
+            return Ok(());
+        }
+        self.server.stop()
+    }
+
     fn start_new_server(&mut self) {
-        match self.server.start() {
+        match self.start_server() {
             Ok(_) => {
                 println!("Servidor iniciado com sucesso!");
                 self.run_server_menu();
@@ -71,14 +258,26 @@ impl Menu {
     }
 
     fn run_server_menu(&mut self) {
-        let admin = ServerAdmin::new(PathBuf::from(self.server.get_work_dir()));
+        let admin = self.instance.admin().with_echo();
 
         loop {
             self.display_server_options();
             match self.get_user_input() {
                 1 => self.admin_menu(&admin),
                 2 => {
-                    if let Err(e) = self.server.stop() {
+                    if let Err(e) = console::run_console(&self.instance, self.server.events()) {
+                        println!("Erro no console: {}", e);
+                    }
+                }
+                3 => {
+                    let config_manager = self.instance.config_manager();
+                    match config_manager.configure_server() {
+                        Ok(changed) => self.apply_property_changes(&changed),
+                        Err(e) => println!("Erro ao configurar servidor: {}", e),
+                    }
+                }
+                4 => {
+                    if let Err(e) = self.stop_server() {
                         println!("Erro ao parar servidor: {}", e);
                     } else {
                         println!("Servidor parado com sucesso!");
@@ -86,9 +285,11 @@ impl Menu {
                         break;
                     }
                 }
-                3 => {
-                    println!("Saindo...");
-                    let _ = self.server.stop();
+                // O painel é redesenhado ao voltar ao menu
+                5 => {}
+                6 => {
+                    println!("Saindo... O servidor continua em execução.");
+                    println!("Abra o gerenciador novamente para se reconectar a ele.");
                     break;
                 }
                 _ => println!("Opção inválida! Por favor, tente novamente."),
@@ -102,7 +303,12 @@ impl Menu {
             println!("1. Listar Jogadores");
             println!("2. Gerenciar Jogador");
             println!("3. Gerenciar Whitelist");
-            println!("4. Voltar");
+            println!("4. Gerenciar Banimentos");
+            println!("5. Estatísticas de Jogadores");
+            println!("6. Gerenciar Mundo");
+            println!("7. Mensagens");
+            println!("8. Tarefas Agendadas");
+            println!("9. Voltar");
 
             match self.get_user_input() {
                 1 => {
@@ -112,7 +318,12 @@ impl Menu {
                 }
                 2 => self.player_management_menu(admin),
                 3 => self.whitelist_menu(admin),
-                4 => break,
+                4 => self.ban_menu(admin),
+                5 => self.players_view(),
+                6 => self.world_menu(admin),
+                7 => self.messages_menu(admin),
+                8 => self.schedule_menu(),
+                9 => break,
                 _ => println!("Opção inválida!"),
             }
         }
@@ -130,7 +341,12 @@ impl Menu {
             println!("4. Kickar");
             println!("5. Dar Item");
             println!("6. Teleportar");
-            println!("7. Voltar");
+            println!("7. Aplicar Efeito");
+            println!("8. Remover Efeitos");
+            println!("9. Limpar Inventário");
+            println!("10. Dar Experiência");
+            println!("11. Definir Ponto de Spawn");
+            println!("12. Voltar");
 
             match self.get_user_input() {
                 1 => {
@@ -158,27 +374,398 @@ impl Menu {
                     }
                 }
                 5 => {
-                    println!("\nDigite o item:");
+                    println!("\nDigite o item (ex: diamond, minecraft:wool):");
                     let item = self.get_input_string();
                     println!("Digite a quantidade:");
                     let amount: u32 = self.get_input_string().parse().unwrap_or(1);
-                    if let Err(e) = admin.give_item(&player, &item, amount) {
+                    println!("Digite o valor de dados (opcional):");
+                    let data = self.get_input_string().parse().ok();
+
+                    let result = ItemStack::new(&item, amount).and_then(|stack| {
+                        let stack = match data {
+                            Some(data) => stack.with_data(data),
+                            None => stack,
+                        };
+                        admin.give_item(&player, stack)
+                    });
+                    if let Err(e) = result {
                         println!("Erro ao dar item: {}", e);
                     }
                 }
                 6 => {
-                    println!("\nDigite o alvo (jogador ou coordenadas):");
+                    println!(
+                        "\nDigite o alvo (jogador, seletor como @p ou coordenadas como ~ ~1 ~):"
+                    );
                     let target = self.get_input_string();
                     if let Err(e) = admin.teleport_player(&player, &target) {
                         println!("Erro ao teleportar: {}", e);
                     }
                 }
+                7 => {
+                    println!("\nDigite o efeito (ex: speed, night_vision, regeneration):");
+                    let effect = self.get_input_string();
+                    println!("Digite a duração em segundos:");
+                    let seconds: u32 = self.get_input_string().parse().unwrap_or(30);
+                    println!("Digite o nível do efeito (0 = nível I):");
+                    let amplifier: u8 = self.get_input_string().parse().unwrap_or(0);
+                    println!("Ocultar partículas? (s/N)");
+                    let hide = self.get_input_string().eq_ignore_ascii_case("s");
+
+                    let result = Effect::new(&effect, seconds, amplifier).and_then(|effect| {
+                        let effect = if hide {
+                            effect.hide_particles()
+                        } else {
+                            effect
+                        };
+                        admin.give_effect(&player, effect)
+                    });
+                    if let Err(e) = result {
+                        println!("Erro ao aplicar efeito: {}", e);
+                    }
+                }
+                8 => {
+                    if let Err(e) = admin.clear_effects(&player) {
+                        println!("Erro ao remover efeitos: {}", e);
+                    }
+                }
+                9 => {
+                    println!("\nDigite o item a remover (vazio para limpar tudo):");
+                    let item = self.get_optional_input();
+                    if let Err(e) = admin.clear_inventory(&player, item.as_deref()) {
+                        println!("Erro ao limpar inventário: {}", e);
+                    }
+                }
+                10 => {
+                    println!(
+                        "\nDigite a quantidade (ex: 100 pontos ou 5L para níveis, -5L remove):"
+                    );
+                    let input = self.get_input_string();
+                    let (amount, levels) = match input.strip_suffix(['L', 'l']) {
+                        Some(levels) => (levels.parse::<i32>(), true),
+                        None => (input.parse::<i32>(), false),
+                    };
+                    let result = amount
+                        .map_err(|_| format!("Quantidade inválida: {}", input))
+                        .and_then(|amount| admin.give_xp(&player, amount, levels));
+                    if let Err(e) = result {
+                        println!("Erro ao dar experiência: {}", e);
+                    }
+                }
+                11 => {
+                    println!("\nDigite as coordenadas (vazio para a posição atual do jogador):");
+                    let position = self.get_optional_input();
+                    if let Err(e) = admin.set_spawnpoint(&player, position.as_deref()) {
+                        println!("Erro ao definir ponto de spawn: {}", e);
+                    }
+                }
+                12 => break,
+                _ => println!("Opção inválida!"),
+            }
+        }
+    }
+
+    fn world_menu(&self, admin: &ServerAdmin) {
+        loop {
+            println!("\n=== Gerenciar Mundo ===");
+            println!("1. Definir Horário");
+            println!("2. Mudar Clima");
+            println!("3. Mudar Dificuldade");
+            println!("4. Alterar Gamerule");
+            println!("5. Definir Spawn do Mundo");
+            println!("6. Gamerules Persistentes");
+            println!("7. Voltar");
+
+            match self.get_user_input() {
+                1 => {
+                    println!("\nDigite o horário (day, noon, sunset, night, midnight, sunrise ou ticks):");
+                    let time = self.get_input_string();
+                    if let Err(e) = admin.set_time(&time) {
+                        println!("Erro ao definir horário: {}", e);
+                    }
+                }
+                2 => {
+                    println!("\nEscolha o clima (clear, rain, thunder):");
+                    let weather = self.get_input_string();
+                    println!("Digite a duração em segundos (opcional):");
+                    let duration = self.get_input_string().parse().ok();
+                    if let Err(e) = admin.set_weather(&weather, duration) {
+                        println!("Erro ao mudar clima: {}", e);
+                    }
+                }
+                3 => {
+                    println!("\nEscolha a dificuldade (peaceful, easy, normal, hard):");
+                    let difficulty = self.get_input_string();
+                    if let Err(e) = admin.set_difficulty(&difficulty) {
+                        println!("Erro ao mudar dificuldade: {}", e);
+                    }
+                }
+                4 => {
+                    println!("\nDigite o nome da gamerule (ex: keepInventory):");
+                    let rule = self.get_input_string();
+                    println!("Digite o valor (true, false ou número):");
+                    let value = self.get_input_string();
+                    if let Err(e) = admin.set_gamerule(&rule, &value) {
+                        println!("Erro ao alterar gamerule: {}", e);
+                    }
+                }
+                5 => {
+                    println!("\nDigite as coordenadas (vazio para a posição atual):");
+                    let position = self.get_optional_input();
+                    if let Err(e) = admin.set_world_spawn(position.as_deref()) {
+                        println!("Erro ao definir spawn do mundo: {}", e);
+                    }
+                }
+                6 => self.gamerules_menu(admin),
                 7 => break,
                 _ => println!("Opção inválida!"),
             }
         }
     }
 
+    fn gamerules_menu(&self, admin: &ServerAdmin) {
+        let work_dir = PathBuf::from(self.server.get_work_dir());
+
+        loop {
+            println!("\n=== Gamerules Persistentes ===");
+            println!("1. Ver Valores Atuais");
+            println!("2. Comparar com Estado Desejado");
+            println!("3. Aplicar Estado Desejado Agora");
+            println!("4. Definir Valor Desejado");
+            println!("5. Remover Valor Desejado");
+            println!("6. Voltar");
+
+            let choice = self.get_user_input();
+            if choice == 6 {
+                break;
+            }
+
+            let mut desired = match DesiredGamerules::load(&work_dir) {
+                Ok(desired) => desired,
+                Err(e) => {
+                    println!("Erro ao carregar gamerules.json: {}", e);
+                    continue;
+                }
+            };
+
+            match choice {
+                1 => match gamerules::query_current(admin) {
+                    Ok(current) => gamerules::print_current(&current),
+                    Err(e) => println!("Erro ao consultar gamerules: {}", e),
+                },
+                2 => match gamerules::query_current(admin) {
+                    Ok(current) => gamerules::print_diff(&desired.diff(&current)),
+                    Err(e) => println!("Erro ao consultar gamerules: {}", e),
+                },
+                3 => match desired.apply(admin) {
+                    Ok(count) => println!("{} gamerules aplicadas!", count),
+                    Err(e) => println!("Erro ao aplicar gamerules: {}", e),
+                },
+                4 => {
+                    println!("\nDigite o nome da gamerule (ex: keepInventory):");
+                    let rule = self.get_input_string();
+                    println!("Digite o valor (true, false ou número):");
+                    let value = self.get_input_string();
+                    let result = desired
+                        .set(&rule, &value)
+                        .and_then(|_| admin.set_gamerule(&rule, &value));
+                    match result {
+                        Ok(_) => println!("Gamerule {} salva e aplicada!", rule),
+                        Err(e) => println!("Erro ao definir gamerule: {}", e),
+                    }
+                }
+                5 => {
+                    println!("\nDigite o nome da gamerule:");
+                    let rule = self.get_input_string();
+                    match desired.unset(&rule) {
+                        Ok(true) => println!("Gamerule {} removida do estado desejado", rule),
+                        Ok(false) => println!("Gamerule {} não estava definida", rule),
+                        Err(e) => println!("Erro ao remover gamerule: {}", e),
+                    }
+                }
+                _ => println!("Opção inválida!"),
+            }
+        }
+    }
+
+    fn schedule_menu(&self) {
+        let work_dir = &self.instance.work_dir;
+
+        loop {
+            if let Err(e) = scheduler::print_jobs(work_dir) {
+                println!("Erro ao carregar tarefas: {}", e);
+                return;
+            }
+            println!("\n1. Executar Agora");
+            println!("2. Pausar");
+            println!("3. Retomar");
+            println!("4. Voltar");
+
+            let choice = self.get_user_input();
+            if choice == 4 {
+                break;
+            }
+            if !(1..=3).contains(&choice) {
+                println!("Opção inválida!");
+                continue;
+            }
+
+            println!("\nDigite o nome da tarefa:");
+            let name = self.get_input_string();
+            let result = JobSchedule::load(work_dir).and_then(|mut schedule| match choice {
+                1 => scheduler::run_job(&self.instance, schedule.get(&name)?),
+                _ => schedule.set_paused(work_dir, &name, choice == 2),
+            });
+            match result {
+                Ok(()) => println!("Tarefa {} atualizada!", name),
+                Err(e) => println!("Erro na tarefa {}: {}", name, e),
+            }
+        }
+    }
+
+    // Com o servidor rodando, aplica na hora o que aceita comando e oferece
+    // reiniciar agora ou agendar o reinício para o resto
+    fn apply_property_changes(&self, changed: &[String]) {
+        if changed.is_empty() {
+            return;
+        }
+        if !self.instance.is_running() {
+            println!("As alterações valem no próximo início do servidor.");
+            return;
+        }
+        let restart = match properties::apply_live(&self.instance, changed) {
+            Ok(restart) => restart,
+            Err(e) => {
+                println!("Erro ao aplicar alterações no servidor: {}", e);
+                return;
+            }
+        };
+        if restart.is_empty() {
+            println!("Todas as alterações já valem no servidor.");
+            return;
+        }
+
+        println!("\nExigem reinício para valer: {}", restart.join(", "));
+        println!("1. Reiniciar Agora (aviso de 60 segundos)");
+        println!("2. Agendar Reinício");
+        println!("3. Reiniciar Depois");
+        let reason = "alteração de configuração";
+        let result = match self.get_user_input() {
+            1 => daemon::restart_with_warnings(&self.instance, 60, reason),
+            2 => {
+                println!("Quando? (ex: 30m, 2h ou 04:00)");
+                let input = self.get_input_string();
+                scheduler::parse_when(&input).and_then(|at| {
+                    scheduler::schedule_restart(&self.instance.work_dir, at, reason)?;
+                    println!(
+                        "Reinício agendado para {}",
+                        at.with_timezone(&Local).format("%d/%m/%Y %H:%M")
+                    );
+                    Ok(())
+                })
+            }
+            _ => {
+                println!("As alterações valem no próximo início do servidor.");
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            println!("Erro: {}", e);
+        }
+    }
+
+    fn profiles_menu(&self) {
+        let work_dir = &self.instance.work_dir;
+
+        loop {
+            if let Err(e) = profiles::print_list(work_dir) {
+                println!("Erro ao carregar perfis: {}", e);
+                return;
+            }
+            println!("\n1. Aplicar Perfil");
+            println!("2. Salvar Configuração Atual como Perfil");
+            println!("3. Desfazer Última Aplicação");
+            println!("4. Voltar");
+
+            let result = match self.get_user_input() {
+                1 => {
+                    println!("\nDigite o nome do perfil:");
+                    let name = self.get_input_string();
+                    profiles::apply(&self.instance, &name, 60)
+                }
+                2 => {
+                    println!("\nDigite o nome do novo perfil:");
+                    let name = self.get_input_string();
+                    profiles::save(work_dir, &name).map(|dir| {
+                        println!("Perfil {} criado em {}", name, dir.display());
+                    })
+                }
+                3 => profiles::restore(&self.instance, None, 60),
+                4 => break,
+                _ => {
+                    println!("Opção inválida!");
+                    continue;
+                }
+            };
+            if let Err(e) = result {
+                println!("Erro: {}", e);
+            }
+        }
+    }
+
+    fn messages_menu(&self, admin: &ServerAdmin) {
+        loop {
+            println!("\n=== Mensagens ===");
+            println!("1. Mensagem para Todos");
+            println!("2. Mensagem Privada");
+            println!("3. Mostrar Título");
+            println!("4. Limpar Título");
+            println!("5. Voltar");
+
+            match self.get_user_input() {
+                1 => {
+                    println!("\nDigite a mensagem:");
+                    let message = self.get_input_string();
+                    if let Err(e) = admin.say(&message) {
+                        println!("Erro ao enviar mensagem: {}", e);
+                    }
+                }
+                2 => {
+                    println!("\nDigite o nome do jogador:");
+                    let player = self.get_input_string();
+                    println!("Digite a mensagem:");
+                    let message = self.get_input_string();
+                    if let Err(e) = admin.tell(&player, &message) {
+                        println!("Erro ao enviar mensagem: {}", e);
+                    }
+                }
+                3 => {
+                    println!("\nDigite o alvo (jogador ou seletor, ex: @a):");
+                    let target = self.get_input_string();
+                    println!("Tipo (1. Título, 2. Subtítulo, 3. Barra de ação):");
+                    let kind = match self.get_user_input() {
+                        2 => TitleKind::Subtitle,
+                        3 => TitleKind::Actionbar,
+                        _ => TitleKind::Title,
+                    };
+                    println!("Digite o texto:");
+                    let text = self.get_input_string();
+                    if let Err(e) = admin.show_title(&target, kind, &text) {
+                        println!("Erro ao mostrar título: {}", e);
+                    }
+                }
+                4 => {
+                    println!("\nDigite o alvo (jogador ou seletor, ex: @a):");
+                    let target = self.get_input_string();
+                    if let Err(e) = admin.clear_title(&target) {
+                        println!("Erro ao limpar título: {}", e);
+                    }
+                }
+                5 => break,
+                _ => println!("Opção inválida!"),
+            }
+        }
+    }
+
     fn whitelist_menu(&self, admin: &ServerAdmin) {
         loop {
             println!("\n=== Gerenciar Whitelist ===");
@@ -207,29 +794,111 @@ impl Menu {
         }
     }
 
+    fn ban_menu(&self, admin: &ServerAdmin) {
+        let work_dir = PathBuf::from(self.server.get_work_dir());
+
+        loop {
+            println!("\n=== Gerenciar Banimentos ===");
+            println!("1. Banir Jogador");
+            println!("2. Banir Temporariamente");
+            println!("3. Remover Banimento");
+            println!("4. Listar Banimentos");
+            println!("5. Voltar");
+
+            let choice = self.get_user_input();
+            if choice == 5 {
+                break;
+            }
+
+            let mut bans = match BanList::load(&work_dir) {
+                Ok(bans) => bans,
+                Err(e) => {
+                    println!("Erro ao carregar banimentos: {}", e);
+                    continue;
+                }
+            };
+
+            match choice {
+                1 | 2 => {
+                    println!("\nDigite o nome do jogador:");
+                    let player = self.get_input_string();
+                    println!("Digite o XUID (opcional):");
+                    let xuid = self.get_input_string();
+                    let duration = if choice == 2 {
+                        println!("Digite a duração (ex: 30m, 12h, 7d):");
+                        match parse_duration(&self.get_input_string()) {
+                            Ok(duration) => Some(duration),
+                            Err(e) => {
+                                println!("{}", e);
+                                continue;
+                            }
+                        }
+                    } else {
+                        None
+                    };
+                    println!("Digite o motivo:");
+                    let reason = self.get_input_string();
+
+                    match bans.ban(&player, Some(&xuid), &reason, "menu", duration) {
+                        Ok(entry) => {
+                            println!("Jogador {} banido!", entry.name);
+                            if let Err(e) = admin.kick_player(&player, &entry.kick_message()) {
+                                println!("Erro ao kickar jogador: {}", e);
+                            }
+                        }
+                        Err(e) => println!("Erro ao banir jogador: {}", e),
+                    }
+                }
+                3 => {
+                    println!("\nDigite o nome ou XUID do jogador:");
+                    let player = self.get_input_string();
+                    match bans.unban(&player) {
+                        Ok(true) => println!("Banimento removido!"),
+                        Ok(false) => println!("Jogador não está banido."),
+                        Err(e) => println!("Erro ao remover banimento: {}", e),
+                    }
+                }
+                4 => bans.print(),
+                _ => println!("Opção inválida!"),
+            }
+        }
+    }
+
+    fn players_view(&self) {
+        println!("\nOrdenar por:");
+        println!("1. Tempo de jogo");
+        println!("2. Última vez visto");
+        let sort = match self.get_user_input() {
+            2 => PlayerSort::LastSeen,
+            _ => PlayerSort::Playtime,
+        };
+
+        match PlayerDatabase::load(&PathBuf::from(self.server.get_work_dir())) {
+            Ok(db) => db.print(sort),
+            Err(e) => println!("Erro ao carregar estatísticas: {}", e),
+        }
+    }
+
     fn run_config_menu(&mut self) {
         let config_manager = ConfigManager::new(PathBuf::from(self.server.get_work_dir()));
 
         loop {
             println!("\n=== Menu de Configuração ===");
             println!("1. Configurar Servidor");
-            println!("2. Atualizar Servidor");
-            println!("3. Voltar");
+            println!("2. Baixar/Atualizar Servidor");
+            println!("3. Perfis de Configuração");
+            println!("4. Voltar");
 
             match self.get_user_input() {
-                1 => {
-                    if let Err(e) = config_manager.configure_server() {
-                        println!("Erro ao configurar servidor: {}", e);
-                    }
-                }
-                2 => {
-                    if let Err(e) = config_manager.initialize_configs() {
-                        println!("Erro ao atualizar servidor: {}", e);
-                    }
-                }
-                3 => {
+                1 => match config_manager.configure_server() {
+                    Ok(changed) => self.apply_property_changes(&changed),
+                    Err(e) => println!("Erro ao configurar servidor: {}", e),
+                },
+                2 => Config::new(self.instance.work_dir.clone()).run(),
+                3 => self.profiles_menu(),
+                4 => {
                     // Tentar iniciar o servidor após configuração
-                    match self.server.start() {
+                    match self.start_server() {
                         Ok(_) => {
                             println!("Servidor iniciado com sucesso!");
                             self.run_server_menu();
@@ -246,14 +915,101 @@ impl Menu {
     }
 
     fn display_server_options(&self) {
-        println!("\n=== Servidor em Execução ===");
+        println!("\n=== Servidor em Execução ({}) ===", self.instance.name);
+        self.print_status_panel();
+        println!();
         println!("1. Menu de Administração");
-        println!("2. Parar Servidor e Configurar");
-        println!("3. Sair");
+        println!("2. Console ao Vivo");
+        println!("3. Configurar Servidor");
+        println!("4. Parar Servidor e Configurar");
+        println!("5. Atualizar Status");
+        println!("6. Sair (mantém o servidor rodando)");
         print!("Escolha uma opção: ");
         io::stdout().flush().unwrap();
     }
 
+    // Uso de recursos do processo registrado no server.lock, espaço em disco e
+    // estado do servidor, lidos a cada vez que o menu é exibido
+    fn print_status_panel(&self) {
+        let work_dir = &self.instance.work_dir;
+        match self.instance.server_status() {
+            ServerStatus::Running(lock) => {
+                println!(
+                    "Estado: em execução (PID {}) há {}",
+                    lock.pid,
+                    format_playtime(lock.uptime().num_seconds())
+                );
+                match resources::sample_process(lock.pid) {
+                    Some(usage) => println!(
+                        "CPU: {:.1}% | Memória: {}",
+                        usage.cpu_percent,
+                        resources::format_bytes(usage.rss_bytes)
+                    ),
+                    None => println!("CPU/Memória: indisponível"),
+                }
+            }
+            ServerStatus::StaleLock(lock) => println!(
+                "Estado: parado (lock obsoleto do PID {} removido)",
+                lock.pid
+            ),
+            ServerStatus::Stopped => println!("Estado: parado"),
+        }
+
+        println!(
+            "Disco: worlds/ {} | backups/ {}",
+            resources::format_bytes(resources::dir_size(&work_dir.join("worlds"))),
+            resources::format_bytes(resources::dir_size(&work_dir.join("backups")))
+        );
+
+        let online: Vec<String> = PlayerDatabase::load(work_dir)
+            .map(|database| {
+                database
+                    .online_players()
+                    .into_iter()
+                    .map(|record| record.name.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let max = self
+            .instance
+            .config_manager()
+            .read_property("max-players")
+            .unwrap_or_else(|| "?".to_string());
+        if online.is_empty() {
+            println!("Jogadores online: 0/{}", max);
+        } else {
+            println!(
+                "Jogadores online: {}/{} ({})",
+                online.len(),
+                max,
+                online.join(", ")
+            );
+        }
+
+        println!(
+            "Versão: {}",
+            config::installed_version(work_dir).unwrap_or_else(|| "desconhecida".to_string())
+        );
+
+        let state = InstanceState::load(work_dir);
+        let failed = if state.last_backup_success == Some(false) {
+            " (a última tentativa falhou)"
+        } else {
+            ""
+        };
+        match backup::last_backup_at(work_dir) {
+            Some(at) => println!(
+                "Último backup: {}{}",
+                at.with_timezone(&Local).format("%d/%m/%Y %H:%M"),
+                failed
+            ),
+            None => println!("Último backup: nenhum{}", failed),
+        }
+        if let Some(reason) = state.backups_paused {
+            println!("Backups pausados pelo watchdog: {}", reason);
+        }
+    }
+
     fn get_user_input(&self) -> u32 {
         let mut input = String::new();
         io::stdin()
@@ -270,4 +1026,13 @@ impl Menu {
             .expect("Falha ao ler entrada");
         input.trim().to_string()
     }
+
+    fn get_optional_input(&self) -> Option<String> {
+        let input = self.get_input_string();
+        if input.is_empty() {
+            None
+        } else {
+            Some(input)
+        }
+    }
 }
@@ -0,0 +1,194 @@
+use crate::backup;
+use crate::config;
+use crate::instance_state::InstanceState;
+use crate::instances::Instance;
+use crate::players::PlayerDatabase;
+use crate::resources;
+use chrono::Utc;
+use std::fmt::Write;
+
+// Texto no formato de exposição do Prometheus (0.0.4). Toda métrica leva o
+// rótulo `instance` com o nome da instância.
+struct Exposition {
+    instance: String,
+    output: String,
+}
+
+impl Exposition {
+    fn metric(&mut self, name: &str, kind: &str, help: &str, value: impl Into<f64>) {
+        self.metric_with_labels(name, kind, help, "", value);
+    }
+
+    fn metric_with_labels(
+        &mut self,
+        name: &str,
+        kind: &str,
+        help: &str,
+        labels: &str,
+        value: impl Into<f64>,
+    ) {
+        let _ = writeln!(self.output, "# HELP {} {}", name, help);
+        let _ = writeln!(self.output, "# TYPE {} {}", name, kind);
+        let _ = writeln!(
+            self.output,
+            "{}{{instance=\"{}\"{}}} {}",
+            name,
+            escape_label(&self.instance),
+            labels,
+            value.into()
+        );
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn flag(value: bool) -> f64 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+pub fn render(instance: &Instance) -> String {
+    let mut metrics = Exposition {
+        instance: instance.name.clone(),
+        output: String::new(),
+    };
+    let work_dir = &instance.work_dir;
+    let state = InstanceState::load(work_dir);
+    let lock = instance.running_server();
+
+    metrics.metric(
+        "bedrock_server_up",
+        "gauge",
+        "1 se o bedrock_server está em execução",
+        flag(lock.is_some()),
+    );
+    if let Some(lock) = &lock {
+        metrics.metric(
+            "bedrock_server_uptime_seconds",
+            "gauge",
+            "Tempo desde o início do processo",
+            lock.uptime().num_seconds() as f64,
+        );
+        if let Some(usage) = resources::sample_process(lock.pid) {
+            metrics.metric(
+                "bedrock_process_cpu_percent",
+                "gauge",
+                "Uso de CPU do processo (100 = um núcleo)",
+                usage.cpu_percent,
+            );
+            metrics.metric(
+                "bedrock_process_resident_memory_bytes",
+                "gauge",
+                "Memória residente (RSS) do processo",
+                usage.rss_bytes as f64,
+            );
+            if let Some(threads) = usage.threads {
+                metrics.metric(
+                    "bedrock_process_threads",
+                    "gauge",
+                    "Threads do processo",
+                    threads as f64,
+                );
+            }
+        }
+    }
+    metrics.metric(
+        "bedrock_server_starts_total",
+        "counter",
+        "Inícios do servidor registrados pelo gerenciador, incluindo reinícios",
+        state.start_count as f64,
+    );
+
+    let online = PlayerDatabase::load(work_dir)
+        .map(|database| database.online_players().len())
+        .unwrap_or(0);
+    metrics.metric(
+        "bedrock_players_online",
+        "gauge",
+        "Jogadores conectados",
+        online as f64,
+    );
+    if let Some(max) = instance
+        .config_manager()
+        .read_property("max-players")
+        .and_then(|value| value.parse::<u32>().ok())
+    {
+        metrics.metric(
+            "bedrock_players_max",
+            "gauge",
+            "Limite de jogadores (max-players)",
+            max,
+        );
+    }
+    metrics.metric(
+        "bedrock_world_size_bytes",
+        "gauge",
+        "Tamanho do diretório worlds/",
+        resources::dir_size(&work_dir.join("worlds")) as f64,
+    );
+
+    if let Some(at) = backup::last_backup_at(work_dir) {
+        metrics.metric(
+            "bedrock_last_backup_timestamp_seconds",
+            "gauge",
+            "Momento do último backup (Unix)",
+            at.timestamp() as f64,
+        );
+        metrics.metric(
+            "bedrock_last_backup_age_seconds",
+            "gauge",
+            "Segundos desde o último backup",
+            (Utc::now() - at).num_seconds() as f64,
+        );
+    }
+    if let Some(success) = state.last_backup_success {
+        metrics.metric(
+            "bedrock_last_backup_success",
+            "gauge",
+            "1 se o último backup terminou sem erro",
+            flag(success),
+        );
+    }
+
+    metrics.metric(
+        "bedrock_update_in_progress",
+        "gauge",
+        "1 durante o download ou a atualização do servidor",
+        flag(state.update_in_progress),
+    );
+    if let Some(success) = state.last_update_success {
+        metrics.metric(
+            "bedrock_last_update_success",
+            "gauge",
+            "1 se o último download/atualização terminou sem erro",
+            flag(success),
+        );
+    }
+    if let Some(at) = state.last_update_at {
+        metrics.metric(
+            "bedrock_last_update_timestamp_seconds",
+            "gauge",
+            "Momento do último download/atualização (Unix)",
+            at.timestamp() as f64,
+        );
+    }
+    if let Some(version) = config::installed_version(work_dir) {
+        metrics.metric_with_labels(
+            "bedrock_server_info",
+            "gauge",
+            "Versão instalada do servidor",
+            &format!(",version=\"{}\"", escape_label(&version)),
+            1.0,
+        );
+    }
+
+    metrics.output
+}
@@ -0,0 +1,365 @@
+use crate::events::{EventBus, ServerEvent};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlayerSession {
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub duration_secs: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlayerRecord {
+    pub xuid: String,
+    pub name: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub session_count: u32,
+    pub total_playtime_secs: i64,
+    pub sessions: Vec<PlayerSession>,
+    // Início da sessão atual, se o jogador estiver conectado
+    #[serde(default)]
+    pub online_since: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Copy)]
+pub enum PlayerSort {
+    Playtime,
+    LastSeen,
+}
+
+impl PlayerSort {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "playtime" | "tempo" => Ok(PlayerSort::Playtime),
+            "last-seen" | "visto" => Ok(PlayerSort::LastSeen),
+            _ => Err(format!(
+                "Ordenação inválida: '{}' (use playtime ou last-seen)",
+                value
+            )),
+        }
+    }
+}
+
+// Banco de jogadores indexado por XUID (ou pelo nome, para clientes sem XUID,
+// como os de LAN/offline), alimentado pelos eventos do console
+pub struct PlayerDatabase {
+    path: PathBuf,
+    players: BTreeMap<String, PlayerRecord>,
+}
+
+impl PlayerDatabase {
+    pub fn load(work_dir: &Path) -> Result<Self, String> {
+        let path = work_dir.join("players.json");
+        let players = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| format!("Erro ao ler players.json: {}", e))?;
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Erro ao ler players.json: {}", e))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(PlayerDatabase { path, players })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(&self.players)
+            .map_err(|e| format!("Erro ao serializar players.json: {}", e))?;
+        fs::write(&self.path, content).map_err(|e| format!("Erro ao salvar players.json: {}", e))
+    }
+
+    pub fn record_connect(
+        &mut self,
+        name: &str,
+        xuid: &str,
+        at: DateTime<Utc>,
+    ) -> Result<(), String> {
+        let record = self
+            .players
+            .entry(player_key(name, xuid))
+            .or_insert_with(|| PlayerRecord {
+                xuid: xuid.to_string(),
+                name: name.to_string(),
+                first_seen: at,
+                last_seen: at,
+                session_count: 0,
+                total_playtime_secs: 0,
+                sessions: Vec::new(),
+                online_since: None,
+            });
+
+        record.name = name.to_string();
+        record.last_seen = at;
+        record.session_count += 1;
+        record.online_since = Some(at);
+        self.save()
+    }
+
+    pub fn record_disconnect(
+        &mut self,
+        name: &str,
+        xuid: &str,
+        at: DateTime<Utc>,
+    ) -> Result<(), String> {
+        let Some(record) = self.players.get_mut(&player_key(name, xuid)) else {
+            // Conexão anterior ao início do monitoramento: não há como medir a sessão
+            return Ok(());
+        };
+
+        record.name = name.to_string();
+        record.last_seen = at;
+        close_session(record, at);
+        self.save()
+    }
+
+    // Parada ou queda do servidor: quem estava online sai nesse instante
+    pub fn close_open_sessions(&mut self, at: DateTime<Utc>) -> Result<(), String> {
+        let mut changed = false;
+        for record in self.players.values_mut() {
+            changed |= close_session(record, at);
+        }
+        if changed {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    // Sessões ainda abertas quando o servidor inicia ficaram sem parada
+    // registrada (gerenciador fora do ar); não há como medi-las
+    pub fn discard_open_sessions(&mut self) -> Result<(), String> {
+        let mut changed = false;
+        for record in self.players.values_mut() {
+            if record.online_since.take().is_some() {
+                changed = true;
+            }
+        }
+        if changed {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    pub fn online_players(&self) -> Vec<&PlayerRecord> {
+        self.players
+            .values()
+            .filter(|record| record.online_since.is_some())
+            .collect()
+    }
+
+    pub fn sorted(&self, sort: PlayerSort) -> Vec<&PlayerRecord> {
+        let mut players: Vec<&PlayerRecord> = self.players.values().collect();
+        match sort {
+            PlayerSort::Playtime => players.sort_by_key(|r| Reverse(r.total_playtime_secs)),
+            PlayerSort::LastSeen => players.sort_by_key(|r| Reverse(r.last_seen)),
+        }
+        players
+    }
+
+    pub fn print(&self, sort: PlayerSort) {
+        let players = self.sorted(sort);
+        if players.is_empty() {
+            println!("Nenhum jogador registrado ainda.");
+            return;
+        }
+
+        println!("\n=== Jogadores ===");
+        for record in players {
+            let status = if record.online_since.is_some() {
+                " [online]"
+            } else {
+                ""
+            };
+            let average = if record.sessions.is_empty() {
+                0
+            } else {
+                record.total_playtime_secs / record.sessions.len() as i64
+            };
+            println!(
+                "- {}{} (xuid: {}) | tempo total: {} | sessões: {} | média: {} | primeira vez: {} | última vez: {}",
+                record.name,
+                status,
+                record.xuid,
+                format_playtime(record.total_playtime_secs),
+                record.session_count,
+                format_playtime(average),
+                record.first_seen.with_timezone(&Local).format("%d/%m/%Y %H:%M"),
+                record.last_seen.with_timezone(&Local).format("%d/%m/%Y %H:%M"),
+            );
+        }
+    }
+}
+
+fn player_key(name: &str, xuid: &str) -> String {
+    if xuid.is_empty() {
+        name.to_string()
+    } else {
+        xuid.to_string()
+    }
+}
+
+// Encerra a sessão em aberto, somando a duração ao tempo total
+fn close_session(record: &mut PlayerRecord, at: DateTime<Utc>) -> bool {
+    let Some(started_at) = record.online_since.take() else {
+        return false;
+    };
+    let duration_secs = (at - started_at).num_seconds().max(0);
+    record.last_seen = at;
+    record.total_playtime_secs += duration_secs;
+    record.sessions.push(PlayerSession {
+        started_at,
+        ended_at: at,
+        duration_secs,
+    });
+    true
+}
+
+pub fn format_playtime(secs: i64) -> String {
+    let hours = secs / 3600;
+    let minutes = (secs % 3600) / 60;
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+// Registra conexões e desconexões reportadas pelo console
+pub fn spawn_tracker(events: &EventBus, work_dir: PathBuf) {
+    let receiver = events.subscribe();
+
+    thread::spawn(move || {
+        for event in receiver {
+            if matches!(
+                event,
+                ServerEvent::ConsoleLine(_)
+                    | ServerEvent::ChatMessage { .. }
+                    | ServerEvent::BackupCompleted { .. }
+                    | ServerEvent::BackupFailed { .. }
+                    | ServerEvent::UpdateAvailable { .. }
+                    | ServerEvent::UpdateApplied { .. }
+                    | ServerEvent::ResourceAlert { .. }
+            ) {
+                continue;
+            }
+
+            let now = Utc::now();
+            let mut db = match PlayerDatabase::load(&work_dir) {
+                Ok(db) => db,
+                Err(e) => {
+                    println!("Erro ao carregar estatísticas de jogadores: {}", e);
+                    continue;
+                }
+            };
+
+            let result = match &event {
+                ServerEvent::PlayerConnected { name, xuid } => db.record_connect(name, xuid, now),
+                ServerEvent::PlayerDisconnected { name, xuid } => {
+                    db.record_disconnect(name, xuid, now)
+                }
+                ServerEvent::ServerStarted => db.discard_open_sessions(),
+                ServerEvent::ServerStopped | ServerEvent::ServerCrashed => {
+                    db.close_open_sessions(now)
+                }
+                ServerEvent::ConsoleLine(_)
+                | ServerEvent::ChatMessage { .. }
+                | ServerEvent::BackupCompleted { .. }
+                | ServerEvent::BackupFailed { .. }
+                | ServerEvent::UpdateAvailable { .. }
+                | ServerEvent::UpdateApplied { .. }
+                | ServerEvent::ResourceAlert { .. } => Ok(()),
+            };
+
+            if let Err(e) = result {
+                println!("Erro ao atualizar estatísticas de jogadores: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use chrono::Duration;
+
+    fn temp_db(name: &str) -> PlayerDatabase {
+        PlayerDatabase::load(&test_util::temp_dir(name)).unwrap()
+    }
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap() + Duration::seconds(secs)
+    }
+
+    #[test]
+    fn join_and_leave_add_playtime() {
+        let mut db = temp_db("join-leave");
+        db.record_connect("Steve", "100", at(0)).unwrap();
+        db.record_disconnect("Steve", "100", at(90)).unwrap();
+        db.record_connect("Steve", "100", at(200)).unwrap();
+        db.record_disconnect("Steve", "100", at(230)).unwrap();
+
+        let record = &db.players["100"];
+        assert_eq!(record.session_count, 2);
+        assert_eq!(record.sessions.len(), 2);
+        assert_eq!(record.total_playtime_secs, 120);
+        assert!(record.online_since.is_none());
+        assert_eq!(record.last_seen, at(230));
+    }
+
+    #[test]
+    fn stop_closes_open_sessions_at_event_time() {
+        let mut db = temp_db("stop");
+        db.record_connect("Steve", "100", at(0)).unwrap();
+        db.record_connect("Alex", "200", at(30)).unwrap();
+        db.close_open_sessions(at(60)).unwrap();
+
+        assert_eq!(db.players["100"].total_playtime_secs, 60);
+        assert_eq!(db.players["200"].total_playtime_secs, 30);
+        assert!(db.online_players().is_empty());
+
+        // A desconexão que chega depois da parada não conta de novo
+        db.record_disconnect("Steve", "100", at(70)).unwrap();
+        assert_eq!(db.players["100"].total_playtime_secs, 60);
+    }
+
+    #[test]
+    fn start_discards_stale_sessions() {
+        let mut db = temp_db("start");
+        db.record_connect("Steve", "100", at(0)).unwrap();
+        db.discard_open_sessions().unwrap();
+
+        let record = &db.players["100"];
+        assert!(record.online_since.is_none());
+        assert_eq!(record.total_playtime_secs, 0);
+        assert!(record.sessions.is_empty());
+    }
+
+    #[test]
+    fn players_without_xuid_are_keyed_by_name() {
+        let mut db = temp_db("no-xuid");
+        db.record_connect("Steve", "", at(0)).unwrap();
+        db.record_connect("Alex", "", at(10)).unwrap();
+        db.record_disconnect("Steve", "", at(40)).unwrap();
+
+        assert_eq!(db.players.len(), 2);
+        assert_eq!(db.players["Steve"].total_playtime_secs, 40);
+        assert!(db.players["Alex"].online_since.is_some());
+    }
+
+    #[test]
+    fn sessions_survive_reload() {
+        let mut db = temp_db("reload");
+        db.record_connect("Steve", "100", at(0)).unwrap();
+        let dir = db.path.parent().unwrap().to_path_buf();
+
+        let mut db = PlayerDatabase::load(&dir).unwrap();
+        db.record_disconnect("Steve", "100", at(50)).unwrap();
+        assert_eq!(db.players["100"].total_playtime_secs, 50);
+    }
+}
@@ -0,0 +1,496 @@
+use crate::audit;
+use crate::config_manager::{AllowlistEntry, ConfigManager, Permission};
+use crate::daemon;
+use crate::gamerules::DesiredGamerules;
+use crate::instances::Instance;
+use crate::properties;
+use chrono::Local;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Pacotes ativos do mundo, em worlds/<level-name>/
+const PACK_FILES: &[&str] = &["world_behavior_packs.json", "world_resource_packs.json"];
+
+// Propriedades de cada instância, que um perfil não leva nem altera
+const INSTANCE_PROPERTIES: &[&str] = &["server-port", "server-portv6"];
+
+// Um perfil é um diretório em profiles/<nome>/ com os mesmos arquivos da
+// instância: server.properties (só as chaves que mudam), gamerules.json,
+// allowlist.json, permissions.json e as listas de pacotes. Arquivos
+// ausentes mantêm a configuração atual.
+fn profiles_dir(work_dir: &Path) -> PathBuf {
+    work_dir.join("profiles")
+}
+
+// Cópias da configuração feitas antes de cada `profile apply`
+fn backups_dir(work_dir: &Path) -> PathBuf {
+    profiles_dir(work_dir).join(".backups")
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Nome de perfil inválido: '{}' (use letras, números, - e _)",
+            name
+        ));
+    }
+    Ok(())
+}
+
+fn world_dir(work_dir: &Path) -> PathBuf {
+    ConfigManager::new(work_dir.to_path_buf()).world_dir()
+}
+
+// Perfis em profiles/, em ordem alfabética
+pub fn list(work_dir: &Path) -> Result<Vec<String>, String> {
+    list_dirs(&profiles_dir(work_dir))
+}
+
+fn list_dirs(dir: &Path) -> Result<Vec<String>, String> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(dir).map_err(|e| format!("Erro ao ler {}: {}", dir.display(), e))?;
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+// Conteúdo de um perfil, lido e validado antes de qualquer alteração
+struct Profile {
+    properties: Option<Vec<(String, String)>>,
+    gamerules: bool,
+    allowlist: Option<Vec<AllowlistEntry>>,
+    permissions: Option<Vec<Permission>>,
+    packs: Vec<(&'static str, String)>,
+}
+
+impl Profile {
+    fn load(dir: &Path) -> Result<Self, String> {
+        if !dir.is_dir() {
+            return Err(format!("Perfil não encontrado: {}", dir.display()));
+        }
+        // O diretório do perfil tem o mesmo formato de uma instância
+        let config = ConfigManager::new(dir.to_path_buf());
+
+        let properties = if dir.join("server.properties").exists() {
+            let values: Vec<(String, String)> = config
+                .read_all_properties()?
+                .into_iter()
+                .filter(|(key, _)| !INSTANCE_PROPERTIES.contains(&key.as_str()))
+                .collect();
+            for (key, value) in &values {
+                properties::validate(key, value)?;
+            }
+            Some(values)
+        } else {
+            None
+        };
+
+        let gamerules = dir.join("gamerules.json").exists();
+        if gamerules {
+            DesiredGamerules::load(dir)?;
+        }
+
+        let allowlist = match dir.join("allowlist.json").exists() {
+            true => Some(config.read_allowlist()?),
+            false => None,
+        };
+        let permissions = match dir.join("permissions.json").exists() {
+            true => Some(config.read_permissions()?),
+            false => None,
+        };
+
+        let mut packs = Vec::new();
+        for file in PACK_FILES {
+            let path = dir.join(file);
+            if !path.exists() {
+                continue;
+            }
+            let content =
+                fs::read_to_string(&path).map_err(|e| format!("Erro ao ler {}: {}", file, e))?;
+            match serde_json::from_str::<Value>(&content) {
+                Ok(Value::Array(_)) => packs.push((*file, content)),
+                _ => return Err(format!("{} deve ser uma lista de pacotes", file)),
+            }
+        }
+
+        Ok(Profile {
+            properties,
+            gamerules,
+            allowlist,
+            permissions,
+            packs,
+        })
+    }
+
+    fn parts(&self) -> Vec<&'static str> {
+        let mut parts = Vec::new();
+        if self.properties.is_some() {
+            parts.push("propriedades");
+        }
+        if self.gamerules {
+            parts.push("gamerules");
+        }
+        if self.allowlist.is_some() {
+            parts.push("allowlist");
+        }
+        if self.permissions.is_some() {
+            parts.push("permissões");
+        }
+        if !self.packs.is_empty() {
+            parts.push("pacotes");
+        }
+        parts
+    }
+}
+
+// Copia a configuração atual da instância para `dir`
+fn capture(work_dir: &Path, dir: &Path, all_properties: bool) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Erro ao criar {}: {}", dir.display(), e))?;
+
+    let config = ConfigManager::new(work_dir.to_path_buf());
+    if let Ok(values) = config.read_all_properties() {
+        let content: String = values
+            .iter()
+            .filter(|(key, _)| all_properties || !INSTANCE_PROPERTIES.contains(&key.as_str()))
+            .map(|(key, value)| format!("{}={}\n", key, value))
+            .collect();
+        fs::write(dir.join("server.properties"), content)
+            .map_err(|e| format!("Erro ao copiar server.properties: {}", e))?;
+    }
+
+    let world = world_dir(work_dir);
+    let files = ["gamerules.json", "allowlist.json", "permissions.json"]
+        .iter()
+        .map(|file| (work_dir.join(file), *file))
+        .chain(PACK_FILES.iter().map(|file| (world.join(file), *file)));
+    for (source, file) in files {
+        if source.exists() {
+            fs::copy(&source, dir.join(file))
+                .map_err(|e| format!("Erro ao copiar {}: {}", file, e))?;
+        }
+    }
+    Ok(())
+}
+
+// Grava a configuração atual como um novo perfil, sem as portas
+pub fn save(work_dir: &Path, name: &str) -> Result<PathBuf, String> {
+    validate_name(name)?;
+    let dir = profiles_dir(work_dir).join(name);
+    if dir.exists() {
+        return Err(format!("Já existe um perfil chamado {}", name));
+    }
+    let result = capture(work_dir, &dir, false);
+    audit::record(
+        work_dir,
+        "profile.save",
+        json!({ "profile": name }),
+        &result,
+    );
+    result.map(|_| dir)
+}
+
+// Aplica o perfil, guardando antes uma cópia da configuração atual. Com o
+// servidor rodando, gamerules, allowlist, permissões e as propriedades que
+// aceitam comando valem na hora; as demais propriedades e os pacotes pedem
+// reinício, feito com `warning_secs` de aviso.
+pub fn apply(instance: &Instance, name: &str, warning_secs: u64) -> Result<(), String> {
+    validate_name(name)?;
+    let dir = profiles_dir(&instance.work_dir).join(name);
+    apply_dir(
+        instance,
+        &dir,
+        &format!("perfil {}", name),
+        false,
+        warning_secs,
+    )
+}
+
+// Volta à configuração guardada antes de um `profile apply`; sem carimbo,
+// usa a cópia mais recente
+pub fn restore(instance: &Instance, stamp: Option<&str>, warning_secs: u64) -> Result<(), String> {
+    let stamps = list_dirs(&backups_dir(&instance.work_dir))?;
+    let stamp = match stamp {
+        Some(stamp) => {
+            validate_name(stamp)?;
+            stamp.to_string()
+        }
+        None => stamps
+            .last()
+            .cloned()
+            .ok_or("Nenhuma cópia de configuração encontrada")?,
+    };
+    let dir = backups_dir(&instance.work_dir).join(&stamp);
+    apply_dir(
+        instance,
+        &dir,
+        &format!("cópia {}", stamp),
+        true,
+        warning_secs,
+    )
+}
+
+// Diretório novo para a cópia, com carimbo em milissegundos e um sufixo
+// se dois `apply` ainda assim coincidirem
+fn new_backup_dir(work_dir: &Path) -> Result<(String, PathBuf), String> {
+    let dir = backups_dir(work_dir);
+    fs::create_dir_all(&dir).map_err(|e| format!("Erro ao criar {}: {}", dir.display(), e))?;
+    let base = Local::now().format("%Y%m%d-%H%M%S-%3f").to_string();
+    for n in 0.. {
+        let stamp = match n {
+            0 => base.clone(),
+            n => format!("{}-{}", base, n),
+        };
+        let path = dir.join(&stamp);
+        match fs::create_dir(&path) {
+            Ok(_) => return Ok((stamp, path)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Erro ao criar {}: {}", path.display(), e)),
+        }
+    }
+    unreachable!()
+}
+
+// Com `exact`, arquivos opcionais ausentes em `dir` também são removidos da
+// instância, como numa cópia feita por `capture`
+fn apply_dir(
+    instance: &Instance,
+    dir: &Path,
+    label: &str,
+    exact: bool,
+    warning_secs: u64,
+) -> Result<(), String> {
+    let profile = Profile::load(dir)?;
+    let work_dir = &instance.work_dir;
+    let (stamp, backup) = new_backup_dir(work_dir)?;
+    capture(work_dir, &backup, true)?;
+    println!("Configuração atual guardada em {}", backup.display());
+
+    let result = write_profile(instance, &profile, dir, exact);
+    audit::record(
+        work_dir,
+        "profile.apply",
+        json!({ "profile": label, "parts": profile.parts(), "backup": stamp }),
+        &result,
+    );
+    let needs_restart = result?;
+
+    if !instance.is_running() {
+        println!(
+            "Configuração aplicada ({}); vale no próximo início do servidor",
+            label
+        );
+        return Ok(());
+    }
+    if needs_restart {
+        println!("Reiniciando o servidor para aplicar {}...", label);
+        daemon::restart_with_warnings(instance, warning_secs, label)?;
+    }
+    println!("Configuração aplicada ({})", label);
+    Ok(())
+}
+
+// Grava os arquivos do perfil e devolve se o servidor precisa reiniciar
+fn write_profile(
+    instance: &Instance,
+    profile: &Profile,
+    dir: &Path,
+    exact: bool,
+) -> Result<bool, String> {
+    let work_dir = &instance.work_dir;
+    let config = instance.config_manager();
+    let running = instance.is_running();
+    let mut needs_restart = false;
+
+    if let Some(values) = &profile.properties {
+        let changed = if exact {
+            // A cópia substitui o arquivo inteiro, mantendo só as portas atuais
+            let mut values: BTreeMap<String, String> = values.iter().cloned().collect();
+            let current = config.read_all_properties().unwrap_or_default();
+            for key in INSTANCE_PROPERTIES {
+                if let Some(value) = current.get(*key) {
+                    values.insert(key.to_string(), value.clone());
+                }
+            }
+            config.replace_properties(&values)?
+        } else {
+            let updates: Vec<(&str, String)> = values
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.clone()))
+                .collect();
+            config.set_properties(&updates)?
+        };
+        needs_restart |= !properties::apply_live(instance, &changed)?.is_empty();
+    }
+
+    if profile.gamerules {
+        fs::copy(dir.join("gamerules.json"), work_dir.join("gamerules.json"))
+            .map_err(|e| format!("Erro ao copiar gamerules.json: {}", e))?;
+        if running {
+            DesiredGamerules::load(work_dir)?.apply(&instance.admin())?;
+        }
+    } else if exact && work_dir.join("gamerules.json").exists() {
+        fs::remove_file(work_dir.join("gamerules.json"))
+            .map_err(|e| format!("Erro ao remover gamerules.json: {}", e))?;
+    }
+
+    if let Some(entries) = &profile.allowlist {
+        config.save_allowlist(entries)?;
+        if running {
+            instance.admin().send_command("allowlist reload")?;
+        }
+    }
+    if let Some(permissions) = &profile.permissions {
+        config.save_permissions(permissions)?;
+        if running {
+            instance.admin().send_command("permission reload")?;
+        }
+    }
+
+    let world = world_dir(work_dir);
+    for file in PACK_FILES {
+        let path = world.join(file);
+        let before = fs::read_to_string(&path).ok();
+        match profile.packs.iter().find(|(name, _)| name == file) {
+            Some((_, content)) if before.as_ref() != Some(content) => {
+                config.write_config_file(file, content)?;
+                needs_restart = true;
+            }
+            None if exact && before.is_some() => {
+                fs::remove_file(&path).map_err(|e| format!("Erro ao remover {}: {}", file, e))?;
+                needs_restart = true;
+            }
+            _ => {}
+        }
+    }
+    Ok(needs_restart)
+}
+
+pub fn print_list(work_dir: &Path) -> Result<(), String> {
+    println!("\n=== Perfis de Configuração ===");
+    let names = list(work_dir)?;
+    if names.is_empty() {
+        println!(
+            "Nenhum perfil: crie um com `profile save <nome>` ou em {}",
+            profiles_dir(work_dir).display()
+        );
+    }
+    for name in names {
+        match Profile::load(&profiles_dir(work_dir).join(&name)) {
+            Ok(profile) => println!("- {}: {}", name, profile.parts().join(", ")),
+            Err(e) => println!("- {}: inválido ({})", name, e),
+        }
+    }
+
+    let stamps = list_dirs(&backups_dir(work_dir))?;
+    if let Some(last) = stamps.last() {
+        println!(
+            "\n{} cópia(s) da configuração anterior; a mais recente é {}",
+            stamps.len(),
+            last
+        );
+    }
+    Ok(())
+}
+
+pub fn print_profile(work_dir: &Path, name: &str) -> Result<(), String> {
+    validate_name(name)?;
+    let dir = profiles_dir(work_dir).join(name);
+    let profile = Profile::load(&dir)?;
+    println!("\n=== Perfil {} ===", name);
+    if let Some(values) = &profile.properties {
+        println!("Propriedades:");
+        for (key, value) in values {
+            println!("  {}={}", key, value);
+        }
+    }
+    if profile.gamerules {
+        let content = fs::read_to_string(dir.join("gamerules.json")).unwrap_or_default();
+        println!(
+            "Gamerules: {}",
+            content.split_whitespace().collect::<String>()
+        );
+    }
+    if let Some(entries) = &profile.allowlist {
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        println!("Allowlist: {}", names.join(", "));
+    }
+    if let Some(permissions) = &profile.permissions {
+        println!("Permissões: {} jogador(es)", permissions.len());
+    }
+    for (file, content) in &profile.packs {
+        let count = serde_json::from_str::<Vec<Value>>(content)
+            .map(|packs| packs.len())
+            .unwrap_or(0);
+        println!("{}: {} pacote(s)", file, count);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instances::InstanceRegistry;
+    use crate::test_util;
+
+    #[test]
+    fn restore_replaces_the_whole_properties_file() {
+        let _dir = test_util::enter_temp_dir("profiles-restore");
+        let instance = InstanceRegistry::load()
+            .unwrap()
+            .get("default")
+            .unwrap()
+            .clone();
+        fs::create_dir_all(&instance.work_dir).unwrap();
+        let config = instance.config_manager();
+        config.initialize_configs().unwrap();
+        let before = config.read_all_properties().unwrap();
+
+        let profile = profiles_dir(&instance.work_dir).join("evento");
+        fs::create_dir_all(&profile).unwrap();
+        fs::write(
+            profile.join("server.properties"),
+            "gamemode=adventure\nchave-do-evento=1\n",
+        )
+        .unwrap();
+        apply(&instance, "evento", 0).unwrap();
+        assert_eq!(
+            config.read_property("gamemode").as_deref(),
+            Some("adventure")
+        );
+        assert_eq!(
+            config.read_property("chave-do-evento").as_deref(),
+            Some("1")
+        );
+
+        restore(&instance, None, 0).unwrap();
+        assert_eq!(config.read_all_properties().unwrap(), before);
+        assert!(config.read_property("chave-do-evento").is_none());
+
+        // Cada `apply` e `restore` guardou uma cópia com carimbo próprio
+        let stamps = list_dirs(&backups_dir(&instance.work_dir)).unwrap();
+        assert_eq!(stamps.len(), 2);
+        assert_ne!(stamps[0], stamps[1]);
+    }
+
+    #[test]
+    fn backup_dirs_do_not_collide() {
+        let dir = test_util::temp_dir("profiles-stamps");
+        let stamps: Vec<String> = (0..5).map(|_| new_backup_dir(&dir).unwrap().0).collect();
+        let unique: std::collections::BTreeSet<&String> = stamps.iter().collect();
+        assert_eq!(unique.len(), stamps.len());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,521 @@
+use crate::instances::Instance;
+use crate::manager_config;
+use std::collections::BTreeMap;
+
+pub enum PropertyKind {
+    Text,
+    // Vira nome de pasta (worlds/<level-name>): sem barras nem "." e ".."
+    FolderName,
+    Number { min: i64, max: i64 },
+    Decimal,
+    Bool,
+    Choice(&'static [&'static str]),
+}
+
+// Propriedade conhecida do server.properties. Só as que têm `label`
+// aparecem no editor interativo. As que têm `live` valem na hora pelo
+// comando gerado a partir do novo valor; as demais exigem reinício.
+pub struct PropertyDef {
+    pub key: &'static str,
+    pub default: &'static str,
+    pub kind: PropertyKind,
+    pub label: Option<&'static str>,
+    pub help: &'static str,
+    pub live: Option<fn(&str) -> String>,
+}
+
+const fn property(
+    key: &'static str,
+    default: &'static str,
+    kind: PropertyKind,
+    label: Option<&'static str>,
+    help: &'static str,
+) -> PropertyDef {
+    PropertyDef {
+        key,
+        default,
+        kind,
+        label,
+        help,
+        live: None,
+    }
+}
+
+impl PropertyDef {
+    const fn live(self, command: fn(&str) -> String) -> PropertyDef {
+        PropertyDef {
+            live: Some(command),
+            ..self
+        }
+    }
+}
+
+const PORT: PropertyKind = PropertyKind::Number { min: 1, max: 65535 };
+
+// Valores padrão do server.properties, na ordem em que são gravados
+pub const PROPERTIES: &[PropertyDef] = &[
+    property(
+        "server-name",
+        "Dedicated Server",
+        PropertyKind::Text,
+        Some("Nome do Servidor"),
+        "Digite o nome do servidor",
+    ),
+    property(
+        "server-port",
+        "19132",
+        PORT,
+        Some("Porta do Servidor"),
+        "Digite a porta do servidor (1-65535)",
+    ),
+    property(
+        "server-portv6",
+        "19133",
+        PORT,
+        Some("Porta IPv6 do Servidor"),
+        "Digite a porta IPv6 do servidor (1-65535)",
+    ),
+    property(
+        "gamemode",
+        "survival",
+        PropertyKind::Choice(&["survival", "creative", "adventure"]),
+        Some("Modo de Jogo"),
+        "Escolha o modo de jogo",
+    )
+    .live(|value| format!("defaultgamemode {}", value)),
+    property(
+        "difficulty",
+        "normal",
+        PropertyKind::Choice(&["peaceful", "easy", "normal", "hard"]),
+        Some("Dificuldade"),
+        "Escolha a dificuldade",
+    )
+    .live(|value| format!("difficulty {}", value)),
+    property(
+        "allow-cheats",
+        "false",
+        PropertyKind::Bool,
+        Some("Permitir Cheats"),
+        "Libera comandos de cheat para os jogadores",
+    )
+    .live(|value| format!("changesetting allow-cheats {}", value)),
+    property(
+        "max-players",
+        "10",
+        PropertyKind::Number { min: 1, max: 1000 },
+        Some("Máximo de Jogadores"),
+        "Digite o número máximo de jogadores",
+    ),
+    property(
+        "online-mode",
+        "true",
+        PropertyKind::Bool,
+        Some("Modo Online"),
+        "Exige autenticação Xbox Live",
+    ),
+    property(
+        "white-list",
+        "false",
+        PropertyKind::Bool,
+        Some("Usar Whitelist"),
+        "Só jogadores do allowlist.json podem entrar",
+    )
+    .live(|value| format!("allowlist {}", if value == "true" { "on" } else { "off" })),
+    property(
+        "view-distance",
+        "32",
+        PropertyKind::Number { min: 5, max: 96 },
+        Some("Distância de Visão"),
+        "Digite a distância de visão em chunks (mínimo 5)",
+    ),
+    property(
+        "tick-distance",
+        "4",
+        PropertyKind::Number { min: 4, max: 12 },
+        Some("Distância de Simulação"),
+        "Digite a distância de simulação em chunks (4-12)",
+    ),
+    property(
+        "player-idle-timeout",
+        "30",
+        PropertyKind::Number { min: 0, max: 1440 },
+        Some("Tempo de Inatividade"),
+        "Minutos até expulsar jogadores parados (0 desliga)",
+    ),
+    property(
+        "max-threads",
+        "8",
+        PropertyKind::Number { min: 0, max: 256 },
+        None,
+        "Threads usadas pelo servidor (0 usa todas)",
+    ),
+    property(
+        "level-name",
+        "Bedrock level",
+        PropertyKind::FolderName,
+        None,
+        "Nome do mundo, que é também a pasta em worlds/",
+    ),
+    property(
+        "level-seed",
+        "",
+        PropertyKind::Text,
+        None,
+        "Semente usada ao gerar um mundo novo (vazio sorteia uma)",
+    ),
+    property(
+        "level-type",
+        "DEFAULT",
+        PropertyKind::Choice(&["DEFAULT", "FLAT", "LEGACY"]),
+        None,
+        "Tipo de terreno gerado em um mundo novo",
+    ),
+    property(
+        "default-player-permission-level",
+        "member",
+        PropertyKind::Choice(&["visitor", "member", "operator"]),
+        Some("Permissão Padrão"),
+        "Escolha a permissão de jogadores novos",
+    ),
+    property(
+        "texturepack-required",
+        "false",
+        PropertyKind::Bool,
+        None,
+        "Obriga os jogadores a usar os pacotes do mundo",
+    ),
+    property(
+        "content-log-file-enabled",
+        "false",
+        PropertyKind::Bool,
+        None,
+        "Grava erros de conteúdo em arquivo",
+    ),
+    property(
+        "compression-threshold",
+        "1",
+        PropertyKind::Number { min: 0, max: 65535 },
+        None,
+        "Tamanho mínimo de pacote a comprimir",
+    ),
+    property(
+        "server-authoritative-movement",
+        "server-auth",
+        PropertyKind::Choice(&["client-auth", "server-auth", "server-auth-with-rewind"]),
+        None,
+        "Quem valida o movimento dos jogadores",
+    ),
+    property(
+        "player-movement-score-threshold",
+        "20",
+        PropertyKind::Number { min: 0, max: 1000 },
+        None,
+        "Divergências toleradas antes de reportar movimento inválido",
+    ),
+    property(
+        "player-movement-action-direction-threshold",
+        "0.85",
+        PropertyKind::Decimal,
+        None,
+        "Tolerância entre a direção do olhar e a do ataque",
+    ),
+    property(
+        "player-movement-distance-threshold",
+        "0.3",
+        PropertyKind::Decimal,
+        None,
+        "Diferença de posição tolerada entre cliente e servidor",
+    ),
+    property(
+        "player-movement-duration-threshold-in-ms",
+        "500",
+        PropertyKind::Number { min: 0, max: 60000 },
+        None,
+        "Tempo que a divergência pode durar antes de ser reportada",
+    ),
+    property(
+        "correct-player-movement",
+        "false",
+        PropertyKind::Bool,
+        None,
+        "Corrige a posição do cliente quando o movimento é inválido",
+    ),
+    property(
+        "server-authoritative-block-breaking",
+        "false",
+        PropertyKind::Bool,
+        None,
+        "O servidor valida a quebra de blocos",
+    ),
+];
+
+// Perfis embutidos; o manager.json pode sobrescrevê-los ou criar outros em
+// property_profiles
+const BUILTIN_PROFILES: &[(&str, &[(&str, &str)])] = &[
+    (
+        "survival-smp",
+        &[
+            ("gamemode", "survival"),
+            ("difficulty", "hard"),
+            ("allow-cheats", "false"),
+            ("max-players", "20"),
+            ("white-list", "true"),
+        ],
+    ),
+    (
+        "creative-build",
+        &[
+            ("gamemode", "creative"),
+            ("difficulty", "peaceful"),
+            ("allow-cheats", "true"),
+            ("player-idle-timeout", "0"),
+        ],
+    ),
+];
+
+pub fn find(key: &str) -> Option<&'static PropertyDef> {
+    PROPERTIES.iter().find(|property| property.key == key)
+}
+
+// Propriedades fora do registro também exigem reinício
+pub fn needs_restart(key: &str) -> bool {
+    find(key).is_none_or(|property| property.live.is_none())
+}
+
+// Aplica com comandos as propriedades alteradas que valem na hora e devolve
+// as que só valem depois de reiniciar. Com o servidor parado, nada é enviado
+// e nada exige reinício: tudo vale no próximo início.
+pub fn apply_live(instance: &Instance, changed: &[String]) -> Result<Vec<String>, String> {
+    if !instance.is_running() {
+        return Ok(Vec::new());
+    }
+    let (live, restart): (Vec<&String>, Vec<&String>) =
+        changed.iter().partition(|key| !needs_restart(key));
+    let restart = restart.into_iter().cloned().collect();
+
+    let config = instance.config_manager();
+    let admin = instance.admin();
+    for key in live {
+        let (Some(command), Some(value)) = (
+            find(key).and_then(|property| property.live),
+            config.read_property(key),
+        ) else {
+            continue;
+        };
+        admin.send_command(&command(&value))?;
+        println!("{}={} aplicado no servidor", key, value);
+    }
+    Ok(restart)
+}
+
+// Confere o valor de uma propriedade conhecida; as demais são aceitas como
+// estão, já que o servidor tem chaves que o gerenciador não conhece
+pub fn validate(key: &str, value: &str) -> Result<(), String> {
+    // Caracteres de controle (\r, \n, tabs...) quebrariam o arquivo ou o
+    // comando gerado para as propriedades que valem na hora
+    if key.is_empty() || key.contains('=') || key.starts_with('#') || key.contains(char::is_control)
+    {
+        return Err(format!("Propriedade inválida: '{}'", key));
+    }
+    if value.contains(char::is_control) {
+        return Err(format!("Valor inválido para {}", key));
+    }
+    let Some(property) = find(key) else {
+        return Ok(());
+    };
+    let valid = match &property.kind {
+        PropertyKind::Text => true,
+        PropertyKind::FolderName => {
+            !value.trim().is_empty()
+                && value != "."
+                && value != ".."
+                && !value.contains(['/', '\\'])
+        }
+        PropertyKind::Number { min, max } => {
+            matches!(value.parse::<i64>(), Ok(n) if (*min..=*max).contains(&n))
+        }
+        PropertyKind::Decimal => value.parse::<f64>().is_ok(),
+        PropertyKind::Bool => value == "true" || value == "false",
+        PropertyKind::Choice(options) => options.contains(&value),
+    };
+    if valid {
+        return Ok(());
+    }
+    Err(match &property.kind {
+        PropertyKind::Number { min, max } => {
+            format!("{} deve ser um número entre {} e {}", key, min, max)
+        }
+        PropertyKind::Decimal => format!("{} deve ser um número decimal", key),
+        PropertyKind::Bool => format!("{} deve ser true ou false", key),
+        PropertyKind::Choice(options) => {
+            format!("{} deve ser um entre: {}", key, options.join(", "))
+        }
+        PropertyKind::FolderName => format!(
+            "{} não pode ser vazio, \".\", \"..\" nem conter barras",
+            key
+        ),
+        PropertyKind::Text => unreachable!(),
+    })
+}
+
+fn user_profiles() -> &'static BTreeMap<String, BTreeMap<String, String>> {
+    &manager_config::settings().property_profiles
+}
+
+// Perfis disponíveis, embutidos e do manager.json
+pub fn profile_names() -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_PROFILES
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .chain(user_profiles().keys().cloned())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+// Valores do perfil; os do manager.json substituem chave a chave os do
+// perfil embutido de mesmo nome
+pub fn profile(name: &str) -> Result<BTreeMap<String, String>, String> {
+    let builtin = BUILTIN_PROFILES
+        .iter()
+        .find(|(builtin, _)| *builtin == name);
+    let user = user_profiles().get(name);
+    if builtin.is_none() && user.is_none() {
+        return Err(format!(
+            "Perfil desconhecido: {} (use {})",
+            name,
+            profile_names().join(", ")
+        ));
+    }
+
+    let mut values: BTreeMap<String, String> = builtin
+        .into_iter()
+        .flat_map(|(_, values)| values.iter())
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    values.extend(
+        user.into_iter()
+            .flatten()
+            .map(|(k, v)| (k.clone(), v.clone())),
+    );
+    Ok(values)
+}
+
+// Valores usados ao criar um server.properties: os do registro, depois os
+// default_properties do manager.json e por fim os do perfil. Chaves fora do
+// registro vão para o final.
+pub fn defaults(profile_name: Option<&str>) -> Result<Vec<(String, String)>, String> {
+    let mut overrides = manager_config::settings().default_properties.clone();
+    if let Some(name) = profile_name {
+        overrides.extend(profile(name)?);
+    }
+    for (key, value) in &overrides {
+        validate(key, value)?;
+    }
+
+    let mut values: Vec<(String, String)> = PROPERTIES
+        .iter()
+        .map(|property| {
+            let value = overrides
+                .remove(property.key)
+                .unwrap_or_else(|| property.default.to_string());
+            (property.key.to_string(), value)
+        })
+        .collect();
+    values.extend(overrides);
+    Ok(values)
+}
+
+pub fn default_file_content(profile_name: Option<&str>) -> Result<String, String> {
+    Ok(defaults(profile_name)?
+        .iter()
+        .map(|(key, value)| format!("{}={}\n", key, value))
+        .collect())
+}
+
+pub fn print_defaults(profile_name: Option<&str>) -> Result<(), String> {
+    match profile_name {
+        Some(name) => println!("\n=== Propriedades Padrão (perfil {}) ===", name),
+        None => println!("\n=== Propriedades Padrão ==="),
+    }
+    for (key, value) in defaults(profile_name)? {
+        let changed = find(&key).is_none_or(|property| property.default != value);
+        println!("{}={}{}", key, value, if changed { "  *" } else { "" });
+    }
+    println!("(* difere do padrão do servidor)");
+    Ok(())
+}
+
+pub fn print_profiles() -> Result<(), String> {
+    println!("\n=== Perfis de Propriedades ===");
+    for name in profile_names() {
+        let values = profile(&name)?;
+        let source = match (
+            BUILTIN_PROFILES.iter().any(|(builtin, _)| *builtin == name),
+            user_profiles().contains_key(&name),
+        ) {
+            (true, true) => "embutido, alterado no manager.json",
+            (true, false) => "embutido",
+            _ => "manager.json",
+        };
+        let summary: Vec<String> = values
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        println!("{} ({})", name, source);
+        println!("  {}", summary.join(", "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_control_characters() {
+        assert!(validate("server-name", "Meu servidor").is_ok());
+        for value in ["a\nb", "a\rb", "a\tb", "a\u{1b}b"] {
+            assert!(validate("server-name", value).is_err(), "{:?}", value);
+            assert!(
+                validate("chave-desconhecida", value).is_err(),
+                "{:?}",
+                value
+            );
+        }
+        assert!(validate("chave\r", "x").is_err());
+        assert!(validate("a=b", "x").is_err());
+        assert!(validate("#a", "x").is_err());
+    }
+
+    #[test]
+    fn validates_level_properties() {
+        assert_eq!(find("level-name").unwrap().default, "Bedrock level");
+        assert_eq!(find("level-seed").unwrap().default, "");
+        assert_eq!(find("level-type").unwrap().default, "DEFAULT");
+
+        assert!(validate("level-name", "Meu mundo").is_ok());
+        for value in ["", " ", ".", "..", "../fora", "a/b", "a\\b"] {
+            assert!(validate("level-name", value).is_err(), "{:?}", value);
+        }
+        assert!(validate("level-seed", "").is_ok());
+        assert!(validate("level-seed", "-123456").is_ok());
+        assert!(validate("level-type", "FLAT").is_ok());
+        assert!(validate("level-type", "flat").is_err());
+    }
+
+    #[test]
+    fn validates_kinds() {
+        assert!(validate("server-port", "19132").is_ok());
+        assert!(validate("server-port", "0").is_err());
+        assert!(validate("server-port", "porta").is_err());
+        assert!(validate("allow-cheats", "sim").is_err());
+        assert!(validate("gamemode", "creative").is_ok());
+        assert!(validate("player-movement-distance-threshold", "0.5").is_ok());
+        assert!(validate("player-movement-distance-threshold", "x").is_err());
+        assert!(!needs_restart("difficulty"));
+        assert!(needs_restart("level-name"));
+        assert!(needs_restart("chave-desconhecida"));
+    }
+}
@@ -0,0 +1,76 @@
+use std::fs;
+use std::path::Path;
+use std::thread;
+use sysinfo::{Disks, Pid, ProcessRefreshKind, System, MINIMUM_CPU_UPDATE_INTERVAL};
+
+// Uso de recursos do processo do servidor em um instante
+pub struct ProcessUsage {
+    // Percentual de um núcleo; passa de 100 quando usa vários núcleos
+    pub cpu_percent: f32,
+    pub rss_bytes: u64,
+    // Disponível apenas no Linux
+    pub threads: Option<usize>,
+}
+
+// O uso de CPU é a diferença entre duas leituras, então a função espera o
+// intervalo mínimo do sysinfo (cerca de 200 ms) antes de responder
+pub fn sample_process(pid: u32) -> Option<ProcessUsage> {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    let refresh = ProcessRefreshKind::new().with_cpu().with_memory();
+    system.refresh_pids_specifics(&[pid], refresh);
+    thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+    system.refresh_pids_specifics(&[pid], refresh);
+
+    let process = system.process(pid)?;
+    Some(ProcessUsage {
+        cpu_percent: process.cpu_usage(),
+        rss_bytes: process.memory(),
+        // As tarefas listadas pelo sysinfo não incluem a thread principal
+        threads: process.tasks().map(|tasks| tasks.len() + 1),
+    })
+}
+
+// Espaço livre no disco que contém `path` (o ponto de montagem mais
+// específico entre os que são prefixo do caminho)
+pub fn free_disk_space(path: &Path) -> Option<u64> {
+    let path = path.canonicalize().ok()?;
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+// Ex: 1536 -> "1.5 KB"
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+// Tamanho total dos arquivos de um diretório, recursivamente
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
@@ -0,0 +1,462 @@
+use crate::audit;
+use crate::backup;
+use crate::ban_list::parse_duration;
+use crate::daemon;
+use crate::events::EventBus;
+use crate::instance_state::{InstanceState, PendingRestart};
+use crate::instances::Instance;
+use chrono::{DateTime, Local, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Intervalo entre verificações das tarefas
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+// Antecedência dos avisos de um reinício agendado
+const RESTART_WARNING_SECS: i64 = 300;
+
+fn default_warning_secs() -> u64 {
+    300
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobAction {
+    // Reinicia o servidor avisando os jogadores antes
+    Restart {
+        #[serde(default = "default_warning_secs")]
+        warning_secs: u64,
+    },
+    // Mensagem no chat, como um MOTD
+    Say {
+        message: String,
+    },
+    Backup,
+    // Comandos do console executados em ordem
+    Commands {
+        commands: Vec<String>,
+    },
+}
+
+impl JobAction {
+    fn describe(&self) -> String {
+        match self {
+            JobAction::Restart { warning_secs } => {
+                format!("reiniciar (aviso de {}s)", warning_secs)
+            }
+            JobAction::Say { message } => format!("say \"{}\"", message),
+            JobAction::Backup => "backup dos mundos".to_string(),
+            JobAction::Commands { commands } => format!("comandos: {}", commands.join("; ")),
+        }
+    }
+
+    // Antecedência com que a ação começa para terminar no horário agendado
+    fn lead_secs(&self) -> u64 {
+        match self {
+            JobAction::Restart { warning_secs } => *warning_secs,
+            _ => 0,
+        }
+    }
+}
+
+// Tarefa recorrente. Roda a cada `every` (30m, 6h, 1d) ou todo dia no
+// horário `at` (04:00, horário local).
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Job {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub every: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at: Option<String>,
+    pub action: JobAction,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub paused: bool,
+}
+
+enum Schedule {
+    Every(chrono::Duration),
+    Daily(NaiveTime),
+}
+
+impl Job {
+    fn schedule(&self) -> Result<Schedule, String> {
+        match (&self.every, &self.at) {
+            (Some(every), None) => parse_duration(every).map(Schedule::Every),
+            (None, Some(at)) => NaiveTime::parse_from_str(at, "%H:%M")
+                .map(Schedule::Daily)
+                .map_err(|_| format!("Horário inválido: '{}' (use HH:MM)", at)),
+            _ => Err(format!(
+                "A tarefa {} deve definir apenas um entre every e at",
+                self.name
+            )),
+        }
+    }
+
+    fn describe_schedule(&self) -> String {
+        match (&self.every, &self.at) {
+            (Some(every), None) => format!("a cada {}", every),
+            (None, Some(at)) => format!("diariamente às {}", at),
+            _ => "agenda inválida".to_string(),
+        }
+    }
+
+    // Próxima execução depois de `base` (a última execução ou o início do
+    // agendador)
+    fn next_run(&self, base: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+        match self.schedule()? {
+            Schedule::Every(interval) => Ok(base + interval),
+            Schedule::Daily(time) => next_daily(time, base),
+        }
+    }
+}
+
+// Próxima ocorrência do horário local `time` depois de `base`
+fn next_daily(time: NaiveTime, base: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let mut date = base.with_timezone(&Local).date_naive();
+    // Horários que não existem por causa do horário de verão passam para o
+    // dia seguinte
+    loop {
+        if let Some(candidate) = date.and_time(time).and_local_timezone(Local).earliest() {
+            let candidate = candidate.with_timezone(&Utc);
+            if candidate > base {
+                return Ok(candidate);
+            }
+        }
+        date = date.succ_opt().ok_or("Data fora do intervalo suportado")?;
+    }
+}
+
+// Horário de um reinício agendado: daqui a um intervalo (30m, 2h) ou no
+// próximo HH:MM
+pub fn parse_when(input: &str) -> Result<DateTime<Utc>, String> {
+    if input.contains(':') {
+        let time = NaiveTime::parse_from_str(input, "%H:%M")
+            .map_err(|_| format!("Horário inválido: '{}' (use HH:MM)", input))?;
+        return next_daily(time, Utc::now());
+    }
+    Ok(Utc::now() + parse_duration(input)?)
+}
+
+// Agenda um reinício único, executado pelo processo que acompanha o
+// servidor; os jogadores são avisados nos últimos minutos
+pub fn schedule_restart(work_dir: &Path, at: DateTime<Utc>, reason: &str) -> Result<(), String> {
+    let result = if at > Utc::now() {
+        InstanceState::update(work_dir, |state| {
+            state.pending_restart = Some(PendingRestart {
+                at,
+                reason: reason.to_string(),
+            })
+        });
+        Ok(())
+    } else {
+        Err("O horário do reinício já passou".to_string())
+    };
+    audit::record(
+        work_dir,
+        "schedule.restart",
+        json!({ "at": at, "reason": reason }),
+        &result,
+    );
+    result
+}
+
+pub fn cancel_restart(work_dir: &Path) -> Result<(), String> {
+    let result = match InstanceState::load(work_dir).pending_restart {
+        Some(_) => {
+            InstanceState::update(work_dir, |state| state.pending_restart = None);
+            Ok(())
+        }
+        None => Err("Nenhum reinício agendado".to_string()),
+    };
+    audit::record(work_dir, "schedule.cancel_restart", json!({}), &result);
+    result
+}
+
+// Começa o reinício agendado quando faltam RESTART_WARNING_SECS ou menos
+fn run_pending_restart(instance: &Instance) {
+    let Some(pending) = InstanceState::load(&instance.work_dir).pending_restart else {
+        return;
+    };
+    let remaining = (pending.at - Utc::now()).num_seconds();
+    if remaining > RESTART_WARNING_SECS {
+        return;
+    }
+    InstanceState::update(&instance.work_dir, |state| state.pending_restart = None);
+    if !instance.is_running() {
+        return;
+    }
+    println!("[{}] Reinício agendado: {}", instance.name, pending.reason);
+    if let Err(e) =
+        daemon::restart_with_warnings(instance, remaining.max(0) as u64, &pending.reason)
+    {
+        println!("[{}] Erro ao reiniciar: {}", instance.name, e);
+    }
+}
+
+// Tarefas da instância, em schedule.json. Exemplo:
+// { "jobs": [
+//     { "name": "reinicio", "at": "04:00", "action": { "type": "restart" } },
+//     { "name": "motd", "every": "30m",
+//       "action": { "type": "say", "message": "Bem-vindo!" } } ] }
+#[derive(Serialize, Deserialize, Default)]
+pub struct JobSchedule {
+    #[serde(default)]
+    pub jobs: Vec<Job>,
+}
+
+impl JobSchedule {
+    pub fn load(work_dir: &Path) -> Result<Self, String> {
+        let path = work_dir.join("schedule.json");
+        if !path.exists() {
+            return Ok(JobSchedule::default());
+        }
+        let content =
+            fs::read_to_string(&path).map_err(|e| format!("Erro ao ler schedule.json: {}", e))?;
+        let schedule: JobSchedule = serde_json::from_str(&content)
+            .map_err(|e| format!("Erro ao ler schedule.json: {}", e))?;
+        for job in &schedule.jobs {
+            job.schedule()?;
+        }
+        Ok(schedule)
+    }
+
+    fn save(&self, work_dir: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Erro ao serializar schedule.json: {}", e))?;
+        fs::write(work_dir.join("schedule.json"), content)
+            .map_err(|e| format!("Erro ao salvar schedule.json: {}", e))
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Job, String> {
+        self.jobs
+            .iter()
+            .find(|job| job.name == name)
+            .ok_or_else(|| format!("Tarefa {} não encontrada", name))
+    }
+
+    // Pausa ou retoma uma tarefa, gravando no schedule.json para valer
+    // também no daemon
+    pub fn set_paused(&mut self, work_dir: &Path, name: &str, paused: bool) -> Result<(), String> {
+        let job = self
+            .jobs
+            .iter_mut()
+            .find(|job| job.name == name)
+            .ok_or_else(|| format!("Tarefa {} não encontrada", name))?;
+        job.paused = paused;
+        let result = self.save(work_dir);
+        audit::record(
+            work_dir,
+            if paused {
+                "schedule.pause"
+            } else {
+                "schedule.resume"
+            },
+            json!({ "job": name }),
+            &result,
+        );
+        result
+    }
+}
+
+// Executa a tarefa agora, registrando o horário para o agendador contar o
+// próximo intervalo a partir daqui
+pub fn run_job(instance: &Instance, job: &Job) -> Result<(), String> {
+    run_job_at(instance, job, Utc::now() + lead(job))
+}
+
+fn lead(job: &Job) -> chrono::Duration {
+    chrono::Duration::seconds(job.action.lead_secs().min(u32::MAX as u64) as i64)
+}
+
+// Horário agendado da próxima execução, se ela já deve começar em `now`.
+// Reinícios começam os avisos antes para acontecer no horário.
+fn due(job: &Job, base: DateTime<Utc>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    job.next_run(base)
+        .ok()
+        .filter(|next| *next - lead(job) <= now)
+}
+
+// Executa a tarefa agendada para `scheduled`, que é o horário registrado
+// como última execução. Um reinício avisa os jogadores só pelo tempo que
+// falta até esse horário.
+fn run_job_at(instance: &Instance, job: &Job, scheduled: DateTime<Utc>) -> Result<(), String> {
+    InstanceState::update(&instance.work_dir, |state| {
+        state.job_runs.insert(job.name.clone(), scheduled);
+    });
+
+    match &job.action {
+        JobAction::Restart { warning_secs } => {
+            if !instance.is_running() {
+                return Err("O servidor não está em execução".to_string());
+            }
+            let remaining =
+                ((scheduled - Utc::now()).num_milliseconds().max(0) as u64).div_ceil(1000);
+            daemon::restart_with_warnings(
+                instance,
+                remaining.min(*warning_secs),
+                &format!("tarefa {}", job.name),
+            )
+        }
+        JobAction::Say { message } => instance.admin().say(message),
+        JobAction::Backup => backup::create_backup(instance).map(|_| ()),
+        JobAction::Commands { commands } => {
+            let admin = instance.admin();
+            commands
+                .iter()
+                .try_for_each(|command| admin.run_raw(command))
+        }
+    }
+}
+
+// Executa as tarefas vencidas enquanto o barramento da instância existir. O
+// schedule.json é relido a cada ciclo, então pausas e edições valem sem
+// reiniciar nada.
+pub fn spawn_scheduler(events: &EventBus, instance: Instance) {
+    let receiver = events.subscribe();
+    let started_at = Utc::now();
+
+    thread::spawn(move || {
+        audit::with_actor("auto:agenda", || loop {
+            run_pending_restart(&instance);
+
+            let jobs = match JobSchedule::load(&instance.work_dir) {
+                Ok(schedule) => schedule.jobs,
+                Err(e) => {
+                    println!("[{}] {}", instance.name, e);
+                    Vec::new()
+                }
+            };
+            let runs = InstanceState::load(&instance.work_dir).job_runs;
+            let now = Utc::now();
+
+            for job in jobs.iter().filter(|job| !job.paused) {
+                let base = runs.get(&job.name).copied().unwrap_or(started_at);
+                let Some(next) = due(job, base, now) else {
+                    continue;
+                };
+                println!("[{}] Executando tarefa {}", instance.name, job.name);
+                if let Err(e) = run_job_at(&instance, job, next) {
+                    println!("[{}] Erro na tarefa {}: {}", instance.name, job.name, e);
+                }
+            }
+
+            // Os eventos só servem para saber quando o servidor foi descartado
+            let deadline = Instant::now() + CHECK_INTERVAL;
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
+                }
+                match receiver.recv_timeout(remaining) {
+                    Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        })
+    });
+}
+
+pub fn print_jobs(work_dir: &Path) -> Result<(), String> {
+    let schedule = JobSchedule::load(work_dir)?;
+    println!("\n=== Tarefas Agendadas ===");
+    let state = InstanceState::load(work_dir);
+    if let Some(pending) = &state.pending_restart {
+        println!(
+            "Reinício agendado para {} ({})",
+            pending.at.with_timezone(&Local).format("%d/%m/%Y %H:%M"),
+            pending.reason
+        );
+    }
+    if schedule.jobs.is_empty() {
+        println!(
+            "Nenhuma tarefa: defina-as em {}",
+            work_dir.join("schedule.json").display()
+        );
+        return Ok(());
+    }
+
+    let runs = state.job_runs;
+    let format_time = |time: DateTime<Utc>| {
+        time.with_timezone(&Local)
+            .format("%d/%m/%Y %H:%M")
+            .to_string()
+    };
+    for (i, job) in schedule.jobs.iter().enumerate() {
+        let last = runs.get(&job.name).copied();
+        println!(
+            "{}. {} [{}] {}: {}",
+            i + 1,
+            job.name,
+            if job.paused { "pausada" } else { "ativa" },
+            job.describe_schedule(),
+            job.action.describe()
+        );
+        println!(
+            "   Última: {}  Próxima: {}",
+            last.map(format_time).unwrap_or_else(|| "nunca".to_string()),
+            if job.paused {
+                "-".to_string()
+            } else {
+                job.next_run(last.unwrap_or_else(Utc::now))
+                    .map(format_time)
+                    .unwrap_or_else(|e| e)
+            }
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(every: &str, action: JobAction) -> Job {
+        Job {
+            name: "teste".to_string(),
+            every: Some(every.to_string()),
+            at: None,
+            action,
+            paused: false,
+        }
+    }
+
+    #[test]
+    fn restart_warnings_start_before_the_scheduled_time() {
+        let base = Utc::now();
+        let restart = job("1h", JobAction::Restart { warning_secs: 300 });
+        let at = |minutes: i64, seconds: i64| {
+            base + chrono::Duration::minutes(minutes) + chrono::Duration::seconds(seconds)
+        };
+
+        assert_eq!(due(&restart, base, at(54, 59)), None);
+        assert_eq!(due(&restart, base, at(55, 0)), Some(at(60, 0)));
+        assert_eq!(due(&restart, base, at(61, 0)), Some(at(60, 0)));
+
+        let say = job(
+            "1h",
+            JobAction::Say {
+                message: "oi".to_string(),
+            },
+        );
+        assert_eq!(due(&say, base, at(55, 0)), None);
+        assert_eq!(due(&say, base, at(60, 0)), Some(at(60, 0)));
+    }
+
+    #[test]
+    fn daily_jobs_run_once_per_day() {
+        let time = NaiveTime::from_hms_opt(4, 0, 0).unwrap();
+        let base = Utc::now();
+        let first = next_daily(time, base).unwrap();
+        assert!(first > base);
+        // Registrar o horário agendado como execução leva ao dia seguinte
+        let second = next_daily(time, first).unwrap();
+        assert!(second > first);
+        assert!(second - first <= chrono::Duration::hours(25));
+        assert!(second - first >= chrono::Duration::hours(23));
+    }
+}
@@ -1,11 +1,16 @@
 use crate::config_manager::ConfigManager;
-use std::env;
+use crate::events::{console_log_path, ConsoleWatcher, EventBus};
+#[cfg(unix)]
+use crate::instances;
+use crate::instances::Instance;
+use crate::server_lock;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::{Child, Command};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
@@ -14,21 +19,25 @@ use std::os::unix::fs::PermissionsExt;
 use std::os::windows::process::CommandExt;
 
 pub struct Server {
+    // Só é usado no Windows; no Linux o processo vive dentro da sessão screen
+    #[cfg_attr(unix, allow(dead_code))]
     process: Option<Child>,
     is_running: Arc<AtomicBool>,
     work_dir: String,
+    session: String,
+    events: EventBus,
+    console_watcher: Option<ConsoleWatcher>,
 }
 
 impl Server {
-    pub fn new() -> Self {
-        // Obter o caminho absoluto para o diretório do servidor
-        let current_dir = env::current_dir().expect("Erro ao obter diretório atual");
-        let work_dir = current_dir.join("server").to_string_lossy().to_string();
-
+    pub fn for_instance(instance: &Instance) -> Self {
         Server {
             process: None,
             is_running: Arc::new(AtomicBool::new(false)),
-            work_dir,
+            work_dir: instance.work_dir.to_string_lossy().to_string(),
+            session: instance.session_name(),
+            events: EventBus::new(),
+            console_watcher: None,
         }
     }
 
@@ -36,6 +45,46 @@ impl Server {
         self.is_running.load(Ordering::Relaxed)
     }
 
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    fn watch_console(&mut self) {
+        self.console_watcher = Some(ConsoleWatcher::spawn(
+            Path::new(&self.work_dir),
+            self.events.clone(),
+        ));
+    }
+
+    // Faz o screen gravar a saída do console em logs/console.log
+    #[cfg(unix)]
+    fn enable_console_log(&self) -> Result<(), String> {
+        let log_path = console_log_path(Path::new(&self.work_dir));
+        if let Some(logs_dir) = log_path.parent() {
+            fs::create_dir_all(logs_dir)
+                .map_err(|e| format!("Erro ao criar diretório de logs: {}", e))?;
+        }
+
+        let log_path = log_path.to_string_lossy().to_string();
+        let commands: [&[&str]; 3] = [
+            &["logfile", &log_path],
+            &["logfile", "flush", "1"],
+            &["log", "on"],
+        ];
+        for args in commands {
+            let status = Command::new("screen")
+                .args(["-S", &self.session, "-X"])
+                .args(args)
+                .current_dir(&self.work_dir)
+                .status()
+                .map_err(|e| format!("Erro ao configurar log do console: {}", e))?;
+            if !status.success() {
+                return Err("Falha ao configurar log do console".to_string());
+            }
+        }
+        Ok(())
+    }
+
     fn check_screen_installed(&self) -> bool {
         Command::new("which")
             .arg("screen")
@@ -48,6 +97,12 @@ impl Server {
         if self.is_running() {
             return Err("Servidor já está em execução".to_string());
         }
+        if let Some(lock) = server_lock::running_server(Path::new(&self.work_dir)) {
+            return Err(format!(
+                "Servidor já está em execução (PID {}); conecte-se a ele em vez de iniciar outro",
+                lock.pid
+            ));
+        }
 
         #[cfg(windows)]
         return self.start_windows();
@@ -73,13 +128,32 @@ impl Server {
         const CREATE_NO_WINDOW: u32 = 0x08000000;
         const DETACHED_PROCESS: u32 = 0x00000008;
 
+        // Redireciona a saída do console para logs/console.log
+        let log_path = console_log_path(Path::new(&self.work_dir));
+        if let Some(logs_dir) = log_path.parent() {
+            fs::create_dir_all(logs_dir)
+                .map_err(|e| format!("Erro ao criar diretório de logs: {}", e))?;
+        }
+        let log_file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)
+            .map_err(|e| format!("Erro ao abrir log do console: {}", e))?;
+        let log_file_err = log_file
+            .try_clone()
+            .map_err(|e| format!("Erro ao abrir log do console: {}", e))?;
+
         let mut command = Command::new(&server_path);
         command
             .current_dir(&self.work_dir)
+            .stdout(log_file)
+            .stderr(log_file_err)
             .creation_flags(CREATE_NO_WINDOW | DETACHED_PROCESS);
 
+        self.watch_console();
         match command.spawn() {
             Ok(child) => {
+                server_lock::register(Path::new(&self.work_dir), child.id())?;
                 self.process = Some(child);
                 self.is_running.store(true, Ordering::Relaxed);
                 println!("Servidor iniciado com sucesso!");
@@ -150,24 +224,9 @@ impl Server {
                 .map_err(|e| format!("Erro ao criar diretório de logs: {}", e))?;
         }
 
-        // Verificar se os arquivos necessários existem
-        let files_to_check = ["allowlist.json", "permissions.json", "server.properties"];
-        for file in files_to_check.iter() {
-            let file_path = Path::new(&self.work_dir).join(file);
-            if !file_path.exists() {
-                let content = match *file {
-                    "server.properties" => "server-name=Dedicated Server\nserver-port=19132\ngamemode=survival\ndifficulty=normal\nallow-cheats=false\nmax-players=10\nonline-mode=true\nwhite-list=false\nview-distance=32\ntick-distance=4\nplayer-idle-timeout=30\nmax-threads=8\ndefault-player-permission-level=member\ntexturepack-required=false\ncontent-log-file-enabled=false\ncompression-threshold=1\nserver-authoritative-movement=server-auth\nplayer-movement-score-threshold=20\nplayer-movement-action-direction-threshold=0.85\nplayer-movement-distance-threshold=0.3\nplayer-movement-duration-threshold-in-ms=500\ncorrect-player-movement=false\nserver-authoritative-block-breaking=false\n",
-                    _ => "[]"
-                };
-                fs::write(&file_path, content)
-                    .map_err(|e| format!("Erro ao criar {}: {}", file, e))?;
-                println!("Arquivo {} criado com configuração padrão", file);
-            }
-        }
-
         // Iniciar o servidor em uma sessão screen
         let screen_status = Command::new("screen")
-            .args(["-dmS", "minecraft"])
+            .args(["-dmS", &self.session])
             .current_dir(&self.work_dir)
             .status()
             .map_err(|e| format!("Erro ao criar sessão screen: {}", e))?;
@@ -179,26 +238,48 @@ impl Server {
         // Pequena pausa para garantir que a sessão screen foi criada
         std::thread::sleep(std::time::Duration::from_millis(500));
 
-        // Iniciar o servidor dentro da sessão screen
+        // Qualquer falha daqui em diante encerra a sessão criada, para não
+        // sobrar uma sessão vazia que bloqueie o próximo início
+        let lock = match self.launch_in_session(&server_path) {
+            Ok(lock) => lock,
+            Err(e) => {
+                let _ = Command::new("screen")
+                    .args(["-S", &self.session, "-X", "quit"])
+                    .current_dir(&self.work_dir)
+                    .status();
+                self.console_watcher = None;
+                return Err(e);
+            }
+        };
+        println!("Servidor iniciado com sucesso! (PID {})", lock.pid);
+        self.is_running.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn launch_in_session(&mut self, server_path: &Path) -> Result<server_lock::ServerLock, String> {
+        self.enable_console_log()?;
+        self.watch_console();
+
+        // Iniciar o servidor dentro da sessão screen. Com `exec` a sessão
+        // termina junto com o servidor, então ela indica se ele está rodando
         let status = Command::new("screen")
             .args([
                 "-S",
-                "minecraft",
+                &self.session,
                 "-X",
                 "stuff",
-                &format!("{}\n", server_path.display()),
+                &format!("exec \"{}\"\n", server_path.display()),
             ])
             .current_dir(&self.work_dir)
             .status()
             .map_err(|e| format!("Erro ao iniciar servidor: {}", e))?;
 
-        if status.success() {
-            println!("Servidor iniciado com sucesso!");
-            self.is_running.store(true, Ordering::Relaxed);
-            Ok(())
-        } else {
-            Err("Falha ao iniciar servidor".to_string())
+        if !status.success() {
+            return Err("Falha ao iniciar servidor".to_string());
         }
+
+        server_lock::wait_for_start(Path::new(&self.work_dir), Duration::from_secs(10))
     }
 
     pub fn stop(&mut self) -> Result<(), String> {
@@ -208,6 +289,8 @@ impl Server {
                 match child.kill() {
                     Ok(_) => {
                         self.is_running.store(false, Ordering::Relaxed);
+                        self.console_watcher = None;
+                        server_lock::ServerLock::remove(Path::new(&self.work_dir));
                         Ok(())
                     }
                     Err(e) => Err(format!("Erro ao parar servidor: {}", e)),
@@ -221,17 +304,24 @@ impl Server {
         {
             // Enviar comando de stop via screen
             let status = Command::new("screen")
-                .args(["-S", "minecraft", "-X", "stuff", "stop\n"])
+                .args(["-S", &self.session, "-X", "stuff", "stop\n"])
                 .current_dir(&self.work_dir)
                 .status()
                 .map_err(|e| format!("Erro ao parar servidor: {}", e))?;
 
-            if status.success() {
-                self.is_running.store(false, Ordering::Relaxed);
-                Ok(())
-            } else {
-                Err("Falha ao parar servidor".to_string())
+            if !status.success() {
+                return Err("Falha ao parar servidor".to_string());
+            }
+
+            self.is_running.store(false, Ordering::Relaxed);
+            self.console_watcher = None;
+            // O servidor salva o mundo antes de encerrar; só então o lock sai
+            if !server_lock::wait_for_exit(Path::new(&self.work_dir), Duration::from_secs(30)) {
+                println!(
+                    "O servidor ainda não encerrou; o lock será limpo quando o processo terminar"
+                );
             }
+            Ok(())
         }
     }
 
@@ -239,68 +329,32 @@ impl Server {
         &self.work_dir
     }
 
-    pub fn check_existing_servers(&self) -> Result<Vec<String>, String> {
-        #[cfg(unix)]
-        {
-            let output = Command::new("screen")
-                .args(["-ls"])
-                .output()
-                .map_err(|e| format!("Erro ao verificar servidores: {}", e))?;
-
-            let screen_list = String::from_utf8_lossy(&output.stdout);
-            let mut servers = Vec::new();
-
-            for line in screen_list.lines() {
-                if line.contains("minecraft") {
-                    if let Some(name) = line.split('.').nth(1) {
-                        servers.push(name.trim().to_string());
-                    }
-                }
-            }
-
-            Ok(servers)
-        }
-
-        #[cfg(windows)]
-        {
-            // No Windows, verificamos se existe um processo do servidor rodando
-            let output = Command::new("tasklist")
-                .args(["/FI", "IMAGENAME eq bedrock_server.exe"])
-                .output()
-                .map_err(|e| format!("Erro ao verificar servidores: {}", e))?;
-
-            let process_list = String::from_utf8_lossy(&output.stdout);
-            let mut servers = Vec::new();
+    // Reconecta a um servidor iniciado antes, confirmando pelo server.lock que
+    // o processo ainda existe e retomando a leitura do console
+    pub fn attach_to_existing(&mut self) -> Result<(), String> {
+        let lock = server_lock::running_server(Path::new(&self.work_dir))
+            .ok_or("Nenhum servidor em execução nesta instância")?;
 
-            if process_list.contains("bedrock_server.exe") {
-                servers.push("bedrock_server".to_string());
-            }
-
-            Ok(servers)
-        }
-    }
-
-    pub fn attach_to_existing(&mut self, server_name: &str) -> Result<(), String> {
         #[cfg(unix)]
         {
-            self.is_running.store(true, Ordering::Relaxed);
-            println!("Conectado ao servidor existente: {}", server_name);
-            Ok(())
-        }
-
-        #[cfg(windows)]
-        {
-            self.is_running.store(true, Ordering::Relaxed);
-            println!("Conectado ao servidor existente");
-            Ok(())
+            if !instances::running_sessions().contains(&self.session) {
+                return Err(format!(
+                    "O servidor (PID {}) está rodando fora da sessão screen {}; \
+                    não é possível enviar comandos a ele",
+                    lock.pid, self.session
+                ));
+            }
+            self.enable_console_log()?;
         }
-    }
-}
 
-impl Drop for Server {
-    fn drop(&mut self) {
-        if self.is_running() {
-            let _ = self.stop();
-        }
+        self.watch_console();
+        self.is_running.store(true, Ordering::Relaxed);
+        println!(
+            "Conectado ao servidor existente: {} (PID {}, versão {})",
+            self.session,
+            lock.pid,
+            lock.version.as_deref().unwrap_or("desconhecida")
+        );
+        Ok(())
     }
 }
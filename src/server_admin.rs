@@ -1,3 +1,13 @@
+use crate::audit;
+use crate::bedrock_command::{
+    validate_gamerule_name, BedrockCommand, Difficulty, Effect, ItemStack, TargetSelector,
+    TitleKind,
+};
+use crate::command::{escape_for_screen, validate_identifier, validate_text};
+use crate::events::{console_log_path, read_console_since};
+use crate::manager_config;
+use serde_json::json;
+use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
 use std::thread;
@@ -5,33 +15,42 @@ use std::time::Duration;
 
 pub struct ServerAdmin {
     work_dir: PathBuf,
+    session: String,
+    // Mostra a resposta do servidor depois de cada comando tipado
+    echo: bool,
 }
 
 impl ServerAdmin {
-    pub fn new(work_dir: PathBuf) -> Self {
-        ServerAdmin { work_dir }
+    pub fn new(work_dir: PathBuf, session: String) -> Self {
+        ServerAdmin {
+            work_dir,
+            session,
+            echo: false,
+        }
+    }
+
+    // Só para o menu interativo: a captura da resposta espera e escreve no
+    // terminal, o que atrasaria e poluiria a saída dos serviços
+    pub fn with_echo(self) -> Self {
+        ServerAdmin { echo: true, ..self }
     }
 
     pub fn execute_command(&self, command: &str) -> Result<(), String> {
+        // Uma quebra de linha no meio do comando enviaria um segundo comando
+        validate_text(command)?;
+
         #[cfg(unix)]
         {
-            // Envia o comando
-            let status = Command::new("screen")
-                .args(["-S", "minecraft", "-X", "stuff", &format!("{}\n", command)])
-                .current_dir(&self.work_dir)
-                .status()
-                .map_err(|e| format!("Erro ao executar comando: {}", e))?;
-
-            if !status.success() {
-                return Err("Falha ao executar comando".to_string());
-            }
+            self.send_to_screen(command)?;
 
             // Aguarda um momento para o comando ser processado
-            thread::sleep(Duration::from_millis(100));
+            thread::sleep(Duration::from_millis(
+                manager_config::settings().command_delay_ms,
+            ));
 
             // Captura a saída do screen
             let output = Command::new("screen")
-                .args(["-S", "minecraft", "-X", "hardcopy", "latest.log"])
+                .args(["-S", &self.session, "-X", "hardcopy", "latest.log"])
                 .current_dir(&self.work_dir)
                 .status()
                 .map_err(|e| format!("Erro ao capturar saída: {}", e))?;
@@ -57,7 +76,9 @@ impl ServerAdmin {
                 .map_err(|e| format!("Erro ao executar comando: {}", e))?;
 
             // Aguarda um momento para o comando ser processado
-            thread::sleep(Duration::from_millis(100));
+            thread::sleep(Duration::from_millis(
+                manager_config::settings().command_delay_ms,
+            ));
 
             // Lê o arquivo de log se existir
             if let Ok(content) = std::fs::read_to_string(&log_file) {
@@ -68,39 +89,236 @@ impl ServerAdmin {
         Ok(())
     }
 
+    // Envia o comando sem esperar nem mostrar a resposta do servidor
+    pub fn send_command(&self, command: &str) -> Result<(), String> {
+        validate_text(command)?;
+
+        #[cfg(windows)]
+        return self.execute_command(command);
+
+        #[cfg(unix)]
+        self.send_to_screen(command)
+    }
+
+    #[cfg(unix)]
+    fn send_to_screen(&self, command: &str) -> Result<(), String> {
+        let status = Command::new("screen")
+            .args([
+                "-S",
+                &self.session,
+                "-X",
+                "stuff",
+                &format!("{}\n", escape_for_screen(command)),
+            ])
+            .current_dir(&self.work_dir)
+            .status()
+            .map_err(|e| format!("Erro ao executar comando: {}", e))?;
+
+        if !status.success() {
+            return Err("Falha ao executar comando".to_string());
+        }
+        Ok(())
+    }
+
+    // Envia o comando e devolve as linhas que o servidor escreveu no console
+    // durante `wait`
+    pub fn execute_and_capture(
+        &self,
+        command: &str,
+        wait: Duration,
+    ) -> Result<Vec<String>, String> {
+        let log_path = console_log_path(&self.work_dir);
+        let start = fs::metadata(&log_path).map(|m| m.len()).unwrap_or(0);
+
+        self.send_command(command)?;
+        thread::sleep(wait);
+        let (lines, _) = read_console_since(&log_path, start)?;
+        Ok(lines)
+    }
+
+    // Comandos tipados passam pela auditoria com o nome do comando como ação
+    // (ex: "kick", "op", "whitelist")
+    pub fn run(&self, command: &BedrockCommand) -> Result<(), String> {
+        let command = command.to_string();
+        let result = if self.echo {
+            self.execute_command(&command)
+        } else {
+            self.send_command(&command)
+        };
+        let action = command
+            .split_whitespace()
+            .next()
+            .map(|name| name.trim_start_matches('/'))
+            .unwrap_or("command");
+        audit::record(
+            &self.work_dir,
+            action,
+            json!({ "command": command }),
+            &result,
+        );
+        result
+    }
+
+    // Comando digitado livremente (console, API), registrado como "command"
+    pub fn run_raw(&self, command: &str) -> Result<(), String> {
+        let result = self.send_command(command);
+        audit::record(
+            &self.work_dir,
+            "command",
+            json!({ "command": command }),
+            &result,
+        );
+        result
+    }
+
     pub fn set_gamemode(&self, player: &str, mode: &str) -> Result<(), String> {
-        self.execute_command(&format!("/gamemode {} {}", mode, player))
+        self.run(&BedrockCommand::GameMode {
+            mode: mode.parse()?,
+            target: player.parse()?,
+        })
     }
 
     pub fn op_player(&self, player: &str) -> Result<(), String> {
-        self.execute_command(&format!("/op {}", player))
+        self.run(&BedrockCommand::Op(TargetSelector::player(player)?))
     }
 
     pub fn deop_player(&self, player: &str) -> Result<(), String> {
-        self.execute_command(&format!("/deop {}", player))
+        self.run(&BedrockCommand::Deop(TargetSelector::player(player)?))
     }
 
     pub fn kick_player(&self, player: &str, reason: &str) -> Result<(), String> {
-        self.execute_command(&format!("/kick {} {}", player, reason))
+        self.run(&BedrockCommand::Kick {
+            target: TargetSelector::player(player)?,
+            reason: reason.to_string(),
+        })
     }
 
     pub fn whitelist_add(&self, player: &str) -> Result<(), String> {
-        self.execute_command(&format!("/whitelist add {}", player))
+        self.run(&BedrockCommand::WhitelistAdd(TargetSelector::player(
+            player,
+        )?))
     }
 
     pub fn whitelist_remove(&self, player: &str) -> Result<(), String> {
-        self.execute_command(&format!("/whitelist remove {}", player))
+        self.run(&BedrockCommand::WhitelistRemove(TargetSelector::player(
+            player,
+        )?))
     }
 
     pub fn list_players(&self) -> Result<(), String> {
-        self.execute_command("/list")
+        self.run(&BedrockCommand::List)
     }
 
     pub fn teleport_player(&self, player: &str, target: &str) -> Result<(), String> {
-        self.execute_command(&format!("/tp {} {}", player, target))
+        self.run(&BedrockCommand::Teleport {
+            target: player.parse()?,
+            destination: target.parse()?,
+        })
+    }
+
+    pub fn give_item(&self, player: &str, item: ItemStack) -> Result<(), String> {
+        self.run(&BedrockCommand::Give {
+            target: player.parse()?,
+            item,
+        })
+    }
+
+    pub fn set_difficulty(&self, difficulty: &str) -> Result<(), String> {
+        self.run(&BedrockCommand::Difficulty(
+            difficulty.parse::<Difficulty>()?,
+        ))
+    }
+
+    pub fn say(&self, message: &str) -> Result<(), String> {
+        self.run(&BedrockCommand::Say(message.to_string()))
+    }
+
+    pub fn tellraw(&self, target: &str, text: &str) -> Result<(), String> {
+        self.run(&BedrockCommand::Tellraw {
+            target: target.parse()?,
+            text: text.to_string(),
+        })
+    }
+
+    pub fn tell(&self, player: &str, message: &str) -> Result<(), String> {
+        self.run(&BedrockCommand::Tell {
+            target: player.parse()?,
+            message: message.to_string(),
+        })
+    }
+
+    pub fn set_time(&self, time: &str) -> Result<(), String> {
+        self.run(&BedrockCommand::TimeSet(time.parse()?))
+    }
+
+    pub fn set_weather(&self, weather: &str, duration: Option<u32>) -> Result<(), String> {
+        self.run(&BedrockCommand::Weather {
+            weather: weather.parse()?,
+            duration,
+        })
+    }
+
+    pub fn give_effect(&self, player: &str, effect: Effect) -> Result<(), String> {
+        self.run(&BedrockCommand::Effect {
+            target: player.parse()?,
+            effect,
+        })
+    }
+
+    pub fn clear_effects(&self, player: &str) -> Result<(), String> {
+        self.run(&BedrockCommand::EffectClear(player.parse()?))
+    }
+
+    pub fn clear_inventory(&self, player: &str, item: Option<&str>) -> Result<(), String> {
+        self.run(&BedrockCommand::Clear {
+            target: player.parse()?,
+            item: item
+                .map(|item| validate_identifier(item, "item"))
+                .transpose()?,
+        })
+    }
+
+    pub fn set_gamerule(&self, rule: &str, value: &str) -> Result<(), String> {
+        self.run(&BedrockCommand::Gamerule {
+            rule: validate_gamerule_name(rule)?,
+            value: value.parse()?,
+        })
+    }
+
+    pub fn set_world_spawn(&self, position: Option<&str>) -> Result<(), String> {
+        self.run(&BedrockCommand::SetWorldSpawn(
+            position.map(|p| p.parse()).transpose()?,
+        ))
+    }
+
+    pub fn set_spawnpoint(&self, player: &str, position: Option<&str>) -> Result<(), String> {
+        self.run(&BedrockCommand::SpawnPoint {
+            target: player.parse()?,
+            position: position.map(|p| p.parse()).transpose()?,
+        })
+    }
+
+    pub fn give_xp(&self, player: &str, amount: i32, levels: bool) -> Result<(), String> {
+        // Pontos de experiência não podem ser removidos, apenas níveis
+        if amount < 0 && !levels {
+            return Err("Apenas níveis podem ser removidos (use níveis negativos)".to_string());
+        }
+        self.run(&BedrockCommand::Xp {
+            target: player.parse()?,
+            amount,
+            levels,
+        })
+    }
+
+    pub fn show_title(&self, target: &str, kind: TitleKind, text: &str) -> Result<(), String> {
+        self.run(&BedrockCommand::Title {
+            target: target.parse()?,
+            kind,
+            text: text.to_string(),
+        })
     }
 
-    pub fn give_item(&self, player: &str, item: &str, amount: u32) -> Result<(), String> {
-        self.execute_command(&format!("/give {} {} {}", player, item, amount))
+    pub fn clear_title(&self, target: &str) -> Result<(), String> {
+        self.run(&BedrockCommand::TitleClear(target.parse()?))
     }
 }
@@ -0,0 +1,242 @@
+use crate::config;
+use crate::config_manager::ConfigManager;
+use crate::instance_state::InstanceState;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, System, UpdateKind};
+
+// Registro do processo do servidor em execução, gravado em server.lock no
+// diretório da instância. Permite reencontrar o servidor depois que o
+// gerenciador é reiniciado.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ServerLock {
+    pub pid: u32,
+    pub started_at: DateTime<Utc>,
+    pub version: Option<String>,
+    pub port: u16,
+    pub port_v6: u16,
+}
+
+fn lock_path(work_dir: &Path) -> PathBuf {
+    work_dir.join("server.lock")
+}
+
+fn executable_path(work_dir: &Path) -> PathBuf {
+    if cfg!(windows) {
+        work_dir.join("bedrock_server.exe")
+    } else {
+        work_dir.join("bedrock_server")
+    }
+}
+
+fn scan_processes() -> System {
+    let mut system = System::new();
+    system.refresh_processes_specifics(ProcessRefreshKind::new().with_exe(UpdateKind::Always));
+    system
+}
+
+// Atualiza só o processo registrado, sem percorrer a lista do sistema
+fn load_process(pid: u32) -> System {
+    let mut system = System::new();
+    system.refresh_process_specifics(Pid::from_u32(pid), ProcessRefreshKind::new());
+    system
+}
+
+fn process_started_at(system: &System, pid: u32) -> Option<DateTime<Utc>> {
+    let process = system.process(Pid::from_u32(pid))?;
+    Utc.timestamp_opt(process.start_time() as i64, 0).single()
+}
+
+impl ServerLock {
+    pub fn read(work_dir: &Path) -> Option<Self> {
+        let content = fs::read_to_string(lock_path(work_dir)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn write(&self, work_dir: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Erro ao serializar server.lock: {}", e))?;
+        fs::write(lock_path(work_dir), content)
+            .map_err(|e| format!("Erro ao salvar server.lock: {}", e))
+    }
+
+    pub fn remove(work_dir: &Path) {
+        let _ = fs::remove_file(lock_path(work_dir));
+    }
+
+    // O PID precisa existir, pertencer a um bedrock_server e ter começado no
+    // mesmo instante registrado; assim um PID reaproveitado pelo sistema não
+    // é confundido com o servidor
+    fn is_alive(&self, system: &System) -> bool {
+        let Some(process) = system.process(Pid::from_u32(self.pid)) else {
+            return false;
+        };
+        process.name().starts_with("bedrock_server")
+            && process_started_at(system, self.pid) == Some(self.started_at)
+    }
+
+    pub fn uptime(&self) -> chrono::Duration {
+        Utc::now() - self.started_at
+    }
+}
+
+// Procura um bedrock_server cujo executável é o desta instância
+fn find_server_process(system: &System, work_dir: &Path) -> Option<u32> {
+    let executable = executable_path(work_dir);
+    let executable = executable.canonicalize().unwrap_or(executable);
+    system
+        .processes()
+        .values()
+        .find(|process| {
+            process.name().starts_with("bedrock_server") && process.exe() == Some(&executable)
+        })
+        .map(|process| process.pid().as_u32())
+}
+
+pub enum ServerStatus {
+    Running(ServerLock),
+    // O lock apontava para um processo que não existe mais e foi removido
+    StaleLock(ServerLock),
+    Stopped,
+}
+
+impl ServerStatus {
+    pub fn running(self) -> Option<ServerLock> {
+        match self {
+            ServerStatus::Running(lock) => Some(lock),
+            _ => None,
+        }
+    }
+}
+
+// Estado do servidor desta instância, segundo o server.lock. Um lock cujo
+// processo morreu é removido; um servidor sem lock (iniciado por uma versão
+// antiga do gerenciador) é adotado, o que exige procurar entre todos os
+// processos.
+pub fn server_status(work_dir: &Path) -> ServerStatus {
+    let stale = match ServerLock::read(work_dir) {
+        Some(lock) if lock.is_alive(&load_process(lock.pid)) => return ServerStatus::Running(lock),
+        Some(lock) => {
+            ServerLock::remove(work_dir);
+            Some(lock)
+        }
+        None => None,
+    };
+
+    let system = scan_processes();
+    let adopted =
+        find_server_process(&system, work_dir).and_then(|pid| new_lock(&system, work_dir, pid));
+    match (adopted, stale) {
+        (Some(lock), _) => {
+            let _ = lock.write(work_dir);
+            ServerStatus::Running(lock)
+        }
+        (None, Some(lock)) => ServerStatus::StaleLock(lock),
+        (None, None) => ServerStatus::Stopped,
+    }
+}
+
+pub fn running_server(work_dir: &Path) -> Option<ServerLock> {
+    server_status(work_dir).running()
+}
+
+fn new_lock(system: &System, work_dir: &Path, pid: u32) -> Option<ServerLock> {
+    let (port, port_v6) = ConfigManager::new(work_dir.to_path_buf()).ports();
+    Some(ServerLock {
+        pid,
+        started_at: process_started_at(system, pid)?,
+        version: config::installed_version(work_dir),
+        port,
+        port_v6,
+    })
+}
+
+// Grava o lock de um processo recém-iniciado e conta o início. Servidores
+// adotados em `running_server` não passam por aqui.
+pub fn register(work_dir: &Path, pid: u32) -> Result<ServerLock, String> {
+    let system = load_process(pid);
+    let lock = new_lock(&system, work_dir, pid)
+        .ok_or_else(|| format!("Processo {} não encontrado", pid))?;
+    lock.write(work_dir)?;
+    InstanceState::update(work_dir, |state| state.start_count += 1);
+    Ok(lock)
+}
+
+// Aguarda o bedrock_server da instância aparecer (no Linux ele é iniciado
+// dentro do screen, então o PID não é conhecido diretamente)
+pub fn wait_for_start(work_dir: &Path, timeout: Duration) -> Result<ServerLock, String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let system = scan_processes();
+        if let Some(pid) = find_server_process(&system, work_dir) {
+            return register(work_dir, pid);
+        }
+        if Instant::now() >= deadline {
+            return Err(
+                "O processo bedrock_server não iniciou; verifique logs/console.log".to_string(),
+            );
+        }
+        thread::sleep(Duration::from_millis(250));
+    }
+}
+
+// Aguarda o servidor encerrar depois do `stop` e remove o lock
+pub fn wait_for_exit(work_dir: &Path, timeout: Duration) -> bool {
+    let Some(lock) = ServerLock::read(work_dir) else {
+        return true;
+    };
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if !lock.is_alive(&load_process(lock.pid)) {
+            ServerLock::remove(work_dir);
+            return true;
+        }
+        thread::sleep(Duration::from_millis(250));
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use std::process::Command;
+
+    // Um `sleep` copiado como bedrock_server faz as vezes do servidor
+    #[cfg(unix)]
+    #[test]
+    fn status_follows_the_recorded_process() {
+        let dir = test_util::temp_dir("server-lock");
+        let binary = executable_path(&dir);
+        fs::copy("/bin/sleep", &binary).unwrap();
+        let mut child = Command::new(&binary).arg("30").spawn().unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut status = server_status(&dir);
+        while !matches!(status, ServerStatus::Running(_)) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+            status = server_status(&dir);
+        }
+        // Adotado pela busca e depois reencontrado só pelo PID do lock
+        assert_eq!(status.running().map(|lock| lock.pid), Some(child.id()));
+        assert_eq!(
+            ServerLock::read(&dir).map(|lock| lock.pid),
+            Some(child.id())
+        );
+        assert!(running_server(&dir).is_some());
+
+        let _ = child.kill();
+        let _ = child.wait();
+        assert!(matches!(
+            server_status(&dir),
+            ServerStatus::StaleLock(lock) if lock.pid == child.id()
+        ));
+        assert!(ServerLock::read(&dir).is_none());
+        assert!(matches!(server_status(&dir), ServerStatus::Stopped));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,167 @@
+use chrono::{DateTime, TimeZone, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use sysinfo::{Pid, ProcessRefreshKind, System};
+
+// Os serviços de uma instância (banimentos, estatísticas, gamerules,
+// watchdog, webhooks e agenda) rodam em um único processo por vez. Quem os
+// roda registra em services.lock o próprio PID e uma marca aleatória; o
+// daemon assume a trava de um menu ou API abertos, que percebem a troca e
+// encerram os seus.
+#[derive(Serialize, Deserialize)]
+struct ServicesOwner {
+    pid: u32,
+    started_at: Option<DateTime<Utc>>,
+    token: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Claim {
+    // Só pega a trava livre ou deixada por um processo que morreu
+    WhenFree,
+    // Toma a trava mesmo de um processo vivo (o daemon)
+    TakeOver,
+}
+
+fn lock_path(work_dir: &Path) -> PathBuf {
+    work_dir.join("services.lock")
+}
+
+fn process_started_at(pid: u32) -> Option<DateTime<Utc>> {
+    let mut system = System::new();
+    system.refresh_process_specifics(Pid::from_u32(pid), ProcessRefreshKind::new());
+    let process = system.process(Pid::from_u32(pid))?;
+    Utc.timestamp_opt(process.start_time() as i64, 0).single()
+}
+
+impl ServicesOwner {
+    fn current() -> Self {
+        let pid = std::process::id();
+        let token: u64 = rand::thread_rng().gen();
+        ServicesOwner {
+            pid,
+            started_at: process_started_at(pid),
+            token: format!("{:016x}", token),
+        }
+    }
+
+    fn read(work_dir: &Path) -> Option<Self> {
+        let content = fs::read_to_string(lock_path(work_dir)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    // Um PID reaproveitado pelo sistema não conta como o mesmo processo
+    fn is_alive(&self) -> bool {
+        match process_started_at(self.pid) {
+            Some(started_at) => self.started_at.is_none_or(|at| at == started_at),
+            None => false,
+        }
+    }
+}
+
+pub struct ServicesLock {
+    work_dir: PathBuf,
+    token: String,
+}
+
+impl ServicesLock {
+    pub fn acquire(work_dir: &Path, claim: Claim) -> Option<Self> {
+        let owner = ServicesOwner::current();
+        let content = serde_json::to_string(&owner).ok()?;
+        let path = lock_path(work_dir);
+
+        if claim == Claim::WhenFree {
+            if ServicesOwner::read(work_dir).is_some_and(|current| current.is_alive()) {
+                return None;
+            }
+            // Trava abandonada: se dois processos a removerem ao mesmo tempo,
+            // o que perder a corrida percebe em is_held e libera os serviços
+            let _ = fs::remove_file(&path);
+        }
+
+        // Grava ao lado e só então publica, para ninguém ler o arquivo pela
+        // metade. O link falha se outro processo criou a trava antes.
+        let temp = work_dir.join(format!("services.lock.{}", owner.token));
+        fs::write(&temp, &content).ok()?;
+        let published = match claim {
+            Claim::WhenFree => fs::hard_link(&temp, &path),
+            Claim::TakeOver => fs::rename(&temp, &path),
+        };
+        let _ = fs::remove_file(&temp);
+        published.ok()?;
+
+        Some(ServicesLock {
+            work_dir: work_dir.to_path_buf(),
+            token: owner.token,
+        })
+    }
+
+    // Falso depois que outro processo assumiu os serviços
+    pub fn is_held(&self) -> bool {
+        ServicesOwner::read(&self.work_dir).is_some_and(|owner| owner.token == self.token)
+    }
+
+    // PID de quem roda os serviços da instância agora
+    pub fn owner_pid(work_dir: &Path) -> Option<u32> {
+        ServicesOwner::read(work_dir)
+            .filter(|owner| owner.is_alive())
+            .map(|owner| owner.pid)
+    }
+}
+
+impl Drop for ServicesLock {
+    fn drop(&mut self) {
+        if self.is_held() {
+            let _ = fs::remove_file(lock_path(&self.work_dir));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn only_one_holder_until_released() {
+        let dir = test_util::temp_dir("services-lock");
+        let first = ServicesLock::acquire(&dir, Claim::WhenFree).unwrap();
+        assert!(first.is_held());
+        assert!(ServicesLock::acquire(&dir, Claim::WhenFree).is_none());
+        assert_eq!(ServicesLock::owner_pid(&dir), Some(std::process::id()));
+
+        drop(first);
+        assert!(!lock_path(&dir).exists());
+        assert!(ServicesLock::acquire(&dir, Claim::WhenFree).is_some());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn take_over_releases_previous_holder() {
+        let dir = test_util::temp_dir("services-takeover");
+        let menu = ServicesLock::acquire(&dir, Claim::WhenFree).unwrap();
+        let daemon = ServicesLock::acquire(&dir, Claim::TakeOver).unwrap();
+        assert!(!menu.is_held());
+        assert!(daemon.is_held());
+
+        // Quem perdeu a trava não a remove ao sair
+        drop(menu);
+        assert!(daemon.is_held());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn abandoned_lock_is_replaced() {
+        let dir = test_util::temp_dir("services-abandoned");
+        let dead = ServicesOwner {
+            pid: u32::MAX - 1,
+            started_at: None,
+            token: "morto".to_string(),
+        };
+        fs::write(lock_path(&dir), serde_json::to_string(&dead).unwrap()).unwrap();
+        assert!(ServicesLock::acquire(&dir, Claim::WhenFree).is_some());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
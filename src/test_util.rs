@@ -0,0 +1,50 @@
+// Apoio aos testes: diretórios temporários e troca do diretório atual, de onde
+// o gerenciador lê instances.json, manager.json e audit.jsonl
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+// O diretório atual é do processo inteiro; quem o troca espera a sua vez
+static CURRENT_DIR: Mutex<()> = Mutex::new(());
+
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!(
+        "mbs-test-{}-{}-{}",
+        std::process::id(),
+        name,
+        NEXT_DIR.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Diretório atual temporário, desfeito (e apagado) quando o guarda sai de escopo
+pub struct TempCurrentDir {
+    pub path: PathBuf,
+    previous: PathBuf,
+    _lock: MutexGuard<'static, ()>,
+}
+
+pub fn enter_temp_dir(name: &str) -> TempCurrentDir {
+    let lock = CURRENT_DIR.lock().unwrap_or_else(|e| e.into_inner());
+    let previous = env::current_dir().unwrap();
+    let path = temp_dir(name);
+    env::set_current_dir(&path).unwrap();
+    TempCurrentDir {
+        path,
+        previous,
+        _lock: lock,
+    }
+}
+
+impl Drop for TempCurrentDir {
+    fn drop(&mut self) {
+        let _ = env::set_current_dir(&self.previous);
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
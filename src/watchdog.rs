@@ -0,0 +1,451 @@
+use crate::audit;
+use crate::daemon;
+use crate::events::{self, EventBus, ServerEvent};
+use crate::instance_state::InstanceState;
+use crate::instances::Instance;
+use crate::resources::{self, format_bytes};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchdogAction {
+    // Apenas publica o alerta
+    Alert,
+    // Alerta e reinicia o servidor com avisos aos jogadores
+    Restart,
+    // Alerta e recusa novos backups até o limite voltar ao normal
+    PauseBackups,
+}
+
+impl fmt::Display for WatchdogAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            WatchdogAction::Alert => "alert",
+            WatchdogAction::Restart => "restart",
+            WatchdogAction::PauseBackups => "pause_backups",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Limites do watchdog, em watchdog.json no diretório da instância. Limites
+// ausentes não são verificados. Exemplo:
+// { "max_memory_gb": 6, "memory_action": "restart", "min_free_disk_gb": 5,
+//   "disk_action": "pause_backups" }
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchdogConfig {
+    pub interval_secs: u64,
+    pub max_memory_gb: Option<f64>,
+    pub memory_action: WatchdogAction,
+    pub max_cpu_percent: Option<f32>,
+    pub cpu_minutes: u64,
+    pub cpu_action: WatchdogAction,
+    pub min_free_disk_gb: Option<f64>,
+    pub disk_action: WatchdogAction,
+    pub restart_warning_secs: u64,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        WatchdogConfig {
+            interval_secs: 30,
+            max_memory_gb: None,
+            memory_action: WatchdogAction::Alert,
+            max_cpu_percent: None,
+            cpu_minutes: 10,
+            cpu_action: WatchdogAction::Alert,
+            min_free_disk_gb: None,
+            disk_action: WatchdogAction::Alert,
+            restart_warning_secs: 60,
+        }
+    }
+}
+
+impl WatchdogConfig {
+    // Sem watchdog.json o watchdog fica desligado
+    pub fn load(work_dir: &Path) -> Result<Option<Self>, String> {
+        let path = work_dir.join("watchdog.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content =
+            fs::read_to_string(&path).map_err(|e| format!("Erro ao ler watchdog.json: {}", e))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("Erro ao ler watchdog.json: {}", e))
+    }
+}
+
+// Medições usadas pelo watchdog e pelo comando `watchdog status`
+pub struct Reading {
+    pub memory_bytes: Option<u64>,
+    pub cpu_percent: Option<f32>,
+    pub free_disk_bytes: Option<u64>,
+}
+
+pub fn read_current(instance: &Instance) -> Reading {
+    let usage = instance
+        .running_server()
+        .and_then(|lock| resources::sample_process(lock.pid));
+    Reading {
+        memory_bytes: usage.as_ref().map(|usage| usage.rss_bytes),
+        cpu_percent: usage.as_ref().map(|usage| usage.cpu_percent),
+        free_disk_bytes: resources::free_disk_space(&instance.work_dir),
+    }
+}
+
+// Estado entre verificações: cada limite alerta uma vez ao ser ultrapassado
+// e volta a alertar só depois de normalizar
+#[derive(Default)]
+struct Tracker {
+    memory_alerted: bool,
+    cpu_high_since: Option<Instant>,
+    cpu_alerted: bool,
+    disk_alerted: bool,
+}
+
+struct Watchdog {
+    instance: Instance,
+    tracker: Tracker,
+}
+
+impl Watchdog {
+    fn check(&mut self, config: &WatchdogConfig) {
+        let reading = read_current(&self.instance);
+
+        if let (Some(limit), Some(memory)) = (config.max_memory_gb, reading.memory_bytes) {
+            let high = memory as f64 > limit * GB;
+            if high && !self.tracker.memory_alerted {
+                self.tracker.memory_alerted = true;
+                let message = format!(
+                    "Memória do servidor em {} (limite {} GB)",
+                    format_bytes(memory),
+                    limit
+                );
+                self.trigger("memory", &message, config.memory_action, config);
+            } else if !high {
+                self.tracker.memory_alerted = false;
+                self.release_backups("memory");
+            }
+        } else if config.max_memory_gb.is_none() {
+            self.release_backups("memory");
+        }
+
+        if let (Some(limit), Some(cpu)) = (config.max_cpu_percent, reading.cpu_percent) {
+            if cpu > limit {
+                let since = *self.tracker.cpu_high_since.get_or_insert_with(Instant::now);
+                let sustained = since.elapsed() >= Duration::from_secs(config.cpu_minutes * 60);
+                if sustained && !self.tracker.cpu_alerted {
+                    self.tracker.cpu_alerted = true;
+                    let message = format!(
+                        "CPU do servidor acima de {:.0}% há {} minutos ({:.1}% agora)",
+                        limit, config.cpu_minutes, cpu
+                    );
+                    self.trigger("cpu", &message, config.cpu_action, config);
+                }
+            } else {
+                self.tracker.cpu_high_since = None;
+                self.tracker.cpu_alerted = false;
+                self.release_backups("cpu");
+            }
+        } else if config.max_cpu_percent.is_none() {
+            self.release_backups("cpu");
+        }
+
+        if let (Some(limit), Some(free)) = (config.min_free_disk_gb, reading.free_disk_bytes) {
+            let low = (free as f64) < limit * GB;
+            if low && !self.tracker.disk_alerted {
+                self.tracker.disk_alerted = true;
+                let message = format!(
+                    "Pouco espaço em disco: {} livres (mínimo {} GB)",
+                    format_bytes(free),
+                    limit
+                );
+                self.trigger("disk", &message, config.disk_action, config);
+            } else if !low {
+                self.tracker.disk_alerted = false;
+                self.release_backups("disk");
+            }
+        } else if config.min_free_disk_gb.is_none() {
+            self.release_backups("disk");
+        }
+    }
+
+    // Tira `resource` da pausa de backups quando ele normaliza (ou deixa de
+    // ter limite); a pausa só sai quando nenhum recurso a mantém. Também vale
+    // para pausas deixadas por uma execução anterior; as sem recurso
+    // registrado saem na primeira leitura normal.
+    fn release_backups(&self, resource: &str) {
+        let state = InstanceState::load(&self.instance.work_dir);
+        if state.backups_paused.is_none()
+            || (!state.backups_paused_by.is_empty() && !state.backups_paused_by.contains(resource))
+        {
+            return;
+        }
+        let mut released = false;
+        InstanceState::update(&self.instance.work_dir, |state| {
+            if state.backups_paused.is_none()
+                || (!state.backups_paused_by.is_empty()
+                    && !state.backups_paused_by.remove(resource))
+            {
+                return;
+            }
+            if state.backups_paused_by.is_empty() {
+                state.backups_paused = None;
+                released = true;
+            }
+        });
+        if released {
+            println!(
+                "[{}] {} normalizado; backups liberados",
+                self.instance.name,
+                resource_label(resource)
+            );
+        }
+    }
+
+    fn trigger(
+        &mut self,
+        resource: &str,
+        message: &str,
+        action: WatchdogAction,
+        config: &WatchdogConfig,
+    ) {
+        println!("[{}] Alerta do watchdog: {}", self.instance.name, message);
+        // Anunciado pelo arquivo de eventos para chegar também à API e ao menu
+        events::announce(
+            &self.instance.work_dir,
+            &ServerEvent::ResourceAlert {
+                resource: resource.to_string(),
+                message: message.to_string(),
+            },
+        );
+
+        match action {
+            WatchdogAction::Alert => {}
+            WatchdogAction::PauseBackups => {
+                InstanceState::update(&self.instance.work_dir, |state| {
+                    state.backups_paused = Some(message.to_string());
+                    state.backups_paused_by.insert(resource.to_string());
+                });
+            }
+            WatchdogAction::Restart => {
+                let result = daemon::restart_with_warnings(
+                    &self.instance,
+                    config.restart_warning_secs,
+                    "limite de recursos",
+                );
+                if let Err(e) = result {
+                    println!("[{}] Erro ao reiniciar: {}", self.instance.name, e);
+                }
+                // O processo novo começa com as medições zeradas
+                self.tracker = Tracker::default();
+            }
+        }
+    }
+}
+
+fn resource_label(resource: &str) -> &str {
+    match resource {
+        "memory" => "Uso de memória",
+        "cpu" => "Uso de CPU",
+        "disk" => "Espaço em disco",
+        other => other,
+    }
+}
+
+// Verifica os limites periodicamente enquanto o barramento da instância
+// existir. A configuração é relida a cada ciclo, então editar o
+// watchdog.json vale sem reiniciar nada.
+pub fn spawn_watchdog(events: &EventBus, instance: Instance) {
+    let receiver = events.subscribe();
+    let mut watchdog = Watchdog {
+        instance,
+        tracker: Tracker::default(),
+    };
+
+    thread::spawn(move || {
+        audit::with_actor("auto:watchdog", || loop {
+            let config = match WatchdogConfig::load(&watchdog.instance.work_dir) {
+                Ok(config) => config,
+                Err(e) => {
+                    println!("[{}] {}", watchdog.instance.name, e);
+                    None
+                }
+            };
+            let interval = config
+                .as_ref()
+                .map(|config| config.interval_secs.max(5))
+                .unwrap_or(60);
+            if let Some(config) = &config {
+                watchdog.check(config);
+            }
+
+            // Os eventos só servem para saber quando o servidor foi descartado
+            let deadline = Instant::now() + Duration::from_secs(interval);
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
+                }
+                match receiver.recv_timeout(remaining) {
+                    Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        })
+    });
+}
+
+pub fn print_status(instance: &Instance) -> Result<(), String> {
+    println!("\n=== Watchdog ({}) ===", instance.name);
+    let Some(config) = WatchdogConfig::load(&instance.work_dir)? else {
+        println!(
+            "Desligado: crie watchdog.json em {}",
+            instance.work_dir.display()
+        );
+        return Ok(());
+    };
+
+    let reading = read_current(instance);
+    let measured = |value: Option<String>| value.unwrap_or_else(|| "indisponível".to_string());
+    if let Some(limit) = config.max_memory_gb {
+        println!(
+            "Memória: {} (limite {} GB, ação {})",
+            measured(reading.memory_bytes.map(format_bytes)),
+            limit,
+            config.memory_action
+        );
+    }
+    if let Some(limit) = config.max_cpu_percent {
+        println!(
+            "CPU: {} (limite {:.0}% por {} min, ação {})",
+            measured(reading.cpu_percent.map(|cpu| format!("{:.1}%", cpu))),
+            limit,
+            config.cpu_minutes,
+            config.cpu_action
+        );
+    }
+    if let Some(limit) = config.min_free_disk_gb {
+        println!(
+            "Disco livre: {} (mínimo {} GB, ação {})",
+            measured(reading.free_disk_bytes.map(format_bytes)),
+            limit,
+            config.disk_action
+        );
+    }
+    if let Some(reason) = InstanceState::load(&instance.work_dir).backups_paused {
+        println!("Backups pausados: {}", reason);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use std::collections::BTreeSet;
+    use std::fs;
+
+    fn watchdog(name: &str) -> Watchdog {
+        Watchdog {
+            instance: Instance {
+                name: name.to_string(),
+                work_dir: test_util::temp_dir(name),
+            },
+            tracker: Tracker::default(),
+        }
+    }
+
+    #[test]
+    fn disk_pause_is_released_when_disk_recovers() {
+        let mut watchdog = watchdog("watchdog-disk");
+        let work_dir = watchdog.instance.work_dir.clone();
+        let mut config = WatchdogConfig {
+            min_free_disk_gb: Some(1e12),
+            disk_action: WatchdogAction::PauseBackups,
+            ..WatchdogConfig::default()
+        };
+
+        watchdog.check(&config);
+        let state = InstanceState::load(&work_dir);
+        assert!(state.backups_paused.is_some());
+        assert!(state.backups_paused_by.contains("disk"));
+
+        config.min_free_disk_gb = Some(0.0);
+        watchdog.check(&config);
+        let state = InstanceState::load(&work_dir);
+        assert!(state.backups_paused.is_none());
+        assert!(state.backups_paused_by.is_empty());
+        let _ = fs::remove_dir_all(&work_dir);
+    }
+
+    #[test]
+    fn pause_is_kept_until_its_resource_recovers() {
+        let mut watchdog = watchdog("watchdog-memory");
+        let work_dir = watchdog.instance.work_dir.clone();
+        InstanceState::update(&work_dir, |state| {
+            state.backups_paused = Some("memória alta".to_string());
+            state.backups_paused_by.insert("memory".to_string());
+        });
+        // Sem servidor não há leitura de memória, e o disco está normal
+        let mut config = WatchdogConfig {
+            max_memory_gb: Some(1.0),
+            min_free_disk_gb: Some(0.0),
+            ..WatchdogConfig::default()
+        };
+
+        watchdog.check(&config);
+        assert!(InstanceState::load(&work_dir)
+            .backups_paused_by
+            .contains("memory"));
+
+        // Sem limite de memória a pausa deixa de fazer sentido
+        config.max_memory_gb = None;
+        watchdog.check(&config);
+        assert!(InstanceState::load(&work_dir).backups_paused.is_none());
+        let _ = fs::remove_dir_all(&work_dir);
+    }
+
+    #[test]
+    fn pause_waits_for_every_resource() {
+        let mut watchdog = watchdog("watchdog-both");
+        let work_dir = watchdog.instance.work_dir.clone();
+        InstanceState::update(&work_dir, |state| {
+            state.backups_paused = Some("memória alta".to_string());
+            state.backups_paused_by.insert("memory".to_string());
+        });
+        let mut config = WatchdogConfig {
+            max_memory_gb: Some(1.0),
+            min_free_disk_gb: Some(1e12),
+            disk_action: WatchdogAction::PauseBackups,
+            ..WatchdogConfig::default()
+        };
+        watchdog.check(&config);
+        assert_eq!(InstanceState::load(&work_dir).backups_paused_by.len(), 2);
+
+        // O disco normaliza, mas a memória ainda segura a pausa
+        config.min_free_disk_gb = Some(0.0);
+        watchdog.check(&config);
+        let state = InstanceState::load(&work_dir);
+        assert!(state.backups_paused.is_some());
+        assert_eq!(
+            state.backups_paused_by,
+            BTreeSet::from(["memory".to_string()])
+        );
+
+        config.max_memory_gb = None;
+        watchdog.check(&config);
+        assert!(InstanceState::load(&work_dir).backups_paused.is_none());
+        let _ = fs::remove_dir_all(&work_dir);
+    }
+}
@@ -0,0 +1,501 @@
+use crate::audit;
+use crate::events::{EventBus, ServerEvent};
+use crate::instances::Instance;
+use crate::manager_config::ManagerConfig;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+// Eventos que podem ser enviados aos webhooks
+pub const EVENTS: &[&str] = &[
+    "server_started",
+    "server_stopped",
+    "server_crashed",
+    "player_joined",
+    "player_left",
+    "backup_completed",
+    "backup_failed",
+    "update_available",
+    "update_applied",
+    "resource_alert",
+];
+
+// Envios ainda em andamento; a CLI espera por eles antes de sair
+static PENDING: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+
+// Executor e cliente HTTP compartilhados por todos os envios, que assim
+// reaproveitam as conexões
+static HTTP: OnceLock<Result<(Runtime, reqwest::Client), String>> = OnceLock::new();
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_backoff_secs() -> u64 {
+    2
+}
+
+// Webhook de saída, guardado em manager.json. Exemplo:
+// { "name": "discord", "url": "https://discord.com/api/webhooks/...",
+//   "events": ["player_joined", "server_crashed"],
+//   "templates": { "player_joined": "{\"content\": \"{name} entrou em {instance}\"}" } }
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Webhook {
+    pub name: String,
+    pub url: String,
+    // Eventos enviados; vazio envia todos
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>,
+    // Instâncias observadas; vazio observa todas
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub instances: Vec<String>,
+    // Corpo enviado por evento, com {campo} trocado pelos dados do evento.
+    // Eventos sem template usam o JSON padrão.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub templates: BTreeMap<String, String>,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    // Espera antes da primeira nova tentativa; dobra a cada falha
+    #[serde(default = "default_backoff_secs")]
+    pub backoff_secs: u64,
+}
+
+impl Webhook {
+    fn matches(&self, instance: &str, event: &str) -> bool {
+        (self.events.is_empty() || self.events.iter().any(|e| e == event))
+            && (self.instances.is_empty() || self.instances.iter().any(|i| i == instance))
+    }
+
+    // Monta o corpo da requisição. Os valores entram escapados como conteúdo
+    // de string JSON, então o template deve envolvê-los em aspas.
+    fn render(&self, instance: &str, event: &ServerEvent) -> String {
+        let (name, data) = split_event(event);
+        let timestamp = Utc::now().to_rfc3339();
+        let Some(template) = self.templates.get(&name) else {
+            return json!({
+                "event": name,
+                "instance": instance,
+                "timestamp": timestamp,
+                "data": data,
+            })
+            .to_string();
+        };
+
+        let mut body = template
+            .replace("{event}", &escape(&name))
+            .replace("{instance}", &escape(instance))
+            .replace("{timestamp}", &escape(&timestamp));
+        for (key, value) in &data {
+            let value = match value {
+                Value::String(text) => escape(text),
+                other => other.to_string(),
+            };
+            body = body.replace(&format!("{{{}}}", key), &value);
+        }
+        body
+    }
+}
+
+// Separa o tipo do evento dos seus campos
+fn split_event(event: &ServerEvent) -> (String, Map<String, Value>) {
+    let mut data = match event.to_json() {
+        Value::Object(data) => data,
+        _ => Map::new(),
+    };
+    let name = match data.remove("type") {
+        Some(Value::String(name)) => name,
+        _ => String::new(),
+    };
+    (name, data)
+}
+
+fn escape(text: &str) -> String {
+    let quoted = Value::String(text.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+async fn deliver(client: &reqwest::Client, hook: &Webhook, body: &str) -> Result<(), String> {
+    let mut attempt = 0;
+    loop {
+        let response = client
+            .post(&hook.url)
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .send()
+            .await;
+        // Erros de rede, 429 e 5xx são temporários; os demais não mudam
+        // tentando de novo
+        let (retry, error) = match response {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => {
+                let status = response.status();
+                (
+                    status.is_server_error() || status.as_u16() == 429,
+                    format!("HTTP {}", status),
+                )
+            }
+            Err(e) => (true, e.to_string()),
+        };
+        if !retry || attempt >= hook.max_retries {
+            return Err(format!("{} (após {} tentativa(s))", error, attempt + 1));
+        }
+        let delay = hook.backoff_secs.saturating_mul(1 << attempt.min(10));
+        tokio::time::sleep(Duration::from_secs(delay)).await;
+        attempt += 1;
+    }
+}
+
+fn http() -> Result<&'static (Runtime, reqwest::Client), String> {
+    HTTP.get_or_init(|| {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Erro ao iniciar envio: {}", e))?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| format!("Erro ao criar cliente HTTP: {}", e))?;
+        Ok((runtime, client))
+    })
+    .as_ref()
+    .map_err(Clone::clone)
+}
+
+// Envia o evento a um webhook, bloqueando até o fim das tentativas
+fn send(hook: &Webhook, instance: &str, event: &ServerEvent) -> Result<(), String> {
+    let body = hook.render(instance, event);
+    let (runtime, client) = http()?;
+    runtime.block_on(deliver(client, hook, &body))
+}
+
+// Envia o evento, em segundo plano, a todos os webhooks que o aceitam
+fn dispatch(instance: &str, event: &ServerEvent) {
+    let hooks = match ManagerConfig::load() {
+        Ok(config) => config.webhooks,
+        Err(e) => {
+            println!("Erro ao carregar webhooks: {}", e);
+            return;
+        }
+    };
+    let (name, _) = split_event(event);
+
+    for hook in hooks
+        .into_iter()
+        .filter(|hook| hook.matches(instance, &name))
+    {
+        let instance = instance.to_string();
+        let event = event.clone();
+        let name = name.clone();
+        let handle = thread::spawn(move || {
+            if let Err(e) = send(&hook, &instance, &event) {
+                println!("Webhook {} falhou ao enviar {}: {}", hook.name, name, e);
+            }
+        });
+        let mut pending = PENDING.lock().unwrap();
+        pending.retain(|handle| !handle.is_finished());
+        pending.push(handle);
+    }
+}
+
+// Avisa os webhooks sobre um evento da instância em `work_dir`
+pub fn notify(work_dir: &Path, event: ServerEvent) {
+    dispatch(&audit::instance_name(work_dir), &event);
+}
+
+pub fn wait_for_deliveries() {
+    let handles = std::mem::take(&mut *PENDING.lock().unwrap());
+    for handle in handles {
+        let _ = handle.join();
+    }
+}
+
+// Repassa aos webhooks os eventos do barramento da instância. Backups e
+// atualizações são avisados por quem os executa, já que podem acontecer em
+// outro processo.
+pub fn spawn_notifier(events: &EventBus, instance: Instance) {
+    let receiver = events.subscribe();
+
+    thread::spawn(move || {
+        for event in receiver {
+            if matches!(
+                event,
+                ServerEvent::ServerStarted
+                    | ServerEvent::ServerStopped
+                    | ServerEvent::ServerCrashed
+                    | ServerEvent::PlayerConnected { .. }
+                    | ServerEvent::PlayerDisconnected { .. }
+                    | ServerEvent::ResourceAlert { .. }
+            ) {
+                dispatch(&instance.name, &event);
+            }
+        }
+    });
+}
+
+fn unknown_event(event: &str) -> String {
+    format!("Evento desconhecido: {} (use {})", event, EVENTS.join(", "))
+}
+
+fn check_events(events: &[String]) -> Result<(), String> {
+    match events
+        .iter()
+        .find(|event| !EVENTS.contains(&event.as_str()))
+    {
+        Some(event) => Err(unknown_event(event)),
+        None => Ok(()),
+    }
+}
+
+pub fn add(name: &str, url: &str, events: Vec<String>) -> Result<(), String> {
+    check_events(&events)?;
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err("A URL do webhook deve começar com http:// ou https://".to_string());
+    }
+    let mut config = ManagerConfig::load()?;
+    if config.webhooks.iter().any(|hook| hook.name == name) {
+        return Err(format!("Já existe um webhook chamado {}", name));
+    }
+    config.webhooks.push(Webhook {
+        name: name.to_string(),
+        url: url.to_string(),
+        events,
+        instances: Vec::new(),
+        templates: BTreeMap::new(),
+        max_retries: default_max_retries(),
+        backoff_secs: default_backoff_secs(),
+    });
+    config.save()
+}
+
+pub fn remove(name: &str) -> Result<bool, String> {
+    let mut config = ManagerConfig::load()?;
+    let before = config.webhooks.len();
+    config.webhooks.retain(|hook| hook.name != name);
+    if config.webhooks.len() == before {
+        return Ok(false);
+    }
+    config.save()?;
+    Ok(true)
+}
+
+pub fn print_list() -> Result<(), String> {
+    let hooks = ManagerConfig::load()?.webhooks;
+    if hooks.is_empty() {
+        println!("Nenhum webhook configurado.");
+        return Ok(());
+    }
+    let all = |list: &[String], empty: &str| {
+        if list.is_empty() {
+            empty.to_string()
+        } else {
+            list.join(", ")
+        }
+    };
+    for hook in hooks {
+        println!("{}  {}", hook.name, hook.url);
+        println!("  Eventos: {}", all(&hook.events, "todos"));
+        println!("  Instâncias: {}", all(&hook.instances, "todas"));
+        if !hook.templates.is_empty() {
+            let templates: Vec<&str> = hook.templates.keys().map(|key| key.as_str()).collect();
+            println!("  Templates: {}", templates.join(", "));
+        }
+        println!(
+            "  Tentativas: {} (espera inicial de {}s)",
+            hook.max_retries + 1,
+            hook.backoff_secs
+        );
+    }
+    Ok(())
+}
+
+// Evento fictício usado por `webhook test`
+fn sample_event(name: &str) -> Result<ServerEvent, String> {
+    let text = |value: &str| value.to_string();
+    Ok(match name {
+        "server_started" => ServerEvent::ServerStarted,
+        "server_stopped" => ServerEvent::ServerStopped,
+        "server_crashed" => ServerEvent::ServerCrashed,
+        "player_joined" => ServerEvent::PlayerConnected {
+            name: text("Steve"),
+            xuid: text("2535400000000000"),
+        },
+        "player_left" => ServerEvent::PlayerDisconnected {
+            name: text("Steve"),
+            xuid: text("2535400000000000"),
+        },
+        "backup_completed" => ServerEvent::BackupCompleted {
+            name: text("worlds-20240101-120000-000.zip"),
+        },
+        "backup_failed" => ServerEvent::BackupFailed {
+            error: text("Teste de webhook"),
+        },
+        "update_available" => ServerEvent::UpdateAvailable {
+            version: text("1.21.51.02"),
+        },
+        "update_applied" => ServerEvent::UpdateApplied {
+            version: text("1.21.51.02"),
+        },
+        "resource_alert" => ServerEvent::ResourceAlert {
+            resource: text("memory"),
+            message: text("Teste de webhook"),
+        },
+        other => return Err(unknown_event(other)),
+    })
+}
+
+// Envia um evento de teste ignorando os filtros do webhook
+pub fn test(name: &str, event: &str, instance: &Instance) -> Result<(), String> {
+    let hook = ManagerConfig::load()?
+        .webhooks
+        .into_iter()
+        .find(|hook| hook.name == name)
+        .ok_or_else(|| format!("Webhook {} não encontrado", name))?;
+    send(&hook, &instance.name, &sample_event(event)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn hook(url: &str) -> Webhook {
+        Webhook {
+            name: "teste".to_string(),
+            url: url.to_string(),
+            events: Vec::new(),
+            instances: Vec::new(),
+            templates: BTreeMap::new(),
+            max_retries: 2,
+            backoff_secs: 0,
+        }
+    }
+
+    fn joined(name: &str) -> ServerEvent {
+        ServerEvent::PlayerConnected {
+            name: name.to_string(),
+            xuid: "2535400000000000".to_string(),
+        }
+    }
+
+    #[test]
+    fn renders_default_body() {
+        let body: Value =
+            serde_json::from_str(&hook("").render("lobby", &joined("Steve"))).unwrap();
+        assert_eq!(body["event"], "player_joined");
+        assert_eq!(body["instance"], "lobby");
+        assert_eq!(body["data"]["name"], "Steve");
+        assert_eq!(body["data"]["xuid"], "2535400000000000");
+        assert!(body["timestamp"].is_string());
+    }
+
+    #[test]
+    fn template_values_are_escaped() {
+        let mut hook = hook("");
+        hook.templates.insert(
+            "player_joined".to_string(),
+            r#"{"content": "{name} entrou em {instance} ({event}) {desconhecido}"}"#.to_string(),
+        );
+        let name = "Ste\"ve\n\\";
+        let body: Value = serde_json::from_str(&hook.render("lo\"bby", &joined(name))).unwrap();
+        assert_eq!(
+            body["content"],
+            format!(
+                "{} entrou em lo\"bby (player_joined) {{desconhecido}}",
+                name
+            )
+        );
+
+        // Eventos sem template continuam com o corpo padrão
+        let body: Value =
+            serde_json::from_str(&hook.render("lobby", &ServerEvent::ServerStarted)).unwrap();
+        assert_eq!(body["event"], "server_started");
+    }
+
+    #[test]
+    fn filters_by_event_and_instance() {
+        let mut hook = hook("");
+        assert!(hook.matches("lobby", "server_crashed"));
+        hook.events = vec!["server_crashed".to_string()];
+        hook.instances = vec!["lobby".to_string()];
+        assert!(hook.matches("lobby", "server_crashed"));
+        assert!(!hook.matches("lobby", "player_joined"));
+        assert!(!hook.matches("survival", "server_crashed"));
+    }
+
+    // Servidor HTTP local que responde cada requisição com o próximo status
+    // da lista (o último se repete) e conta as requisições recebidas
+    fn serve(statuses: &'static [u16]) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let count = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&count);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while let Ok(read) = stream.read(&mut buffer) {
+                    request.extend_from_slice(&buffer[..read]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+                        let length = headers
+                            .lines()
+                            .find_map(|line| {
+                                line.to_ascii_lowercase()
+                                    .strip_prefix("content-length:")
+                                    .map(|value| value.trim().parse().unwrap_or(0))
+                            })
+                            .unwrap_or(0);
+                        if body.len() >= length {
+                            break;
+                        }
+                    }
+                    if read == 0 {
+                        break;
+                    }
+                }
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                let status = statuses[n.min(statuses.len() - 1)];
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        (url, count)
+    }
+
+    #[test]
+    fn retries_temporary_failures() {
+        let (url, count) = serve(&[503, 429, 204]);
+        assert!(send(&hook(&url), "lobby", &joined("Steve")).is_ok());
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn gives_up_after_max_retries() {
+        let (url, count) = serve(&[500]);
+        let error = send(&hook(&url), "lobby", &joined("Steve")).unwrap_err();
+        assert!(error.contains("HTTP 500"), "{}", error);
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let (url, count) = serve(&[404]);
+        let error = send(&hook(&url), "lobby", &joined("Steve")).unwrap_err();
+        assert!(error.contains("HTTP 404"), "{}", error);
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,330 @@
+use crate::audit;
+use crate::auth::{ApiToken, Scope};
+use crate::events::{console_log_path, read_console_tail, EventBus};
+use crate::instances::Instance;
+use base64::Engine;
+use hyper::upgrade::Upgraded;
+use hyper::{Body, Request, Response, StatusCode};
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf};
+use tokio::sync::mpsc;
+
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const DEFAULT_BACKLOG: usize = 100;
+const MAX_FRAME_LEN: u64 = 64 * 1024;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+pub fn is_upgrade_request(request: &Request<Body>) -> bool {
+    request
+        .headers()
+        .get("upgrade")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("websocket"))
+}
+
+pub fn query_param(request: &Request<Body>, key: &str) -> Option<String> {
+    request.uri().query().and_then(|query| {
+        query.split('&').find_map(|pair| {
+            pair.strip_prefix(key)
+                .and_then(|rest| rest.strip_prefix('='))
+                .map(|value| value.to_string())
+        })
+    })
+}
+
+// Completa o handshake do WebSocket (RFC 6455) e passa a conexão para
+// `serve` assim que o hyper liberar o socket
+pub fn accept(
+    mut request: Request<Body>,
+    instance: Instance,
+    events: EventBus,
+    token: ApiToken,
+) -> Result<Response<Body>, String> {
+    let key = request
+        .headers()
+        .get("sec-websocket-key")
+        .and_then(|value| value.to_str().ok())
+        .ok_or("Cabeçalho Sec-WebSocket-Key ausente")?;
+    let mut hasher = Sha1::new();
+    hasher.update(key.as_bytes());
+    hasher.update(WEBSOCKET_GUID.as_bytes());
+    let accept_key = base64::engine::general_purpose::STANDARD.encode(hasher.finalize());
+
+    let backlog = query_param(&request, "backlog")
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_BACKLOG);
+
+    let on_upgrade = hyper::upgrade::on(&mut request);
+    tokio::spawn(async move {
+        match on_upgrade.await {
+            Ok(upgraded) => serve(upgraded, instance, events, token, backlog).await,
+            Err(e) => println!("Erro ao abrir WebSocket: {}", e),
+        }
+    });
+
+    Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header("Upgrade", "websocket")
+        .header("Connection", "Upgrade")
+        .header("Sec-WebSocket-Accept", accept_key)
+        .body(Body::empty())
+        .map_err(|e| e.to_string())
+}
+
+async fn serve(
+    upgraded: Upgraded,
+    instance: Instance,
+    events: EventBus,
+    token: ApiToken,
+    backlog: usize,
+) {
+    let (reader, mut writer) = tokio::io::split(upgraded);
+
+    // Histórico recente do console antes dos eventos ao vivo
+    let log_path = console_log_path(&instance.work_dir);
+    let history = tokio::task::spawn_blocking(move || read_console_tail(&log_path, backlog))
+        .await
+        .unwrap_or_default();
+    for line in history {
+        let message = json!({ "type": "console", "line": line, "backlog": true });
+        if write_frame(&mut writer, OPCODE_TEXT, message.to_string().as_bytes())
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+
+    // O barramento usa canais bloqueantes; uma thread repassa os eventos para
+    // o canal assíncrono da conexão até o cliente desconectar
+    let (sender, mut receiver) = mpsc::channel::<Value>(256);
+    let subscription = events.subscribe();
+    let event_sender = sender.clone();
+    tokio::task::spawn_blocking(move || loop {
+        match subscription.recv_timeout(Duration::from_secs(1)) {
+            Ok(event) => {
+                if event_sender.blocking_send(event.to_json()).is_err() {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) if event_sender.is_closed() => break,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    });
+
+    let (control_sender, mut control_receiver) = mpsc::channel::<(u8, Vec<u8>)>(16);
+    let read_task = tokio::spawn(read_loop(reader, instance, token, sender, control_sender));
+
+    loop {
+        tokio::select! {
+            Some(message) = receiver.recv() => {
+                if write_frame(&mut writer, OPCODE_TEXT, message.to_string().as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+            Some((opcode, payload)) = control_receiver.recv() => {
+                let closing = opcode == OPCODE_CLOSE;
+                if write_frame(&mut writer, opcode, &payload).await.is_err() || closing {
+                    break;
+                }
+            }
+            else => break,
+        }
+    }
+
+    read_task.abort();
+    let _ = writer.shutdown().await;
+}
+
+// Lê os frames do cliente: mensagens de texto são comandos para o servidor,
+// aceitos apenas de tokens com escopo admin
+async fn read_loop(
+    mut reader: ReadHalf<Upgraded>,
+    instance: Instance,
+    token: ApiToken,
+    messages: mpsc::Sender<Value>,
+    control: mpsc::Sender<(u8, Vec<u8>)>,
+) {
+    let mut fragments: Vec<u8> = Vec::new();
+    loop {
+        let Ok((fin, opcode, payload)) = read_frame(&mut reader).await else {
+            let _ = control.send((OPCODE_CLOSE, Vec::new())).await;
+            return;
+        };
+
+        match opcode {
+            OPCODE_TEXT | OPCODE_CONTINUATION => {
+                fragments.extend_from_slice(&payload);
+                if fragments.len() as u64 > MAX_FRAME_LEN {
+                    let _ = control.send((OPCODE_CLOSE, Vec::new())).await;
+                    return;
+                }
+                if !fin {
+                    continue;
+                }
+                let command = String::from_utf8_lossy(&fragments).trim().to_string();
+                fragments.clear();
+                if command.is_empty() {
+                    continue;
+                }
+
+                if !token.allows(Scope::Admin) {
+                    let message = "Enviar comandos requer um token com escopo admin";
+                    let _ = messages
+                        .send(json!({ "type": "error", "message": message }))
+                        .await;
+                    continue;
+                }
+
+                let admin = instance.admin();
+                let actor = format!("api:{}", token.name);
+                let result = tokio::task::spawn_blocking(move || {
+                    audit::with_actor(&actor, || admin.run_raw(&command))
+                })
+                .await;
+                if let Ok(Err(e)) = result {
+                    let _ = messages
+                        .send(json!({ "type": "error", "message": e }))
+                        .await;
+                }
+            }
+            OPCODE_PING => {
+                let _ = control.send((OPCODE_PONG, payload)).await;
+            }
+            OPCODE_CLOSE => {
+                let _ = control.send((OPCODE_CLOSE, payload)).await;
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+async fn read_frame(reader: &mut (impl AsyncRead + Unpin)) -> Result<(bool, u8, Vec<u8>), String> {
+    let mut header = [0u8; 2];
+    reader
+        .read_exact(&mut header)
+        .await
+        .map_err(|e| e.to_string())?;
+    let fin = header[0] & 0x80 != 0;
+    let opcode = header[0] & 0x0F;
+    let masked = header[1] & 0x80 != 0;
+
+    let len = match header[1] & 0x7F {
+        126 => reader.read_u16().await.map_err(|e| e.to_string())? as u64,
+        127 => reader.read_u64().await.map_err(|e| e.to_string())?,
+        len => len as u64,
+    };
+    if len > MAX_FRAME_LEN {
+        return Err("Frame grande demais".to_string());
+    }
+
+    let mut mask = [0u8; 4];
+    if masked {
+        reader
+            .read_exact(&mut mask)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let mut payload = vec![0u8; len as usize];
+    reader
+        .read_exact(&mut payload)
+        .await
+        .map_err(|e| e.to_string())?;
+    if masked {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+    Ok((fin, opcode, payload))
+}
+
+async fn write_frame(
+    writer: &mut (impl AsyncWrite + Unpin),
+    opcode: u8,
+    payload: &[u8],
+) -> Result<(), String> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame).await.map_err(|e| e.to_string())?;
+    writer.flush().await.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(bytes: &[u8]) -> Result<(bool, u8, Vec<u8>), String> {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(read_frame(&mut &bytes[..]))
+    }
+
+    #[test]
+    fn masked_frame_is_decoded() {
+        let mask = [0x12, 0x34, 0x56, 0x78];
+        let mut frame = vec![0x81, 0x80 | 4];
+        frame.extend_from_slice(&mask);
+        frame.extend(
+            b"list"
+                .iter()
+                .enumerate()
+                .map(|(i, byte)| byte ^ mask[i % 4]),
+        );
+        assert_eq!(read(&frame), Ok((true, OPCODE_TEXT, b"list".to_vec())));
+
+        // Fragmento sem FIN, com tamanho em 16 bits
+        let mut frame = vec![0x01, 126, 0x01, 0x00];
+        frame.extend(vec![b'a'; 256]);
+        assert_eq!(read(&frame), Ok((false, OPCODE_TEXT, vec![b'a'; 256])));
+        assert!(read(&[0x81, 5, b'o', b'i']).is_err());
+    }
+
+    #[test]
+    fn oversized_frame_is_rejected() {
+        let mut frame = vec![0x81, 127];
+        frame.extend_from_slice(&(MAX_FRAME_LEN + 1).to_be_bytes());
+        assert_eq!(read(&frame), Err("Frame grande demais".to_string()));
+
+        let mut frame = vec![0x81, 127];
+        frame.extend_from_slice(&MAX_FRAME_LEN.to_be_bytes());
+        frame.extend(vec![b'a'; MAX_FRAME_LEN as usize]);
+        assert!(read(&frame).is_ok());
+    }
+
+    #[test]
+    fn written_frames_read_back() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        for len in [0, 125, 126, 70_000] {
+            let payload = vec![b'x'; len];
+            let mut frame = Vec::new();
+            runtime
+                .block_on(write_frame(&mut frame, OPCODE_TEXT, &payload))
+                .unwrap();
+            if len <= MAX_FRAME_LEN as usize {
+                assert_eq!(read(&frame), Ok((true, OPCODE_TEXT, payload)));
+            } else {
+                assert!(read(&frame).is_err());
+            }
+        }
+    }
+}